@@ -0,0 +1,212 @@
+use csv::{ReaderBuilder, WriterBuilder};
+
+use super::xlsx::{extract_tables_from_markdown, DEFAULT_MAX_ROWS_PER_SHEET};
+use super::{escape_table_cell, ConversionError};
+
+/// Delimiters tried when none is given, in order of preference on a tie
+const DELIMITERS: [u8; 3] = [b',', b';', b'\t'];
+
+/// Convert a CSV file to a single GFM table whose header is the first row.
+///
+/// The delimiter is detected from the first record when `delimiter` is
+/// `None`. Quoted fields may contain the delimiter, `""` and line breaks;
+/// rows of different lengths are padded. Data rows are capped like sheets
+/// in spreadsheet imports, with the same note when rows were cut.
+pub fn csv_to_markdown(path: &str, delimiter: Option<char>) -> Result<String, ConversionError> {
+    let bytes =
+        std::fs::read(path).map_err(|e| ConversionError(format!("Failed to read file: {}", e)))?;
+    // Excel saves CSV in the system code page unless told otherwise
+    let (text, _) = crate::files::decode_text(bytes);
+    // "CSV UTF-8" from Excel starts with a byte order mark
+    let text = text.trim_start_matches('\u{feff}');
+    csv_text_to_markdown(text, delimiter, Some(DEFAULT_MAX_ROWS_PER_SHEET))
+}
+
+fn csv_text_to_markdown(
+    text: &str,
+    delimiter: Option<char>,
+    max_rows: Option<usize>,
+) -> Result<String, ConversionError> {
+    let delimiter = match delimiter {
+        Some(c) if c.is_ascii() => c as u8,
+        Some(c) => {
+            return Err(ConversionError(format!(
+                "Unsupported CSV delimiter: '{}'",
+                c
+            )))
+        }
+        None => detect_delimiter(text),
+    };
+    let mut reader = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut data_rows_available = 0;
+    for record in reader.records() {
+        let record = record.map_err(|e| ConversionError(format!("Failed to parse CSV: {}", e)))?;
+        if !rows.is_empty() {
+            data_rows_available += 1;
+            if max_rows.is_some_and(|max| data_rows_available > max) {
+                continue;
+            }
+        }
+        rows.push(record.iter().map(escape_table_cell).collect());
+    }
+
+    let col_count = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if col_count == 0 {
+        return Ok("*(empty file)*\n".to_string());
+    }
+
+    let mut output = String::new();
+    for (i, row) in rows.iter().enumerate() {
+        output.push('|');
+        for col in 0..col_count {
+            output.push_str(&format!(" {} |", row.get(col).map_or("", String::as_str)));
+        }
+        output.push('\n');
+
+        if i == 0 {
+            output.push('|');
+            for _ in 0..col_count {
+                output.push_str(" --- |");
+            }
+            output.push('\n');
+        }
+    }
+
+    let data_rows_shown = rows.len() - 1;
+    if data_rows_shown < data_rows_available {
+        output.push_str(&format!(
+            "\n> **Note**: {} rows were omitted (showing first {} data rows).\n",
+            data_rows_available - data_rows_shown,
+            data_rows_shown
+        ));
+    }
+    Ok(output)
+}
+
+/// Write the table at `table_index` (0-based) in `markdown` to a CSV file,
+/// header first. Cells with commas, quotes or line breaks are quoted.
+pub fn markdown_to_csv(
+    markdown: &str,
+    path: &str,
+    table_index: usize,
+) -> Result<(), ConversionError> {
+    let tables = extract_tables_from_markdown(markdown);
+    let table = tables.get(table_index).ok_or_else(|| {
+        ConversionError(format!(
+            "Table {} not found: the document has {} table(s)",
+            table_index + 1,
+            tables.len()
+        ))
+    })?;
+
+    let write_error = |e: csv::Error| ConversionError(format!("Failed to write CSV: {}", e));
+    let mut writer = WriterBuilder::new()
+        .flexible(true)
+        .from_path(path)
+        .map_err(write_error)?;
+    writer.write_record(&table.header).map_err(write_error)?;
+    for row in &table.rows {
+        writer.write_record(row).map_err(write_error)?;
+    }
+    writer
+        .flush()
+        .map_err(|e| ConversionError(format!("Failed to write CSV: {}", e)))
+}
+
+/// The candidate delimiter that occurs most often outside quotes in the
+/// first record; comma when none does
+fn detect_delimiter(text: &str) -> u8 {
+    let mut counts = [0usize; DELIMITERS.len()];
+    let mut in_quotes = false;
+    for byte in text.bytes() {
+        match byte {
+            b'"' => in_quotes = !in_quotes,
+            b'\n' | b'\r' if !in_quotes => break,
+            _ if !in_quotes => {
+                if let Some(i) = DELIMITERS.iter().position(|&d| d == byte) {
+                    counts[i] += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    // `max_by_key` keeps the last maximum, so walk the candidates backwards
+    (0..DELIMITERS.len())
+        .rev()
+        .max_by_key(|&i| counts[i])
+        .filter(|&i| counts[i] > 0)
+        .map_or(b',', |i| DELIMITERS[i])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_to_markdown_semicolon_delimited() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("semicolon.csv");
+        std::fs::write(&path, "Name;Price;Note\nApple;1,50;fresh\nPear;2,00\n").unwrap();
+        let md = csv_to_markdown(path.to_str().unwrap(), None).unwrap();
+
+        assert_eq!(
+            md,
+            "| Name | Price | Note |\n| --- | --- | --- |\n| Apple | 1,50 | fresh |\n| Pear | 2,00 |  |\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_to_markdown_quoted_fields() {
+        let csv = "id,comment\n1,\"a, b and \"\"c\"\"\"\n2,\"first line\nsecond | line\"\n";
+        let md = csv_text_to_markdown(csv, None, None).unwrap();
+        assert_eq!(
+            md,
+            "| id | comment |\n| --- | --- |\n| 1 | a, b and \"c\" |\n| 2 | first line<br>second \\| line |\n"
+        );
+
+        // Semicolons inside quotes don't outvote the real delimiter
+        assert_eq!(detect_delimiter("\"x;y;z\",b\n"), b',');
+        assert_eq!(detect_delimiter("a\tb\tc\n"), b'\t');
+        assert_eq!(detect_delimiter("single column\n"), b',');
+    }
+
+    #[test]
+    fn test_csv_to_markdown_explicit_delimiter_and_row_cap() {
+        let md = csv_text_to_markdown("a|b\n1|2\n3|4\n5|6\n", Some('|'), Some(2)).unwrap();
+        assert!(md.starts_with("| a | b |\n| --- | --- |\n| 1 | 2 |\n| 3 | 4 |\n\n"));
+        assert!(md.contains("1 rows were omitted (showing first 2 data rows)"));
+        assert_eq!(
+            csv_text_to_markdown("", None, None).unwrap(),
+            "*(empty file)*\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown_to_csv_round_trip() {
+        let md = "| Ignored |\n| --- |\n| x |\n\n| City | Note |\n| --- | --- |\n| Paris | big, old |\n| Oslo | say \"hi\"<br>twice |\n";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.csv");
+        let path_str = path.to_str().unwrap();
+
+        markdown_to_csv(md, path_str, 1).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        let imported = csv_to_markdown(path_str, None).unwrap();
+
+        assert_eq!(
+            written,
+            "City,Note\nParis,\"big, old\"\nOslo,\"say \"\"hi\"\"\ntwice\"\n"
+        );
+        assert_eq!(imported, "| City | Note |\n| --- | --- |\n| Paris | big, old |\n| Oslo | say \"hi\"<br>twice |\n");
+
+        let missing = dir.path().join("missing.csv");
+        let err = markdown_to_csv(md, missing.to_str().unwrap(), 2).unwrap_err();
+        assert_eq!(err.0, "Table 3 not found: the document has 2 table(s)");
+        assert!(!missing.exists());
+    }
+}
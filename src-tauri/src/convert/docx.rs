@@ -2,12 +2,73 @@ use std::fs::File;
 use std::io::BufWriter;
 
 use docx_rs::{
-    read_docx, DocumentChild, Docx, Paragraph, ParagraphChild, Run, RunChild, Table,
-    TableCell, TableCellContent, TableChild, TableRow, TableRowChild,
+    read_docx, AbstractNumbering, AlignmentType, BreakType, BuildXML, DocumentChild, Docx,
+    Hyperlink, HyperlinkData, HyperlinkType, IndentLevel, Level, LevelJc, LevelOverride, LevelText,
+    NumberFormat, Numbering, NumberingId, Numberings, Paragraph, ParagraphChild, Run, RunChild,
+    RunFonts, Shading, SpecialIndentType, Start, Style, StyleType, Table, TableCell,
+    TableCellContent, TableChild, TableRow, TableRowChild,
 };
-use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use pulldown_cmark::{Alignment, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
 
-use super::ConversionError;
+use super::{
+    check_signature, escape_table_cell, invalid_file, is_cell_line_break, ConversionError,
+    MERGED_CELLS_NOTE,
+};
+
+/// Numbering definitions used for exported lists. Bullets share one numbering
+/// instance; each ordered list gets its own instance so its count restarts.
+/// Id 1 is taken by the default numbering docx-rs always writes.
+const BULLET_ABSTRACT_ID: usize = 2;
+const ORDERED_ABSTRACT_ID: usize = 3;
+const BULLET_NUM_ID: usize = 2;
+const FIRST_ORDERED_NUM_ID: usize = 3;
+/// Word supports nine list levels (ilvl 0-8)
+const LIST_LEVELS: usize = 9;
+/// Left indentation per list level, in twips (0.5")
+const LIST_INDENT: i32 = 720;
+/// Monospace font and light grey background for inline code and code blocks
+const CODE_FONT: &str = "Consolas";
+const CODE_SHADING: &str = "F2F2F2";
+/// Paragraph style for blockquotes; nesting adds one `LIST_INDENT` per level
+const QUOTE_STYLE: &str = "Quote";
+/// Paragraph style for code block lines, recognised again on import
+const CODE_STYLE: &str = "SourceCode";
+/// Paragraph style ids (lowercased, matched whole) read back as code block lines
+const CODE_STYLE_IDS: &[&str] = &[
+    "code",
+    "codeblock",
+    "htmlcode",
+    "htmlpreformatted",
+    "sourcecode",
+];
+/// Character style for link text; the underline comes from the style so it
+/// isn't read back as `<u>`
+const LINK_STYLE: &str = "Hyperlink";
+/// Font families (lowercased, matched whole) treated as monospace when importing runs
+const MONOSPACE_FONTS: &[&str] = &[
+    "andale mono",
+    "cascadia code",
+    "cascadia mono",
+    "consolas",
+    "courier",
+    "courier new",
+    "dejavu sans mono",
+    "fira code",
+    "fira mono",
+    "inconsolata",
+    "jetbrains mono",
+    "liberation mono",
+    "lucida console",
+    "lucida sans typewriter",
+    "menlo",
+    "monaco",
+    "monospace",
+    "noto sans mono",
+    "roboto mono",
+    "sf mono",
+    "source code pro",
+    "ubuntu mono",
+];
 
 /// Convert a DOCX file to Markdown text.
 ///
@@ -15,49 +76,166 @@ use super::ConversionError;
 /// - Images are skipped
 /// - Track changes, comments, footnotes are dropped
 /// - Complex layouts (text boxes, columns) may have scrambled order
-pub fn docx_to_markdown(path: &str) -> Result<String, ConversionError> {
+/// - Internal (bookmark) links become plain text
+///
+/// Markdown has no underline, so underlined runs are wrapped in `<u>` tags
+/// unless `keep_underline` is false, in which case the underline is dropped.
+pub fn docx_to_markdown(path: &str, keep_underline: bool) -> Result<String, ConversionError> {
+    check_signature(path, "docx")?;
     let bytes =
         std::fs::read(path).map_err(|e| ConversionError(format!("Failed to read file: {}", e)))?;
 
     let docx = read_docx(&bytes)
-        .map_err(|e| ConversionError(format!("Failed to parse DOCX: {:?}", e)))?;
+        .map_err(|e| invalid_file("docx", format!("Failed to parse DOCX: {:?}", e)))?;
 
     let mut output = String::new();
     let mut first_block = true;
+    // Consecutive list items are kept together without blank lines
+    let mut prev_was_list_item = false;
+    let mut prev_quote_depth = 0;
+    // Lines of consecutive code-styled paragraphs, written as one fenced block
+    let mut code_lines: Vec<String> = Vec::new();
 
     for child in &docx.document.children {
+        if let DocumentChild::Paragraph(para) = child {
+            if is_code_paragraph(para) {
+                code_lines.push(paragraph_text(para));
+                continue;
+            }
+        }
+        if !code_lines.is_empty() {
+            push_code_block(&mut output, &mut code_lines, first_block);
+            first_block = false;
+            prev_was_list_item = false;
+            prev_quote_depth = 0;
+        }
         match child {
             DocumentChild::Paragraph(para) => {
-                let md = paragraph_to_markdown(para);
+                let md = paragraph_to_markdown(para, &docx.hyperlinks, keep_underline);
+                let list_prefix = list_item_prefix(para, &docx.numberings);
+                let depth = quote_depth(para);
                 if md.trim().is_empty() {
                     if !first_block {
                         output.push('\n');
                     }
+                    prev_was_list_item = false;
+                    prev_quote_depth = 0;
                 } else {
-                    if !first_block {
+                    let is_list_item = list_prefix.is_some();
+                    let continues_list = is_list_item && prev_was_list_item;
+                    if !first_block && !continues_list {
+                        // Paragraphs of the same blockquote are separated by a bare `>` line
+                        let shared_depth = depth.min(prev_quote_depth);
+                        output.push_str("> ".repeat(shared_depth).trim_end());
                         output.push('\n');
                     }
+                    output.push_str(&"> ".repeat(depth));
+                    if let Some(prefix) = list_prefix {
+                        output.push_str(&prefix);
+                    }
                     output.push_str(&md);
                     output.push('\n');
                     first_block = false;
+                    prev_was_list_item = is_list_item;
+                    prev_quote_depth = depth;
                 }
             }
             DocumentChild::Table(table) => {
                 if !first_block {
                     output.push('\n');
                 }
-                output.push_str(&table_to_markdown(table));
+                output.push_str(&table_to_markdown(table, &docx.hyperlinks, keep_underline));
                 output.push('\n');
                 first_block = false;
+                prev_was_list_item = false;
+                prev_quote_depth = 0;
             }
             _ => {}
         }
     }
+    if !code_lines.is_empty() {
+        push_code_block(&mut output, &mut code_lines, first_block);
+    }
 
     Ok(output)
 }
 
-fn paragraph_to_markdown(para: &Paragraph) -> String {
+/// Paragraphs in a code style ("Source Code", "HTML Code", ...) are code block lines
+fn is_code_paragraph(para: &Paragraph) -> bool {
+    para.property
+        .style
+        .as_ref()
+        .is_some_and(|s| CODE_STYLE_IDS.contains(&s.val.to_lowercase().as_str()))
+}
+
+/// Text of a paragraph without any Markdown formatting
+fn paragraph_text(para: &Paragraph) -> String {
+    let mut text = String::new();
+    for child in &para.children {
+        match child {
+            ParagraphChild::Run(run) => text.push_str(&run_text(run)),
+            ParagraphChild::Hyperlink(link) => {
+                for child in &link.children {
+                    if let ParagraphChild::Run(run) = child {
+                        text.push_str(&run_text(run));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Write `lines` as a fenced code block, with a fence longer than any run of
+/// backticks in the code so it can't close early.
+fn push_code_block(output: &mut String, lines: &mut Vec<String>, first_block: bool) {
+    if !first_block {
+        output.push('\n');
+    }
+    let code = lines.drain(..).collect::<Vec<_>>().join("\n");
+    let fence = "`".repeat((longest_backtick_run(&code) + 1).max(3));
+    output.push_str(&format!("{}\n{}\n{}\n", fence, code, fence));
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+/// Wrap text in a code span. Backticks in the text are not escaped (code
+/// spans have no escapes); the delimiter is made longer than any of them.
+fn code_span(text: &str) -> String {
+    let delimiter = "`".repeat(longest_backtick_run(text) + 1);
+    // A space keeps a leading or trailing backtick from merging with the delimiter
+    let padding = if text.starts_with('`') || text.ends_with('`') {
+        " "
+    } else {
+        ""
+    };
+    format!("{}{}{}{}{}", delimiter, padding, text, padding, delimiter)
+}
+
+/// Whether a run is set in a monospace font. RunFonts keeps its fields
+/// private, so the font names are read through its serialized form.
+fn is_monospace(run: &Run) -> bool {
+    let Some(fonts) = &run.run_property.fonts else {
+        return false;
+    };
+    let fonts = serde_json::to_value(fonts).unwrap_or_default();
+    ["ascii", "hiAnsi"]
+        .iter()
+        .filter_map(|key| fonts[*key].as_str())
+        .any(|font| MONOSPACE_FONTS.contains(&font.trim().to_lowercase().as_str()))
+}
+
+/// `hyperlinks` is the document's (relationship id, target, mode) list used to
+/// resolve external link targets. Blockquote markers are left to the caller,
+/// since a quote-styled paragraph inside a table cell is still just cell text.
+fn paragraph_to_markdown(
+    para: &Paragraph,
+    hyperlinks: &[(String, String, String)],
+    keep_underline: bool,
+) -> String {
     // Detect heading level from style ID
     let heading_prefix = para
         .property
@@ -77,11 +255,18 @@ fn paragraph_to_markdown(para: &Paragraph) -> String {
         .unwrap_or("");
 
     let mut text = String::new();
+    let mut runs: Vec<&Run> = Vec::new();
     for child in &para.children {
-        if let ParagraphChild::Run(run) = child {
-            text.push_str(&run_to_markdown(run));
+        match child {
+            ParagraphChild::Run(run) => runs.push(run),
+            ParagraphChild::Hyperlink(link) => {
+                text.push_str(&runs_to_markdown(runs.drain(..), keep_underline));
+                text.push_str(&hyperlink_to_markdown(link, hyperlinks, keep_underline))
+            }
+            _ => {}
         }
     }
+    text.push_str(&runs_to_markdown(runs, keep_underline));
 
     if text.is_empty() {
         String::new()
@@ -90,7 +275,98 @@ fn paragraph_to_markdown(para: &Paragraph) -> String {
     }
 }
 
-fn run_to_markdown(run: &Run) -> String {
+/// Blockquote nesting of a paragraph in a quote style, inferred from its left
+/// indentation. Paragraphs in other styles are not quotes.
+fn quote_depth(para: &Paragraph) -> usize {
+    let is_quote = para
+        .property
+        .style
+        .as_ref()
+        .is_some_and(|s| matches!(s.val.to_lowercase().as_str(), "quote" | "intensequote"));
+    if !is_quote {
+        return 0;
+    }
+    let indent = para
+        .property
+        .indent
+        .as_ref()
+        .and_then(|i| i.start)
+        .unwrap_or(0);
+    ((indent + LIST_INDENT / 2) / LIST_INDENT).max(1) as usize
+}
+
+/// Markdown list marker for a numbered paragraph (`- ` or `1. `), indented two
+/// spaces per nesting level. Returns `None` for paragraphs outside any list.
+fn list_item_prefix(para: &Paragraph, numberings: &Numberings) -> Option<String> {
+    let numbering = para.property.numbering_property.as_ref()?;
+    let num_id = numbering.id.as_ref()?.id;
+    // numId 0 explicitly removes numbering from the paragraph
+    if num_id == 0 {
+        return None;
+    }
+    let level = numbering.level.as_ref().map(|l| l.val).unwrap_or(0);
+
+    let marker = if is_ordered_list(numberings, num_id, level) {
+        "1. "
+    } else {
+        "- "
+    };
+    Some(format!("{}{}", "  ".repeat(level), marker))
+}
+
+/// Look up the level's number format through numbering -> abstract numbering.
+/// Anything other than a bullet format counts as ordered; unknown lists are bullets.
+fn is_ordered_list(numberings: &Numberings, num_id: usize, level: usize) -> bool {
+    numberings
+        .numberings
+        .iter()
+        .find(|n| n.id == num_id)
+        .and_then(|n| {
+            numberings
+                .abstract_nums
+                .iter()
+                .find(|a| a.id == n.abstract_num_id)
+        })
+        .and_then(|a| a.levels.iter().find(|l| l.level == level))
+        .map(|l| !matches!(l.format.val.as_str(), "bullet" | "none"))
+        .unwrap_or(false)
+}
+
+/// A hyperlink spanning several runs becomes one `[text](url)` with each run's
+/// emphasis kept inside the brackets. Anchor links and links whose relationship
+/// can't be resolved fall back to their text.
+fn hyperlink_to_markdown(
+    link: &Hyperlink,
+    hyperlinks: &[(String, String, String)],
+    keep_underline: bool,
+) -> String {
+    let mut runs: Vec<&Run> = Vec::new();
+    for child in &link.children {
+        if let ParagraphChild::Run(run) = child {
+            runs.push(run);
+        }
+    }
+    let text = runs_to_markdown(runs, keep_underline);
+    if text.is_empty() {
+        return text;
+    }
+
+    let url = match &link.link {
+        HyperlinkData::External { rid, path } if path.is_empty() => hyperlinks
+            .iter()
+            .find(|(id, _, _)| id == rid)
+            .map(|(_, target, _)| target.as_str()),
+        HyperlinkData::External { path, .. } => Some(path.as_str()),
+        HyperlinkData::Anchor { .. } => None,
+    };
+
+    match url {
+        Some(url) if !url.is_empty() => format!("[{}]({})", text, url),
+        _ => text,
+    }
+}
+
+fn run_text(run: &Run) -> String {
     let mut text = String::new();
     for child in &run.children {
         match child {
@@ -100,14 +376,79 @@ fn run_to_markdown(run: &Run) -> String {
             _ => {}
         }
     }
+    text
+}
+
+/// Word splits text into runs at spell-check and revision boundaries, so a
+/// stretch of code may arrive as several monospace runs; those with the same
+/// emphasis share one code span instead of each getting their own.
+fn runs_to_markdown<'a>(runs: impl IntoIterator<Item = &'a Run>, keep_underline: bool) -> String {
+    let mut text = String::new();
+    // Code text collected so far and the run whose emphasis it takes
+    let mut code: Option<(String, &Run)> = None;
+    for run in runs {
+        let continues_code = is_monospace(run)
+            && code.as_ref().is_some_and(|(_, first)| {
+                run_emphasis(first, keep_underline) == run_emphasis(run, keep_underline)
+            });
+        if continues_code {
+            if let Some((code_text, _)) = code.as_mut() {
+                code_text.push_str(&run_text(run));
+            }
+            continue;
+        }
+        if let Some((code_text, first)) = code.take() {
+            if !code_text.is_empty() {
+                text.push_str(&emphasize(code_span(&code_text), first, keep_underline));
+            }
+        }
+        if is_monospace(run) {
+            code = Some((run_text(run), run));
+        } else {
+            text.push_str(&run_to_markdown(run, keep_underline));
+        }
+    }
+    if let Some((code_text, first)) = code {
+        if !code_text.is_empty() {
+            text.push_str(&emphasize(code_span(&code_text), first, keep_underline));
+        }
+    }
+    text
+}
 
+fn run_to_markdown(run: &Run, keep_underline: bool) -> String {
+    let text = run_text(run);
     if text.is_empty() {
         return String::new();
     }
+    let text = if is_monospace(run) {
+        code_span(&text)
+    } else {
+        text
+    };
+    emphasize(text, run, keep_underline)
+}
 
+/// Bold, italic, strikethrough and (when kept) underline of a run
+fn run_emphasis(run: &Run, keep_underline: bool) -> (bool, bool, bool, bool) {
     // Bold.val is private; presence of Some(_) indicates bold is set
-    let bold = run.run_property.bold.is_some();
-    let italic = run.run_property.italic.is_some();
+    (
+        run.run_property.bold.is_some(),
+        run.run_property.italic.is_some(),
+        run.run_property.strike.is_some(),
+        keep_underline && is_underlined(run),
+    )
+}
+
+/// Wrap already converted text in the markers for the run's emphasis
+fn emphasize(mut text: String, run: &Run, keep_underline: bool) -> String {
+    let (bold, italic, strike, underline) = run_emphasis(run, keep_underline);
+    if strike {
+        text = format!("~~{}~~", text);
+    }
+    if underline {
+        text = format!("<u>{}</u>", text);
+    }
 
     match (bold, italic) {
         (true, true) => format!("***{}***", text),
@@ -117,27 +458,74 @@ fn run_to_markdown(run: &Run) -> String {
     }
 }
 
-fn table_to_markdown(table: &Table) -> String {
+/// Word writes `<w:u w:val="none"/>` to switch underline off, so a present
+/// property alone is not enough. The value is private in docx-rs and is read
+/// through its serialized form.
+fn is_underlined(run: &Run) -> bool {
+    let Some(underline) = &run.run_property.underline else {
+        return false;
+    };
+    let value = serde_json::to_value(underline).unwrap_or_default();
+    let val = value.get("val").unwrap_or(&value);
+    val.as_str() != Some("none")
+}
+
+/// GFM has no cell spans, so merged cells are flattened: a cell spanning
+/// several columns (`gridSpan`) or continued into the rows below (`vMerge`)
+/// has its text repeated in every grid cell it covers, keeping columns aligned.
+fn table_to_markdown(
+    table: &Table,
+    hyperlinks: &[(String, String, String)],
+    keep_underline: bool,
+) -> String {
     let mut rows: Vec<Vec<String>> = Vec::new();
+    // Column alignment comes from the justification of the header cells
+    let mut alignments: Vec<&str> = Vec::new();
+    let mut flattened = false;
 
     for row_child in &table.rows {
         let TableChild::TableRow(table_row) = row_child;
         let mut cells: Vec<String> = Vec::new();
         for cell_child in &table_row.cells {
             let TableRowChild::TableCell(table_cell) = cell_child;
-            let mut cell_text = String::new();
-            for content in &table_cell.children {
-                if let TableCellContent::Paragraph(para) = content {
-                    let p = paragraph_to_markdown(para);
-                    if !p.is_empty() {
-                        if !cell_text.is_empty() {
-                            cell_text.push(' ');
-                        }
-                        cell_text.push_str(p.trim());
-                    }
+            let (span, continues_merge) = cell_merge(table_cell);
+            if span > 1 || continues_merge {
+                flattened = true;
+            }
+            if rows.is_empty() {
+                for _ in 0..span {
+                    alignments.push(cell_alignment_marker(table_cell));
                 }
             }
-            cells.push(cell_text);
+            if continues_merge {
+                // The text lives in the cell that started the merge, in the row above
+                let above = rows
+                    .last()
+                    .and_then(|r| r.get(cells.len()))
+                    .cloned()
+                    .unwrap_or_default();
+                for _ in 0..span {
+                    cells.push(above.clone());
+                }
+                continue;
+            }
+            // Each paragraph of the cell becomes one line
+            let lines: Vec<String> = table_cell
+                .children
+                .iter()
+                .filter_map(|content| match content {
+                    TableCellContent::Paragraph(para) => {
+                        Some(paragraph_to_markdown(para, hyperlinks, keep_underline))
+                    }
+                    _ => None,
+                })
+                .filter(|p| !p.trim().is_empty())
+                .map(|p| p.trim().to_string())
+                .collect();
+            let cell_text = escape_table_cell(&lines.join("\n"));
+            for _ in 0..span {
+                cells.push(cell_text.clone());
+            }
         }
         if !cells.is_empty() {
             rows.push(cells);
@@ -166,8 +554,11 @@ fn table_to_markdown(table: &Table) -> String {
 
     // Separator
     md.push('|');
-    for _ in 0..col_count {
-        md.push_str(" --- |");
+    for i in 0..col_count {
+        md.push_str(&format!(
+            " {} |",
+            alignments.get(i).copied().unwrap_or("---")
+        ));
     }
     md.push('\n');
 
@@ -181,27 +572,248 @@ fn table_to_markdown(table: &Table) -> String {
         md.push('\n');
     }
 
+    if flattened {
+        md.push_str(MERGED_CELLS_NOTE);
+    }
+
     md
 }
 
+/// Number of grid columns a cell spans and whether it continues a vertical
+/// merge from the row above. docx-rs has no getters for cell properties, so
+/// they are read from the `w:tcPr` element it builds through `BuildXML`.
+fn cell_merge(cell: &TableCell) -> (usize, bool) {
+    let xml = String::from_utf8_lossy(&cell.property.build()).into_owned();
+    let element = |name: &str| {
+        let start = xml.find(&format!("<w:{}", name))?;
+        let end = xml[start..].find('>')?;
+        Some(xml[start..start + end].to_string())
+    };
+    let val = |tag: &str| {
+        let start = tag.find("w:val=\"")? + "w:val=\"".len();
+        Some(tag[start..start + tag[start..].find('"')?].to_string())
+    };
+    let span = element("gridSpan")
+        .and_then(|tag| val(&tag))
+        .and_then(|v| v.parse::<usize>().ok())
+        .map_or(1, |n| n.max(1));
+    // A `w:vMerge` without a value continues the merge as well
+    let continues_merge =
+        element("vMerge").is_some_and(|tag| val(&tag).as_deref() != Some("restart"));
+    (span, continues_merge)
+}
+
+/// GFM separator for a header cell based on its first paragraph's justification.
+fn cell_alignment_marker(cell: &TableCell) -> &'static str {
+    let justification = cell.children.iter().find_map(|content| match content {
+        TableCellContent::Paragraph(para) => para.property.alignment.as_ref(),
+        _ => None,
+    });
+    match justification.map(|j| j.val.as_str()) {
+        Some("center") => ":---:",
+        Some("right") | Some("end") => "---:",
+        Some("left") | Some("start") => ":---",
+        _ => "---",
+    }
+}
+
+/// Abstract numbering with `LIST_LEVELS` levels of either bullets or decimals.
+fn list_abstract_numbering(id: usize, ordered: bool) -> AbstractNumbering {
+    const BULLETS: [&str; 3] = ["\u{2022}", "\u{25E6}", "\u{25AA}"];
+    let mut abstract_num = AbstractNumbering::new(id);
+    for level in 0..LIST_LEVELS {
+        let (format, text) = if ordered {
+            ("decimal", format!("%{}.", level + 1))
+        } else {
+            ("bullet", BULLETS[level % BULLETS.len()].to_string())
+        };
+        let indent = LIST_INDENT * (level as i32 + 1);
+        abstract_num = abstract_num.add_level(
+            Level::new(
+                level,
+                Start::new(1),
+                NumberFormat::new(format),
+                LevelText::new(text),
+                LevelJc::new("left"),
+            )
+            .indent(
+                Some(indent),
+                Some(SpecialIndentType::Hanging(360)),
+                None,
+                None,
+            ),
+        );
+    }
+    abstract_num
+}
+
+/// Character formatting active while collecting a run of text.
+#[derive(Clone, Copy, Default, PartialEq)]
+struct RunFormat {
+    bold: bool,
+    italic: bool,
+    strike: bool,
+    underline: bool,
+    code: bool,
+    /// Index of the link URL the run belongs to
+    link: Option<usize>,
+}
+
+/// A run of `text` in `format`. Each `\n` (a Markdown hard break) becomes a
+/// line break within the run.
+fn formatted_run(text: String, format: RunFormat) -> Run {
+    let mut run = Run::new();
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            run = run.add_break(BreakType::TextWrapping);
+        }
+        if !line.is_empty() {
+            run = run.add_text(line);
+        }
+    }
+    if format.bold {
+        run = run.bold();
+    }
+    if format.italic {
+        run = run.italic();
+    }
+    if format.strike {
+        run = run.strike();
+    }
+    if format.underline {
+        run = run.underline("single");
+    }
+    if format.code {
+        run = run
+            .fonts(
+                RunFonts::new()
+                    .ascii(CODE_FONT)
+                    .hi_ansi(CODE_FONT)
+                    .cs(CODE_FONT),
+            )
+            .shading(Shading::new().fill(CODE_SHADING));
+    }
+    if format.link.is_some() {
+        run = run.style(LINK_STYLE);
+    }
+    run
+}
+
+/// Add runs to a paragraph, wrapping consecutive runs of the same link in one
+/// hyperlink. `#anchor` URLs link to the bookmark `markdown_to_docx` puts on
+/// the heading with that slug.
+fn add_runs(
+    mut para: Paragraph,
+    runs: impl IntoIterator<Item = (String, RunFormat)>,
+    link_urls: &[String],
+) -> Paragraph {
+    let mut runs = runs.into_iter().peekable();
+    while let Some((text, run_format)) = runs.next() {
+        let Some(link) = run_format.link else {
+            para = para.add_run(formatted_run(text, run_format));
+            continue;
+        };
+        let url = link_urls[link].as_str();
+        let mut hyperlink = match url.strip_prefix('#') {
+            Some(anchor) => Hyperlink::new(anchor, HyperlinkType::Anchor),
+            None => Hyperlink::new(url, HyperlinkType::External),
+        };
+        hyperlink = hyperlink.add_run(formatted_run(text, run_format));
+        while let Some((text, run_format)) = runs.next_if(|(_, f)| f.link == Some(link)) {
+            hyperlink = hyperlink.add_run(formatted_run(text, run_format));
+        }
+        para = para.add_hyperlink(hyperlink);
+    }
+    para
+}
+
+/// A table cell's content: one list of formatted runs per line, split at `<br>`
+type CellLines = Vec<Vec<(String, RunFormat)>>;
+
+/// Append text to the cell's current line, extending the last run when the
+/// formatting is unchanged
+fn push_cell_text(cell: &mut CellLines, text: &str, format: RunFormat) {
+    if cell.is_empty() {
+        cell.push(Vec::new());
+    }
+    let line = cell.last_mut().expect("cell has a line");
+    match line.last_mut() {
+        Some((last, last_format)) if *last_format == format => last.push_str(text),
+        _ => line.push((text.to_string(), format)),
+    }
+}
+
+/// Settings for DOCX export.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocxOptions {
+    /// Start a new page at each thematic break, as between the notes of a
+    /// folder export; otherwise breaks are left out
+    pub page_break_on_rule: bool,
+}
+
 /// Convert Markdown to a DOCX file.
 pub fn markdown_to_docx(markdown: &str, path: &str) -> Result<(), ConversionError> {
-    let mut docx = Docx::new();
+    markdown_to_docx_with_options(markdown, path, &DocxOptions::default())
+}
+
+/// Convert Markdown to a DOCX file with the given settings.
+pub fn markdown_to_docx_with_options(
+    markdown: &str,
+    path: &str,
+    options: &DocxOptions,
+) -> Result<(), ConversionError> {
+    let mut docx = Docx::new()
+        .add_abstract_numbering(list_abstract_numbering(BULLET_ABSTRACT_ID, false))
+        .add_abstract_numbering(list_abstract_numbering(ORDERED_ABSTRACT_ID, true))
+        .add_numbering(Numbering::new(BULLET_NUM_ID, BULLET_ABSTRACT_ID))
+        .add_style(
+            Style::new(QUOTE_STYLE, StyleType::Paragraph)
+                .name(QUOTE_STYLE)
+                .based_on("Normal")
+                .italic()
+                .color("595959"),
+        )
+        .add_style(
+            Style::new(CODE_STYLE, StyleType::Paragraph)
+                .name("Source Code")
+                .based_on("Normal"),
+        )
+        .add_style(
+            Style::new(LINK_STYLE, StyleType::Character)
+                .name(LINK_STYLE)
+                .color("0563C1")
+                .underline("single"),
+        );
 
     let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
     let parser = Parser::new_ext(markdown, options);
 
     // State machine for building paragraphs
-    let mut pending_runs: Vec<(String, bool, bool)> = Vec::new(); // (text, bold, italic)
+    let mut pending_runs: Vec<(String, RunFormat)> = Vec::new();
     let mut current_text = String::new();
-    let mut in_bold = false;
-    let mut in_italic = false;
+    let mut format = RunFormat::default();
+    // URLs of the links seen so far, indexed by `RunFormat::link`
+    let mut link_urls: Vec<String> = Vec::new();
     let mut heading_level: Option<u8> = None;
+    // Headings are bookmarked under their GitHub-style slug, so `#slug` links resolve
+    let mut slugger = crate::markdown::Slugger::default();
+    let mut heading_bookmark: Option<String> = None;
+    let mut bookmark_id = 0;
     // Table state
     let mut in_table = false;
-    let mut table_rows: Vec<Vec<String>> = Vec::new();
-    let mut current_table_row: Vec<String> = Vec::new();
-    let mut current_cell_text = String::new();
+    let mut table_rows: Vec<Vec<CellLines>> = Vec::new();
+    let mut table_alignments: Vec<Alignment> = Vec::new();
+    let mut current_table_row: Vec<CellLines> = Vec::new();
+    let mut current_cell: CellLines = Vec::new();
+    // List state: numbering id for each open list, innermost last
+    let mut list_stack: Vec<usize> = Vec::new();
+    let mut next_ordered_num_id = FIRST_ORDERED_NUM_ID;
+    // Only an item's first paragraph carries the marker; later ones are just indented
+    let mut item_needs_marker = false;
+    // Code block text is collected verbatim, without emphasis parsing
+    let mut in_code_block = false;
+    let mut code_block_text = String::new();
+    let mut quote_depth = 0;
 
     // Helper: flush current runs into a Paragraph
     macro_rules! flush_paragraph {
@@ -209,17 +821,33 @@ pub fn markdown_to_docx(markdown: &str, path: &str) -> Result<(), ConversionErro
             let mut para = Paragraph::new();
             if let Some(s) = $style {
                 para = para.style(s);
+            } else if quote_depth > 0 && list_stack.is_empty() {
+                para = para.style(QUOTE_STYLE);
+            }
+            if quote_depth > 0 && list_stack.is_empty() {
+                para = para.indent(Some(LIST_INDENT * quote_depth), None, None, None);
+            }
+            if let Some(&num_id) = list_stack.last() {
+                let level = list_stack.len() - 1;
+                if std::mem::take(&mut item_needs_marker) {
+                    para = para.numbering(NumberingId::new(num_id), IndentLevel::new(level));
+                } else {
+                    para = para.indent(Some(LIST_INDENT * (level as i32 + 1)), None, None, None);
+                }
             }
             // Flush any remaining text as a run
             if !current_text.is_empty() {
-                pending_runs.push((current_text.clone(), in_bold, in_italic));
+                pending_runs.push((current_text.clone(), format));
                 current_text.clear();
             }
-            for (text, bold, italic) in pending_runs.drain(..) {
-                let mut run = Run::new().add_text(text);
-                if bold { run = run.bold(); }
-                if italic { run = run.italic(); }
-                para = para.add_run(run);
+            let bookmark = heading_bookmark.take();
+            if let Some(name) = &bookmark {
+                para = para.add_bookmark_start(bookmark_id, name.clone());
+            }
+            para = add_runs(para, pending_runs.drain(..), &link_urls);
+            if bookmark.is_some() {
+                para = para.add_bookmark_end(bookmark_id);
+                bookmark_id += 1;
             }
             docx = docx.add_paragraph(para);
         }};
@@ -242,6 +870,12 @@ pub fn markdown_to_docx(markdown: &str, path: &str) -> Result<(), ConversionErro
             Event::End(TagEnd::Heading(_)) => {
                 let level = heading_level.unwrap_or(1);
                 let style = format!("Heading{}", level);
+                let text: String = pending_runs
+                    .iter()
+                    .map(|(text, _)| text.as_str())
+                    .chain([current_text.as_str()])
+                    .collect();
+                heading_bookmark = Some(slugger.slug(&text));
                 flush_paragraph!(Some(style.as_str()));
                 heading_level = None;
             }
@@ -251,35 +885,162 @@ pub fn markdown_to_docx(markdown: &str, path: &str) -> Result<(), ConversionErro
             }
             Event::Start(Tag::Strong) => {
                 if !current_text.is_empty() {
-                    pending_runs.push((current_text.clone(), in_bold, in_italic));
+                    pending_runs.push((current_text.clone(), format));
                     current_text.clear();
                 }
-                in_bold = true;
+                format.bold = true;
             }
             Event::End(TagEnd::Strong) => {
                 if !current_text.is_empty() {
-                    pending_runs.push((current_text.clone(), in_bold, in_italic));
+                    pending_runs.push((current_text.clone(), format));
                     current_text.clear();
                 }
-                in_bold = false;
+                format.bold = false;
             }
             Event::Start(Tag::Emphasis) => {
                 if !current_text.is_empty() {
-                    pending_runs.push((current_text.clone(), in_bold, in_italic));
+                    pending_runs.push((current_text.clone(), format));
                     current_text.clear();
                 }
-                in_italic = true;
+                format.italic = true;
             }
             Event::End(TagEnd::Emphasis) => {
                 if !current_text.is_empty() {
-                    pending_runs.push((current_text.clone(), in_bold, in_italic));
+                    pending_runs.push((current_text.clone(), format));
+                    current_text.clear();
+                }
+                format.italic = false;
+            }
+            Event::Start(Tag::Strikethrough) => {
+                if !current_text.is_empty() {
+                    pending_runs.push((current_text.clone(), format));
                     current_text.clear();
                 }
-                in_italic = false;
+                format.strike = true;
             }
-            Event::Start(Tag::Table(_)) => {
+            Event::End(TagEnd::Strikethrough) => {
+                if !current_text.is_empty() {
+                    pending_runs.push((current_text.clone(), format));
+                    current_text.clear();
+                }
+                format.strike = false;
+            }
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                if !current_text.is_empty() {
+                    pending_runs.push((current_text.clone(), format));
+                    current_text.clear();
+                }
+                format.link = Some(link_urls.len());
+                link_urls.push(dest_url.to_string());
+            }
+            Event::End(TagEnd::Link) => {
+                if !current_text.is_empty() {
+                    pending_runs.push((current_text.clone(), format));
+                    current_text.clear();
+                }
+                format.link = None;
+            }
+            Event::Start(Tag::List(start)) => {
+                // A nested list ends the parent item's text
+                if !current_text.is_empty() || !pending_runs.is_empty() {
+                    flush_paragraph!(None::<&str>);
+                }
+                let num_id = match start {
+                    Some(n) => {
+                        let id = next_ordered_num_id;
+                        next_ordered_num_id += 1;
+                        let level = list_stack.len();
+                        docx = docx.add_numbering(
+                            Numbering::new(id, ORDERED_ABSTRACT_ID)
+                                .add_override(LevelOverride::new(level).start(n as usize)),
+                        );
+                        id
+                    }
+                    None => BULLET_NUM_ID,
+                };
+                list_stack.push(num_id);
+            }
+            Event::End(TagEnd::List(_)) => {
+                list_stack.pop();
+            }
+            Event::Start(Tag::Item) => {
+                item_needs_marker = true;
+            }
+            Event::End(TagEnd::Item) => {
+                // Tight list items have no Paragraph wrapper
+                if !current_text.is_empty() || !pending_runs.is_empty() {
+                    flush_paragraph!(None::<&str>);
+                } else {
+                    item_needs_marker = false;
+                }
+            }
+            Event::Start(Tag::BlockQuote(_)) => {
+                if !current_text.is_empty() || !pending_runs.is_empty() {
+                    flush_paragraph!(None::<&str>);
+                }
+                quote_depth += 1;
+            }
+            Event::End(TagEnd::BlockQuote(_)) => {
+                if !current_text.is_empty() || !pending_runs.is_empty() {
+                    flush_paragraph!(None::<&str>);
+                }
+                quote_depth -= 1;
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                if !current_text.is_empty() || !pending_runs.is_empty() {
+                    flush_paragraph!(None::<&str>);
+                }
+                in_code_block = true;
+                code_block_text.clear();
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                in_code_block = false;
+                // One paragraph per line keeps line breaks and leading whitespace intact
+                let code = code_block_text
+                    .strip_suffix('\n')
+                    .unwrap_or(&code_block_text);
+                let code_format = RunFormat {
+                    code: true,
+                    ..RunFormat::default()
+                };
+                for line in code.split('\n') {
+                    let line = line.strip_suffix('\r').unwrap_or(line);
+                    docx = docx.add_paragraph(
+                        Paragraph::new()
+                            .style(CODE_STYLE)
+                            .add_run(formatted_run(line.to_string(), code_format)),
+                    );
+                }
+                code_block_text.clear();
+            }
+            Event::Code(code) => {
+                if in_table {
+                    push_cell_text(
+                        &mut current_cell,
+                        &code,
+                        RunFormat {
+                            code: true,
+                            ..format
+                        },
+                    );
+                } else {
+                    if !current_text.is_empty() {
+                        pending_runs.push((current_text.clone(), format));
+                        current_text.clear();
+                    }
+                    pending_runs.push((
+                        code.to_string(),
+                        RunFormat {
+                            code: true,
+                            ..format
+                        },
+                    ));
+                }
+            }
+            Event::Start(Tag::Table(alignments)) => {
                 in_table = true;
                 table_rows.clear();
+                table_alignments = alignments;
             }
             Event::End(TagEnd::Table) => {
                 in_table = false;
@@ -290,9 +1051,33 @@ pub fn markdown_to_docx(markdown: &str, path: &str) -> Result<(), ConversionErro
                     for row in &table_rows {
                         let mut docx_cells: Vec<TableCell> = Vec::new();
                         for i in 0..col_count {
-                            let cell_text = row.get(i).map(|s| s.as_str()).unwrap_or("");
-                            let para = Paragraph::new().add_run(Run::new().add_text(cell_text));
-                            docx_cells.push(TableCell::new().add_paragraph(para));
+                            let empty_line = vec![Vec::new()];
+                            let lines = row
+                                .get(i)
+                                .filter(|lines| !lines.is_empty())
+                                .unwrap_or(&empty_line);
+                            // `<br>` line breaks in the cell become separate paragraphs
+                            let mut cell = TableCell::new();
+                            for line in lines {
+                                let mut para =
+                                    add_runs(Paragraph::new(), line.iter().cloned(), &link_urls);
+                                if line.is_empty() {
+                                    para = para.add_run(Run::new());
+                                }
+                                // Unaligned columns keep Word's default left alignment
+                                match table_alignments.get(i) {
+                                    Some(Alignment::Center) => {
+                                        para = para.align(AlignmentType::Center)
+                                    }
+                                    Some(Alignment::Right) => {
+                                        para = para.align(AlignmentType::Right)
+                                    }
+                                    Some(Alignment::Left) => para = para.align(AlignmentType::Left),
+                                    _ => {}
+                                }
+                                cell = cell.add_paragraph(para);
+                            }
+                            docx_cells.push(cell);
                         }
                         docx_rows.push(TableRow::new(docx_cells));
                     }
@@ -318,24 +1103,50 @@ pub fn markdown_to_docx(markdown: &str, path: &str) -> Result<(), ConversionErro
                 current_table_row.clear();
             }
             Event::Start(Tag::TableCell) => {
-                current_cell_text.clear();
+                current_cell.clear();
             }
             Event::End(TagEnd::TableCell) => {
-                current_table_row.push(current_cell_text.clone());
-                current_cell_text.clear();
+                current_table_row.push(std::mem::take(&mut current_cell));
             }
             Event::Text(text) => {
-                if in_table {
-                    current_cell_text.push_str(&text);
+                if in_code_block {
+                    code_block_text.push_str(&text);
+                } else if in_table {
+                    push_cell_text(&mut current_cell, &text, format);
                 } else {
                     current_text.push_str(&text);
                 }
             }
-            Event::SoftBreak | Event::HardBreak => {
+            Event::SoftBreak => {
                 if !in_table {
                     current_text.push(' ');
                 }
             }
+            Event::HardBreak => {
+                if !in_table {
+                    current_text.push('\n');
+                }
+            }
+            Event::InlineHtml(html) if in_table && is_cell_line_break(&html) => {
+                if current_cell.is_empty() {
+                    current_cell.push(Vec::new());
+                }
+                current_cell.push(Vec::new());
+            }
+            // `<u>` is how underline is written on import
+            Event::InlineHtml(html)
+                if matches!(html.trim().to_lowercase().as_str(), "<u>" | "</u>") =>
+            {
+                if !current_text.is_empty() {
+                    pending_runs.push((current_text.clone(), format));
+                    current_text.clear();
+                }
+                format.underline = !html.trim().starts_with("</");
+            }
+            Event::Rule if options.page_break_on_rule => {
+                docx = docx
+                    .add_paragraph(Paragraph::new().add_run(Run::new().add_break(BreakType::Page)));
+            }
             _ => {}
         }
     }
@@ -358,18 +1169,456 @@ pub fn markdown_to_docx(markdown: &str, path: &str) -> Result<(), ConversionErro
 #[cfg(test)]
 mod tests {
     use super::*;
+    use docx_rs::VMergeType;
+
+    /// `docx` written to a temporary file, deleted when the returned path is dropped
+    fn write_fixture(docx: Docx) -> tempfile::TempPath {
+        let file = tempfile::Builder::new().suffix(".docx").tempfile().unwrap();
+        docx.build().pack(BufWriter::new(file.as_file())).unwrap();
+        file.into_temp_path()
+    }
+
+    fn list_level(level: usize, format: &str) -> Level {
+        Level::new(
+            level,
+            Start::new(1),
+            NumberFormat::new(format),
+            LevelText::new("%1."),
+            LevelJc::new("left"),
+        )
+    }
+
+    fn list_item(text: &str, num_id: usize, level: usize) -> Paragraph {
+        Paragraph::new()
+            .add_run(Run::new().add_text(text))
+            .numbering(NumberingId::new(num_id), IndentLevel::new(level))
+    }
+
+    #[test]
+    fn test_docx_to_markdown_nested_lists() {
+        let docx = Docx::new()
+            .add_abstract_numbering(
+                AbstractNumbering::new(10)
+                    .add_level(list_level(0, "bullet"))
+                    .add_level(list_level(1, "bullet")),
+            )
+            .add_abstract_numbering(AbstractNumbering::new(11).add_level(list_level(0, "decimal")))
+            .add_numbering(Numbering::new(2, 10))
+            .add_numbering(Numbering::new(3, 11))
+            .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Intro")))
+            .add_paragraph(list_item("Fruit", 2, 0))
+            .add_paragraph(list_item("Apple", 2, 1))
+            .add_paragraph(list_item("Pear", 2, 1))
+            .add_paragraph(list_item("Vegetables", 2, 0))
+            .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Steps")))
+            .add_paragraph(list_item("First", 3, 0))
+            .add_paragraph(list_item("Second", 3, 0));
+        let path = write_fixture(docx);
+
+        let md = docx_to_markdown(path.to_str().unwrap(), true).unwrap();
+
+        assert_eq!(
+            md,
+            "Intro\n\n- Fruit\n  - Apple\n  - Pear\n- Vegetables\n\nSteps\n\n1. First\n1. Second\n"
+        );
+    }
+
+    #[test]
+    fn test_docx_to_markdown_flattens_merged_cells() {
+        let cell = |text: &str| {
+            TableCell::new().add_paragraph(Paragraph::new().add_run(Run::new().add_text(text)))
+        };
+        let table = Table::new(vec![
+            TableRow::new(vec![cell("Quarter").grid_span(2), cell("Total")]),
+            TableRow::new(vec![cell("Q1"), cell("Jan"), cell("10")]),
+        ]);
+        let path = write_fixture(Docx::new().add_table(table));
+
+        let md = docx_to_markdown(path.to_str().unwrap(), true).unwrap();
+
+        assert!(md.starts_with(
+            "| Quarter | Quarter | Total |\n| --- | --- | --- |\n| Q1 | Jan | 10 |\n"
+        ));
+        assert!(md.contains("Merged cells were flattened"));
+    }
+
+    #[test]
+    fn test_docx_to_markdown_flattens_vertically_merged_cells() {
+        let cell = |text: &str| {
+            TableCell::new().add_paragraph(Paragraph::new().add_run(Run::new().add_text(text)))
+        };
+        let table = Table::new(vec![
+            TableRow::new(vec![cell("Region"), cell("Sales")]),
+            TableRow::new(vec![
+                cell("North").vertical_merge(VMergeType::Restart),
+                cell("10"),
+            ]),
+            TableRow::new(vec![
+                TableCell::new().vertical_merge(VMergeType::Continue),
+                cell("20"),
+            ]),
+            TableRow::new(vec![cell("South"), cell("30")]),
+        ]);
+        let path = write_fixture(Docx::new().add_table(table));
+
+        let md = docx_to_markdown(path.to_str().unwrap(), true).unwrap();
+
+        assert!(md.starts_with(
+            "| Region | Sales |\n| --- | --- |\n| North | 10 |\n| North | 20 |\n| South | 30 |\n"
+        ));
+        assert!(md.contains("Merged cells were flattened"));
+    }
+
+    #[test]
+    fn test_docx_to_markdown_quote_style_in_table_cell_is_plain_text() {
+        let quoted = |text: &str| {
+            Paragraph::new()
+                .style(QUOTE_STYLE)
+                .add_run(Run::new().add_text(text))
+        };
+        let table = Table::new(vec![
+            TableRow::new(vec![TableCell::new().add_paragraph(quoted("Said"))]),
+            TableRow::new(vec![TableCell::new().add_paragraph(quoted("Hello"))]),
+        ]);
+        let path = write_fixture(Docx::new().add_paragraph(quoted("Intro")).add_table(table));
+
+        let md = docx_to_markdown(path.to_str().unwrap(), true).unwrap();
+
+        assert!(md.starts_with("> Intro\n\n| Said |\n| --- |\n| Hello |\n"));
+    }
+
+    #[test]
+    fn test_table_cells_with_pipes_and_line_breaks_round_trip() {
+        let md = "| Expr | Notes |\n| --- | --- |\n| a \\| b | first<br>second |";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pipe_cells.docx");
+        markdown_to_docx(md, path.to_str().unwrap()).unwrap();
+        let result = docx_to_markdown(path.to_str().unwrap(), true).unwrap();
+        assert_eq!(result.trim_end(), md);
+    }
+
+    #[test]
+    fn test_table_cells_keep_inline_formatting_across_line_breaks() {
+        let md =
+            "| Item | Notes |\n| --- | :---: |\n| **Tea** and `chai` | hot<br>*iced* in summer |";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("formatted_cells.docx");
+        markdown_to_docx(md, path.to_str().unwrap()).unwrap();
+        let result = docx_to_markdown(path.to_str().unwrap(), true).unwrap();
+        assert_eq!(result.trim_end(), md);
+    }
+
+    #[test]
+    fn test_markdown_to_docx_nested_list_round_trip() {
+        let md = "- Fruit\n  - Apple\n  - Pear\n- Vegetables\n\nSteps:\n\n3. First\n4. Second\n   1. Detail\n";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("list_export.docx");
+        let path_str = path.to_str().unwrap();
+
+        markdown_to_docx(md, path_str).unwrap();
+        let round_trip = docx_to_markdown(path_str, true).unwrap();
+
+        assert_eq!(
+            round_trip,
+            "- Fruit\n  - Apple\n  - Pear\n- Vegetables\n\nSteps:\n\n1. First\n1. Second\n  1. Detail\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown_to_docx_table_alignment_round_trip() {
+        let md = "| Name | Qty | Price |\n| --- | :---: | ---: |\n| Tea | 2 | 3.50 |";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("table_align.docx");
+        let path_str = path.to_str().unwrap();
+
+        markdown_to_docx(md, path_str).unwrap();
+        let round_trip = docx_to_markdown(path_str, true).unwrap();
+
+        assert_eq!(round_trip.trim_end(), md);
+    }
+
+    #[test]
+    fn test_markdown_to_docx_blockquote_round_trip() {
+        let md = "> Outer first\n>\n> Outer second\n>\n> > Inner\n\nAfter\n";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("quote_export.docx");
+        let path_str = path.to_str().unwrap();
+
+        markdown_to_docx(md, path_str).unwrap();
+        let round_trip = docx_to_markdown(path_str, true).unwrap();
+
+        assert_eq!(round_trip, md);
+    }
+
+    #[test]
+    fn test_markdown_to_docx_code_uses_monospace_runs() {
+        let md = "Run `cargo build` first.\n\n```\nfn main() {\n    let x = *y*;\n}\n```\n";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("code_export.docx");
+        let path_str = path.to_str().unwrap();
+
+        markdown_to_docx(md, path_str).unwrap();
+        let docx = read_docx(&std::fs::read(&path).unwrap()).unwrap();
+
+        let runs: Vec<&Run> = docx
+            .document
+            .children
+            .iter()
+            .filter_map(|c| match c {
+                DocumentChild::Paragraph(p) => Some(p),
+                _ => None,
+            })
+            .flat_map(|p| p.children.iter())
+            .filter_map(|c| match c {
+                ParagraphChild::Run(r) => Some(r.as_ref()),
+                _ => None,
+            })
+            .collect();
+        let run_text = |run: &Run| {
+            run.children
+                .iter()
+                .filter_map(|c| match c {
+                    RunChild::Text(t) => Some(t.text.clone()),
+                    _ => None,
+                })
+                .collect::<String>()
+        };
+        let font_of = |text: &str| {
+            let run = runs.iter().find(|r| run_text(r) == text).unwrap();
+            run.run_property
+                .fonts
+                .as_ref()
+                .map(|f| serde_json::to_value(f).unwrap()["ascii"].clone())
+        };
+
+        assert_eq!(font_of("cargo build"), Some(serde_json::json!(CODE_FONT)));
+        // Indentation and emphasis markers inside the block are kept as-is
+        assert_eq!(
+            font_of("    let x = *y*;"),
+            Some(serde_json::json!(CODE_FONT))
+        );
+        assert_eq!(font_of("Run "), None);
+    }
+
+    #[test]
+    fn test_markdown_to_docx_hard_break_becomes_line_break() {
+        let md = "First line  \nsecond line\\\nthird line\nsame line\n";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hard_break.docx");
+        markdown_to_docx(md, path.to_str().unwrap()).unwrap();
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let mut xml = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("word/document.xml").unwrap(), &mut xml)
+            .unwrap();
+
+        assert_eq!(xml.matches(r#"<w:br w:type="textWrapping" />"#).count(), 2);
+        // A soft break stays a space
+        assert!(xml.contains("third line same line"));
+        assert!(!xml.contains("First line second"));
+    }
+
+    #[test]
+    fn test_markdown_to_docx_rule_becomes_page_break_when_asked() {
+        let md = "# One\n\nFirst note\n\n---\n\n# Two\n\nSecond note\n";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rule.docx");
+        let document_xml = |options: &DocxOptions| {
+            markdown_to_docx_with_options(md, path.to_str().unwrap(), options).unwrap();
+            let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+            let mut xml = String::new();
+            std::io::Read::read_to_string(
+                &mut archive.by_name("word/document.xml").unwrap(),
+                &mut xml,
+            )
+            .unwrap();
+            xml
+        };
+
+        // A rule in an ordinary export doesn't start a new page
+        assert!(!document_xml(&DocxOptions::default()).contains(r#"w:type="page""#));
+
+        let xml = document_xml(&DocxOptions {
+            page_break_on_rule: true,
+        });
+        assert_eq!(xml.matches(r#"<w:br w:type="page" />"#).count(), 1);
+        let page_break = xml.find(r#"<w:br w:type="page" />"#).unwrap();
+        assert!(xml.find("First note").unwrap() < page_break);
+        assert!(page_break < xml.find("Second note").unwrap());
+    }
+
+    #[test]
+    fn test_markdown_to_docx_bookmarks_headings_for_anchor_links() {
+        let md = "# Getting Started\n\nSee [setup](#setup-guide).\n\n## Setup *guide*\n\n## Setup guide\n";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bookmarks.docx");
+        markdown_to_docx(md, path.to_str().unwrap()).unwrap();
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let mut xml = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("word/document.xml").unwrap(), &mut xml)
+            .unwrap();
+
+        assert!(xml.contains(r#"w:anchor="setup-guide""#));
+        for name in ["getting-started", "setup-guide", "setup-guide-1"] {
+            assert!(
+                xml.contains(&format!(r#"w:name="{}""#, name)),
+                "missing bookmark {}",
+                name
+            );
+        }
+        assert_eq!(xml.matches("<w:bookmarkEnd").count(), 3);
+    }
+
+    #[test]
+    fn test_docx_to_markdown_hyperlinks() {
+        let docx = Docx::new().add_paragraph(
+            Paragraph::new()
+                .add_run(Run::new().add_text("See "))
+                .add_hyperlink(
+                    Hyperlink::new("https://example.com/docs", HyperlinkType::External)
+                        .add_run(Run::new().add_text("the "))
+                        .add_run(Run::new().add_text("docs").bold()),
+                )
+                .add_run(Run::new().add_text(" or "))
+                .add_hyperlink(
+                    Hyperlink::new("intro", HyperlinkType::Anchor)
+                        .add_run(Run::new().add_text("the intro")),
+                ),
+        );
+        let path = write_fixture(docx);
+
+        let md = docx_to_markdown(path.to_str().unwrap(), true).unwrap();
+
+        assert_eq!(
+            md,
+            "See [the **docs**](https://example.com/docs) or the intro\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown_to_docx_inline_code_and_links_round_trip() {
+        let md = "Run `cargo build`, then see [the **docs**](https://example.com/docs) or [setup](#setup).\n";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("links_export.docx");
+        markdown_to_docx(md, path.to_str().unwrap()).unwrap();
+        let result = docx_to_markdown(path.to_str().unwrap(), true).unwrap();
+        // Anchor links have no URL in DOCX terms, so they come back as text
+        assert_eq!(
+            result,
+            "Run `cargo build`, then see [the **docs**](https://example.com/docs) or setup.\n"
+        );
+    }
 
     #[test]
     fn test_run_to_markdown_bold() {
         let run = Run::new().add_text("hello").bold();
-        let result = run_to_markdown(&run);
+        let result = run_to_markdown(&run, true);
         assert_eq!(result, "**hello**");
     }
 
+    #[test]
+    fn test_markdown_to_docx_strikethrough_round_trip() {
+        let md = "Keep ~~drop~~ and ***~~both~~*** here.";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("strike.docx");
+        markdown_to_docx(md, path.to_str().unwrap()).unwrap();
+        let result = docx_to_markdown(path.to_str().unwrap(), true).unwrap();
+        assert_eq!(result.trim_end(), md);
+    }
+
+    #[test]
+    fn test_run_to_markdown_underline() {
+        let run = Run::new().add_text("hello").bold().underline("single");
+        assert_eq!(run_to_markdown(&run, true), "**<u>hello</u>**");
+        assert_eq!(run_to_markdown(&run, false), "**hello**");
+        let off = Run::new().add_text("hello").underline("none");
+        assert_eq!(run_to_markdown(&off, true), "hello");
+    }
+
+    #[test]
+    fn test_markdown_to_docx_underline_round_trip() {
+        let md = "Sign <u>here</u> and *<u>there</u>*.";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("underline.docx");
+        markdown_to_docx(md, path.to_str().unwrap()).unwrap();
+        let result = docx_to_markdown(path.to_str().unwrap(), true).unwrap();
+        assert_eq!(result.trim_end(), md);
+    }
+
+    #[test]
+    fn test_docx_to_markdown_code_paragraphs_and_spans() {
+        let code_line = |text: &str| {
+            Paragraph::new()
+                .style("SourceCode")
+                .add_run(Run::new().add_text(text))
+        };
+        let docx = Docx::new()
+            .add_paragraph(
+                Paragraph::new()
+                    .add_run(Run::new().add_text("Call "))
+                    .add_run(
+                        Run::new()
+                            .add_text("a`b")
+                            .fonts(RunFonts::new().ascii("Courier New")),
+                    )
+                    .add_run(Run::new().add_text(" now.")),
+            )
+            .add_paragraph(code_line("fn main() {"))
+            .add_paragraph(code_line("    let s = \"```\";"))
+            .add_paragraph(code_line("}"))
+            .add_paragraph(Paragraph::new().add_run(Run::new().add_text("Done")))
+            // Style names that merely contain "code" or "source" are ordinary text
+            .add_paragraph(
+                Paragraph::new()
+                    .style("Barcode")
+                    .add_run(Run::new().add_text("4006381333931")),
+            )
+            .add_paragraph(
+                Paragraph::new()
+                    .style("Sources")
+                    .add_run(Run::new().add_text("Smith 2020")),
+            );
+        let path = write_fixture(docx);
+
+        let md = docx_to_markdown(path.to_str().unwrap(), true).unwrap();
+
+        assert_eq!(
+            md,
+            "Call ``a`b`` now.\n\n````\nfn main() {\n    let s = \"```\";\n}\n````\n\nDone\n\n4006381333931\n\nSmith 2020\n"
+        );
+    }
+
+    #[test]
+    fn test_docx_to_markdown_joins_split_code_runs() {
+        let mono =
+            |text: &str, font: &str| Run::new().add_text(text).fonts(RunFonts::new().ascii(font));
+        let docx = Docx::new()
+            .add_paragraph(
+                Paragraph::new()
+                    .add_run(Run::new().add_text("Run "))
+                    .add_run(mono("cargo", "Consolas"))
+                    .add_run(mono(" build", "Consolas"))
+                    .add_run(mono(" --release", "Consolas").bold())
+                    .add_run(Run::new().add_text(" now.")),
+            )
+            .add_paragraph(
+                Paragraph::new()
+                    .add_run(mono("Signed", "Monotype Corsiva"))
+                    .add_run(mono(" 0123", "Barcode")),
+            );
+        let path = write_fixture(docx);
+
+        let md = docx_to_markdown(path.to_str().unwrap(), true).unwrap();
+
+        assert_eq!(
+            md,
+            "Run `cargo build`**` --release`** now.\n\nSigned 0123\n"
+        );
+    }
+
     #[test]
     fn test_run_to_markdown_plain() {
         let run = Run::new().add_text("hello");
-        let result = run_to_markdown(&run);
+        let result = run_to_markdown(&run, true);
         assert_eq!(result, "hello");
     }
 }
@@ -0,0 +1,341 @@
+use std::io::Write;
+
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use serde::Deserialize;
+
+use super::ConversionError;
+
+/// Book metadata for the package document; anything left out is derived or omitted.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct EpubMeta {
+    /// Defaults to the first level-1 heading
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// BCP 47 tag such as `en` or `ja`; `und` (undetermined) when not given
+    pub language: Option<String>,
+}
+
+/// One XHTML file of the book: the content from one level-1 heading to the next.
+struct Chapter {
+    title: String,
+    body: String,
+}
+
+/// Write Markdown to `path` as an EPUB 3 book. Each level-1 heading starts a new
+/// chapter and the navigation document lists the chapters; the book title is
+/// `meta.title` or else the first level-1 heading. Images are referenced as-is,
+/// not embedded.
+pub fn markdown_to_epub(
+    markdown: &str,
+    path: &str,
+    meta: &EpubMeta,
+) -> Result<(), ConversionError> {
+    let chapters = split_chapters(markdown);
+    let title = meta
+        .title
+        .as_deref()
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .or_else(|| {
+            chapters
+                .iter()
+                .map(|c| c.title.as_str())
+                .find(|t| !t.is_empty())
+        })
+        .unwrap_or("Untitled")
+        .to_string();
+
+    let file = std::fs::File::create(path)
+        .map_err(|e| ConversionError(format!("Failed to create EPUB file: {}", e)))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let stored =
+        zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    let deflated = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    // The mimetype must come first and uncompressed so readers can sniff it
+    write_entry(&mut zip, "mimetype", stored, b"application/epub+zip")?;
+    write_entry(
+        &mut zip,
+        "META-INF/container.xml",
+        deflated,
+        br#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#,
+    )?;
+    write_entry(
+        &mut zip,
+        "OEBPS/content.opf",
+        deflated,
+        package_xml(markdown, &title, meta, &chapters).as_bytes(),
+    )?;
+    write_entry(
+        &mut zip,
+        "OEBPS/nav.xhtml",
+        deflated,
+        nav_xhtml(&title, &chapters).as_bytes(),
+    )?;
+    for (i, chapter) in chapters.iter().enumerate() {
+        let name = format!("OEBPS/chapter{}.xhtml", i + 1);
+        let page_title = if chapter.title.is_empty() {
+            &title
+        } else {
+            &chapter.title
+        };
+        write_entry(
+            &mut zip,
+            &name,
+            deflated,
+            xhtml_page(page_title, &chapter.body).as_bytes(),
+        )?;
+    }
+
+    zip.finish()
+        .map_err(|e| ConversionError(format!("ZIP error: {}", e)))?;
+    Ok(())
+}
+
+fn write_entry(
+    zip: &mut zip::ZipWriter<std::fs::File>,
+    name: &str,
+    options: zip::write::SimpleFileOptions,
+    content: &[u8],
+) -> Result<(), ConversionError> {
+    zip.start_file(name, options)
+        .map_err(|e| ConversionError(format!("ZIP error: {}", e)))?;
+    zip.write_all(content)
+        .map_err(|e| ConversionError(format!("ZIP write error: {}", e)))
+}
+
+/// Render the document into chapters of XHTML. Raw HTML is escaped, since it
+/// may not be well-formed XML.
+fn split_chapters(markdown: &str) -> Vec<Chapter> {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    let mut chapters: Vec<(String, Vec<Event>)> = vec![(String::new(), Vec::new())];
+    let mut in_title = false;
+    for event in Parser::new_ext(markdown, options) {
+        match &event {
+            Event::Start(Tag::Heading {
+                level: HeadingLevel::H1,
+                ..
+            }) => {
+                if !chapters.last().is_some_and(|(_, events)| events.is_empty()) {
+                    chapters.push((String::new(), Vec::new()));
+                }
+                in_title = true;
+            }
+            Event::End(TagEnd::Heading(HeadingLevel::H1)) => in_title = false,
+            Event::Text(text) | Event::Code(text) if in_title => {
+                if let Some((title, _)) = chapters.last_mut() {
+                    title.push_str(text);
+                }
+            }
+            _ => {}
+        }
+        let event = match event {
+            Event::Html(html) | Event::InlineHtml(html) => Event::Text(html),
+            other => other,
+        };
+        if let Some((_, events)) = chapters.last_mut() {
+            events.push(event);
+        }
+    }
+
+    chapters
+        .into_iter()
+        .map(|(title, events)| {
+            let mut body = String::new();
+            pulldown_cmark::html::push_html(&mut body, events.into_iter());
+            Chapter {
+                title: title.trim().to_string(),
+                body,
+            }
+        })
+        .collect()
+}
+
+fn package_xml(markdown: &str, title: &str, meta: &EpubMeta, chapters: &[Chapter]) -> String {
+    let language = meta
+        .language
+        .as_deref()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .unwrap_or("und");
+    let creator = meta
+        .author
+        .as_deref()
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(|author| format!("    <dc:creator>{}</dc:creator>\n", escape_xml(author)))
+        .unwrap_or_default();
+    let modified = chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
+
+    let mut manifest = String::from(
+        "    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n",
+    );
+    let mut spine = String::new();
+    for i in 1..=chapters.len() {
+        manifest.push_str(&format!(
+            "    <item id=\"chapter{0}\" href=\"chapter{0}.xhtml\" media-type=\"application/xhtml+xml\"/>\n",
+            i
+        ));
+        spine.push_str(&format!("    <itemref idref=\"chapter{}\"/>\n", i));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="book-id">urn:markbear:{:016x}</dc:identifier>
+    <dc:title>{}</dc:title>
+{}    <dc:language>{}</dc:language>
+    <meta property="dcterms:modified">{}</meta>
+  </metadata>
+  <manifest>
+{}  </manifest>
+  <spine>
+{}  </spine>
+</package>
+"#,
+        fnv1a(markdown.as_bytes()),
+        escape_xml(title),
+        creator,
+        escape_xml(language),
+        modified,
+        manifest,
+        spine
+    )
+}
+
+/// 64-bit FNV-1a, so the same document always gets the same book identifier
+/// (`DefaultHasher` output may change between Rust releases)
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn nav_xhtml(title: &str, chapters: &[Chapter]) -> String {
+    let mut items = String::new();
+    for (i, chapter) in chapters.iter().enumerate() {
+        let label = if chapter.title.is_empty() {
+            title
+        } else {
+            &chapter.title
+        };
+        items.push_str(&format!(
+            "      <li><a href=\"chapter{}.xhtml\">{}</a></li>\n",
+            i + 1,
+            escape_xml(label)
+        ));
+    }
+    let body = format!(
+        "  <nav epub:type=\"toc\" id=\"toc\">\n    <h1>{}</h1>\n    <ol>\n{}    </ol>\n  </nav>\n",
+        escape_xml(title),
+        items
+    );
+    xhtml_page(title, &body)
+}
+
+fn xhtml_page(title: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head>
+  <meta charset="utf-8"/>
+  <title>{}</title>
+</head>
+<body>
+{}</body>
+</html>
+"#,
+        escape_xml(title),
+        body
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn read_entry(archive: &mut zip::ZipArchive<std::fs::File>, name: &str) -> String {
+        let mut content = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        content
+    }
+
+    #[test]
+    fn test_markdown_to_epub_splits_chapters() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("epub.epub");
+        let md = "Preface text\n\n# One & Only\n\nFirst <b>chapter</b>\n\n# Two\n\n- item\n";
+        markdown_to_epub(md, path.to_str().unwrap(), &EpubMeta::default()).unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        {
+            let first = archive.by_index(0).unwrap();
+            assert_eq!(first.name(), "mimetype");
+            assert_eq!(first.compression(), zip::CompressionMethod::Stored);
+        }
+        let opf = read_entry(&mut archive, "OEBPS/content.opf");
+        assert!(opf.contains("<dc:title>One &amp; Only</dc:title>"));
+        assert!(opf.contains("<dc:language>und</dc:language>"));
+        assert!(!opf.contains("<dc:creator>"));
+        assert!(opf.contains("<itemref idref=\"chapter3\"/>"));
+
+        let nav = read_entry(&mut archive, "OEBPS/nav.xhtml");
+        assert!(nav.contains("<a href=\"chapter2.xhtml\">One &amp; Only</a>"));
+        assert!(nav.contains("<a href=\"chapter3.xhtml\">Two</a>"));
+
+        let chapter = read_entry(&mut archive, "OEBPS/chapter2.xhtml");
+        assert!(chapter.contains("<h1>One &amp; Only</h1>"));
+        assert!(chapter.contains("First &lt;b&gt;chapter&lt;/b&gt;"));
+        assert!(read_entry(&mut archive, "OEBPS/chapter1.xhtml").contains("Preface text"));
+    }
+
+    #[test]
+    fn test_markdown_to_epub_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("epub_meta.epub");
+        let meta = EpubMeta {
+            title: Some("Field Notes".to_string()),
+            author: Some("A. Writer".to_string()),
+            language: Some("ja".to_string()),
+        };
+        markdown_to_epub("# Chapter\n\nText\n", path.to_str().unwrap(), &meta).unwrap();
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let opf = read_entry(&mut archive, "OEBPS/content.opf");
+
+        assert!(opf.contains("<dc:title>Field Notes</dc:title>"));
+        assert!(opf.contains("<dc:creator>A. Writer</dc:creator>"));
+        assert!(opf.contains("<dc:language>ja</dc:language>"));
+        // The identifier only depends on the content
+        assert!(opf.contains(&format!(
+            "urn:markbear:{:016x}",
+            fnv1a(b"# Chapter\n\nText\n")
+        )));
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+}
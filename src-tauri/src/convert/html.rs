@@ -0,0 +1,310 @@
+use htmd::element_handler::{HandlerResult, Handlers};
+use htmd::options::{BulletListMarker, HrStyle, Options as HtmdOptions};
+use htmd::{Element, HtmlToMarkdown};
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+use super::ConversionError;
+
+/// Colours taken from the editor themes in `src/theme/types.ts`.
+struct HtmlTheme {
+    name: &'static str,
+    background: &'static str,
+    text: &'static str,
+    heading: &'static str,
+    muted: &'static str,
+    link: &'static str,
+    border: &'static str,
+    code_background: &'static str,
+    inline_code_background: &'static str,
+    quote_background: &'static str,
+    quote_border: &'static str,
+    table_header_background: &'static str,
+}
+
+const THEMES: &[HtmlTheme] = &[
+    HtmlTheme {
+        name: "github-light",
+        background: "#ffffff",
+        text: "#24292f",
+        heading: "#0550ae",
+        muted: "#656d76",
+        link: "#0969da",
+        border: "#d0d7de",
+        code_background: "#f6f8fa",
+        inline_code_background: "#eaeef2",
+        quote_background: "#f6f8fa",
+        quote_border: "#d0d7de",
+        table_header_background: "#f6f8fa",
+    },
+    HtmlTheme {
+        name: "github-dark",
+        background: "#0d1117",
+        text: "#e6edf3",
+        heading: "#79c0ff",
+        muted: "#8b949e",
+        link: "#58a6ff",
+        border: "#30363d",
+        code_background: "#0d1117",
+        inline_code_background: "#161b22",
+        quote_background: "#0d1117",
+        quote_border: "#30363d",
+        table_header_background: "#161b22",
+    },
+    HtmlTheme {
+        name: "dracula",
+        background: "#282a36",
+        text: "#f8f8f2",
+        heading: "#8be9fd",
+        muted: "#6272a4",
+        link: "#8be9fd",
+        border: "#44475a",
+        code_background: "#21222c",
+        inline_code_background: "#3f404b",
+        quote_background: "#1e1f29",
+        quote_border: "#8be9fd",
+        table_header_background: "#44475a",
+    },
+    HtmlTheme {
+        name: "nord-light",
+        background: "#eceff4",
+        text: "#2e3440",
+        heading: "#5e81ac",
+        muted: "#4c566a",
+        link: "#81a1c1",
+        border: "#d8dee9",
+        code_background: "#e5e9f0",
+        inline_code_background: "#d8dee9",
+        quote_background: "#d8dee9",
+        quote_border: "#81a1c1",
+        table_header_background: "#d8dee9",
+    },
+    HtmlTheme {
+        name: "nord-dark",
+        background: "#2e3440",
+        text: "#eceff4",
+        heading: "#88c0d0",
+        muted: "#81a1c1",
+        link: "#81a1c1",
+        border: "#434c5e",
+        code_background: "#2e3440",
+        inline_code_background: "#434c5e",
+        quote_background: "#3b4252",
+        quote_border: "#88c0d0",
+        table_header_background: "#3b4252",
+    },
+    HtmlTheme {
+        name: "solarized-light",
+        background: "#fdf6e3",
+        text: "#657b83",
+        heading: "#268bd2",
+        muted: "#93a1a1",
+        link: "#268bd2",
+        border: "#d6d0bf",
+        code_background: "#eee8d5",
+        inline_code_background: "#eee8d5",
+        quote_background: "#eee8d5",
+        quote_border: "#2aa198",
+        table_header_background: "#eee8d5",
+    },
+    HtmlTheme {
+        name: "solarized-dark",
+        background: "#002b36",
+        text: "#839496",
+        heading: "#268bd2",
+        muted: "#586e75",
+        link: "#268bd2",
+        border: "#073642",
+        code_background: "#002b36",
+        inline_code_background: "#073642",
+        quote_background: "#073642",
+        quote_border: "#2aa198",
+        table_header_background: "#073642",
+    },
+];
+
+/// Convert an HTML file to Markdown.
+pub fn html_to_markdown(path: &str) -> Result<String, ConversionError> {
+    let bytes =
+        std::fs::read(path).map_err(|e| ConversionError(format!("Failed to read HTML: {}", e)))?;
+    let (html, _) = crate::files::decode_text(bytes);
+    html_string_to_markdown(&html)
+}
+
+/// Convert an HTML document or fragment (e.g. pasted from a web page) to Markdown.
+/// Scripts, styles and the document head are dropped.
+pub fn html_string_to_markdown(html: &str) -> Result<String, ConversionError> {
+    let converter = HtmlToMarkdown::builder()
+        .options(HtmdOptions {
+            hr_style: HrStyle::Dashes,
+            bullet_list_marker: BulletListMarker::Dash,
+            ul_bullet_spacing: 1,
+            ol_number_spacing: 1,
+            ..HtmdOptions::default()
+        })
+        .skip_tags(vec!["head", "script", "style", "noscript"])
+        .add_handler(vec!["del", "s", "strike"], strikethrough_handler)
+        .build();
+
+    let mut markdown = converter
+        .convert(html)
+        .map_err(|e| ConversionError(format!("Failed to parse HTML: {}", e)))?;
+    if !markdown.is_empty() {
+        markdown.push('\n');
+    }
+    Ok(markdown)
+}
+
+/// `<del>`/`<s>` become GFM `~~strikethrough~~`.
+fn strikethrough_handler(handlers: &dyn Handlers, element: Element) -> Option<HandlerResult> {
+    let content = handlers.walk_children(element.node).content;
+    let trimmed = content.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    Some(format!("~~{}~~", trimmed).into())
+}
+
+/// Render Markdown as a complete HTML document. `theme` is one of the editor
+/// theme names (e.g. `github-light`); when set, matching CSS is embedded.
+pub fn markdown_to_html(markdown: &str, theme: Option<&str>) -> Result<String, ConversionError> {
+    let style = match theme {
+        Some(name) => {
+            let theme = THEMES
+                .iter()
+                .find(|t| t.name == name)
+                .ok_or_else(|| ConversionError(format!("Unknown theme: {}", name)))?;
+            format!("  <style>\n{}  </style>\n", theme_css(theme))
+        }
+        None => String::new(),
+    };
+
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    let mut body = String::new();
+    pulldown_cmark::html::push_html(&mut body, Parser::new_ext(markdown, options));
+
+    Ok(format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n  <meta charset=\"utf-8\">\n  \
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n  \
+<title>{}</title>\n{}</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(&document_title(markdown)),
+        style,
+        body
+    ))
+}
+
+/// Write Markdown to `path` as an HTML document.
+pub fn markdown_to_html_file(
+    markdown: &str,
+    path: &str,
+    theme: Option<&str>,
+) -> Result<(), ConversionError> {
+    let html = markdown_to_html(markdown, theme)?;
+    std::fs::write(path, html).map_err(|e| ConversionError(format!("Failed to write HTML: {}", e)))
+}
+
+/// Text of the first level-1 heading, or "Untitled".
+fn document_title(markdown: &str) -> String {
+    let mut in_title = false;
+    let mut title = String::new();
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(Tag::Heading {
+                level: HeadingLevel::H1,
+                ..
+            }) => in_title = true,
+            Event::End(TagEnd::Heading(HeadingLevel::H1)) if in_title => break,
+            Event::Text(text) | Event::Code(text) if in_title => title.push_str(&text),
+            _ => {}
+        }
+    }
+    if title.trim().is_empty() {
+        "Untitled".to_string()
+    } else {
+        title.trim().to_string()
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn theme_css(t: &HtmlTheme) -> String {
+    format!(
+        "    body {{ max-width: 48rem; margin: 2rem auto; padding: 0 1rem; \
+font-family: -apple-system, BlinkMacSystemFont, \"Segoe UI\", \"Noto Sans\", \"Microsoft JhengHei\", sans-serif; \
+line-height: 1.6; background: {bg}; color: {text}; }}
+    h1, h2, h3, h4, h5, h6 {{ color: {heading}; }}
+    h1, h2 {{ border-bottom: 1px solid {border}; padding-bottom: 0.3em; }}
+    a {{ color: {link}; }}
+    code {{ background: {inline_code}; padding: 0.1em 0.3em; border-radius: 4px; }}
+    pre {{ background: {code}; border: 1px solid {border}; padding: 1em; border-radius: 6px; overflow-x: auto; }}
+    pre code {{ background: none; padding: 0; }}
+    blockquote {{ margin: 0; padding: 0.5em 1em; background: {quote}; border-left: 4px solid {quote_border}; color: {muted}; }}
+    table {{ border-collapse: collapse; }}
+    th, td {{ border: 1px solid {border}; padding: 0.4em 0.8em; }}
+    th {{ background: {table_header}; }}
+    hr {{ border: none; border-top: 1px solid {border}; }}
+",
+        bg = t.background,
+        text = t.text,
+        heading = t.heading,
+        muted = t.muted,
+        link = t.link,
+        border = t.border,
+        code = t.code_background,
+        inline_code = t.inline_code_background,
+        quote = t.quote_background,
+        quote_border = t.quote_border,
+        table_header = t.table_header_background,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(markdown: &str) -> String {
+        let html = markdown_to_html(markdown, None).unwrap();
+        html_string_to_markdown(&html).unwrap()
+    }
+
+    #[test]
+    fn test_table_round_trip() {
+        let md = "| Name  | Qty |\n| ----- | --- |\n| Apple | 3   |\n| Pear  | 10  |\n";
+        assert_eq!(round_trip(md), md);
+    }
+
+    #[test]
+    fn test_code_block_round_trip() {
+        let md = "```rust\nfn main() {\n    println!(\"<hi>\");\n}\n```\n";
+        assert_eq!(round_trip(md), md);
+    }
+
+    #[test]
+    fn test_markdown_to_html_document() {
+        let html = markdown_to_html("# Notes & Ideas\n\n~~old~~ new\n", Some("nord-dark")).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>\n"));
+        assert!(html.contains("<meta charset=\"utf-8\">"));
+        assert!(html.contains("<title>Notes &amp; Ideas</title>"));
+        assert!(html.contains("background: #2e3440"));
+        assert!(html.contains("<del>old</del>"));
+        assert!(!markdown_to_html("Text", None).unwrap().contains("<style>"));
+        assert!(markdown_to_html("Text", Some("neon")).is_err());
+    }
+
+    #[test]
+    fn test_html_string_to_markdown_drops_head_and_scripts() {
+        let html = "<html><head><title>Page</title><style>p{}</style></head>\
+<body><h2>Hello</h2><script>alert(1)</script><p>Some <s>old</s> <b>text</b></p></body></html>";
+        assert_eq!(
+            html_string_to_markdown(html).unwrap(),
+            "## Hello\n\nSome ~~old~~ **text**\n"
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use pulldown_cmark::{Alignment, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+use super::ConversionError;
+
+/// Packages for the features the converter emits: links, images and strikethrough
+const PREAMBLE: &str = r"\documentclass{article}
+\usepackage[utf8]{inputenc}
+\usepackage[T1]{fontenc}
+\usepackage{hyperref}
+\usepackage{graphicx}
+\usepackage[normalem]{ulem}
+
+\begin{document}
+
+";
+
+/// Write Markdown to `path` as a standalone LaTeX document.
+pub fn markdown_to_latex_file(markdown: &str, path: &str) -> Result<(), ConversionError> {
+    std::fs::write(path, markdown_to_latex(markdown, false))
+        .map_err(|e| ConversionError(format!("Failed to write file: {}", e)))
+}
+
+/// Convert Markdown to LaTeX. Headings map to `\section` down to
+/// `\subparagraph`, emphasis to `\textbf`/`\emph`, lists to
+/// `itemize`/`enumerate` and code blocks to `verbatim`. With `fragment` only
+/// the body is returned, for pasting into an existing paper; otherwise it is
+/// wrapped in a minimal article preamble and `document` environment.
+pub fn markdown_to_latex(markdown: &str, fragment: bool) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+
+    let mut out = String::new();
+    let mut in_code_block = false;
+    // No cell written yet in the current table row
+    let mut first_cell = true;
+    let mut image_depth = 0usize;
+    // Whether each open list is an enumerate, outermost first
+    let mut lists: Vec<bool> = Vec::new();
+    // `\item` was just written and still needs the space or `{}` after it
+    let mut item_opened = false;
+
+    for event in Parser::new_ext(markdown, options) {
+        // Loose list items wrap their text in a paragraph
+        if item_opened && !matches!(event, Event::Start(Tag::Paragraph)) {
+            item_opened = false;
+            // `\item [` would read the bracket as the item's optional label
+            let starts_with_bracket = match &event {
+                Event::Text(text) => text.starts_with('['),
+                Event::TaskListMarker(_) => true,
+                _ => false,
+            };
+            out.push_str(if starts_with_bracket { "{}" } else { " " });
+        }
+        if image_depth > 0 {
+            // Alt text is dropped; the image itself was written on Start
+            match event {
+                Event::Start(Tag::Image { .. }) => image_depth += 1,
+                Event::End(TagEnd::Image) => image_depth -= 1,
+                _ => {}
+            }
+            continue;
+        }
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                out.push('\\');
+                out.push_str(sectioning_command(level));
+                out.push('{');
+            }
+            Event::End(TagEnd::Heading(_)) => out.push_str("}\n\n"),
+            Event::Start(Tag::Paragraph) => {}
+            Event::End(TagEnd::Paragraph) => out.push_str("\n\n"),
+            Event::Start(Tag::Strong) => out.push_str("\\textbf{"),
+            Event::Start(Tag::Emphasis) => out.push_str("\\emph{"),
+            Event::Start(Tag::Strikethrough) => out.push_str("\\sout{"),
+            Event::End(TagEnd::Strong | TagEnd::Emphasis | TagEnd::Strikethrough) => out.push('}'),
+            Event::Start(Tag::List(first)) => {
+                // A nested list starts on its own line after the item text
+                if !lists.is_empty() {
+                    trim_blank_lines(&mut out);
+                }
+                if !out.is_empty() && !out.ends_with('\n') {
+                    out.push('\n');
+                }
+                lists.push(first.is_some());
+                match first {
+                    Some(start) => {
+                        out.push_str("\\begin{enumerate}\n");
+                        if start != 1 {
+                            // The counter is incremented by the first \item
+                            let depth = lists.iter().filter(|&&ordered| ordered).count();
+                            out.push_str(&format!(
+                                "\\setcounter{{{}}}{{{}}}\n",
+                                enumerate_counter(depth),
+                                start.saturating_sub(1)
+                            ));
+                        }
+                    }
+                    None => out.push_str("\\begin{itemize}\n"),
+                }
+            }
+            Event::End(TagEnd::List(ordered)) => {
+                lists.pop();
+                trim_blank_lines(&mut out);
+                out.push_str(if ordered {
+                    "\\end{enumerate}\n"
+                } else {
+                    "\\end{itemize}\n"
+                });
+                out.push('\n');
+            }
+            Event::Start(Tag::Item) => {
+                trim_blank_lines(&mut out);
+                out.push_str("\\item");
+                item_opened = true;
+            }
+            Event::End(TagEnd::Item) => {
+                trim_blank_lines(&mut out);
+                out.push('\n');
+            }
+            Event::Start(Tag::BlockQuote(_)) => out.push_str("\\begin{quote}\n"),
+            Event::End(TagEnd::BlockQuote(_)) => {
+                trim_blank_lines(&mut out);
+                out.push_str("\\end{quote}\n\n");
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code_block = true;
+                out.push_str("\\begin{verbatim}\n");
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                in_code_block = false;
+                if !out.ends_with('\n') {
+                    out.push('\n');
+                }
+                out.push_str("\\end{verbatim}\n\n");
+            }
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                out.push_str(&format!("\\href{{{}}}{{", escape_url(&dest_url)));
+            }
+            Event::End(TagEnd::Link) => out.push('}'),
+            Event::Start(Tag::Image { dest_url, .. }) => {
+                out.push_str(&format!(
+                    "\\includegraphics[width=\\linewidth]{{{}}}",
+                    escape_url(&dest_url)
+                ));
+                image_depth = 1;
+            }
+            Event::Start(Tag::Table(alignments)) => {
+                let spec: String = alignments
+                    .iter()
+                    .map(|a| match a {
+                        Alignment::Center => "c",
+                        Alignment::Right => "r",
+                        _ => "l",
+                    })
+                    .collect::<Vec<_>>()
+                    .join("|");
+                out.push_str(&format!("\\begin{{tabular}}{{|{}|}}\n\\hline\n", spec));
+            }
+            Event::End(TagEnd::Table) => out.push_str("\\end{tabular}\n\n"),
+            Event::Start(Tag::TableHead | Tag::TableRow) => first_cell = true,
+            Event::End(TagEnd::TableHead | TagEnd::TableRow) => out.push_str(" \\\\\n\\hline\n"),
+            Event::Start(Tag::TableCell) => {
+                if !first_cell {
+                    out.push_str(" & ");
+                }
+                first_cell = false;
+            }
+            Event::Text(text) if in_code_block => out.push_str(&text),
+            Event::Text(text) => out.push_str(&escape_latex(&text)),
+            Event::Code(code) => out.push_str(&format!("\\texttt{{{}}}", escape_latex(&code))),
+            Event::SoftBreak => out.push('\n'),
+            Event::HardBreak => out.push_str("\\\\\n"),
+            Event::Rule => out.push_str("\\noindent\\rule{\\linewidth}{0.4pt}\n\n"),
+            Event::TaskListMarker(done) => out.push_str(if done { "[x] " } else { "[ ] " }),
+            _ => {}
+        }
+    }
+
+    let body = format!("{}\n", out.trim_end());
+    if fragment {
+        body
+    } else {
+        format!("{}{}\n\\end{{document}}\n", PREAMBLE, body)
+    }
+}
+
+fn sectioning_command(level: HeadingLevel) -> &'static str {
+    match level {
+        HeadingLevel::H1 => "section",
+        HeadingLevel::H2 => "subsection",
+        HeadingLevel::H3 => "subsubsection",
+        HeadingLevel::H4 => "paragraph",
+        HeadingLevel::H5 | HeadingLevel::H6 => "subparagraph",
+    }
+}
+
+/// Counter of an `enumerate` nested `depth` enumerates deep (1-based).
+/// LaTeX allows four levels.
+fn enumerate_counter(depth: usize) -> &'static str {
+    match depth {
+        0 | 1 => "enumi",
+        2 => "enumii",
+        3 => "enumiii",
+        _ => "enumiv",
+    }
+}
+
+/// Lists are written line by line, so paragraph breaks inside them are dropped
+fn trim_blank_lines(out: &mut String) {
+    while out.ends_with("\n\n") {
+        out.pop();
+    }
+}
+
+/// Escape the characters LaTeX treats specially in running text
+fn escape_latex(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '\\' => out.push_str("\\textbackslash{}"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// `\href` and `\includegraphics` take URLs mostly verbatim; only `%` and `#`
+/// need escaping
+fn escape_url(url: &str) -> String {
+    url.replace('%', "\\%").replace('#', "\\#")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escapes_special_characters() {
+        let md = r"# Costs & 50% off
+
+Price $5 for item_#1 {new} ~ ^ \ done, `a_b` and **bold**.
+";
+        assert_eq!(
+            markdown_to_latex(md, true),
+            concat!(
+                "\\section{Costs \\& 50\\% off}\n\n",
+                "Price \\$5 for item\\_\\#1 \\{new\\} \\textasciitilde{} \\textasciicircum{} ",
+                "\\textbackslash{} done, \\texttt{a\\_b} and \\textbf{bold}.\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_nested_lists() {
+        let md = "- one\n- two\n  1. first\n  2. second\n- three\n";
+        assert_eq!(
+            markdown_to_latex(md, true),
+            concat!(
+                "\\begin{itemize}\n",
+                "\\item one\n",
+                "\\item two\n",
+                "\\begin{enumerate}\n",
+                "\\item first\n",
+                "\\item second\n",
+                "\\end{enumerate}\n",
+                "\\item three\n",
+                "\\end{itemize}\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_nested_enumerate_start_and_bracketed_items() {
+        let md = "3. three\n\n   5. five\n\n- [draft] notes\n- [ ] todo\n\n![chart](img/50%.png)\n";
+        assert_eq!(
+            markdown_to_latex(md, true),
+            concat!(
+                "\\begin{enumerate}\n",
+                "\\setcounter{enumi}{2}\n",
+                "\\item three\n",
+                "\\begin{enumerate}\n",
+                "\\setcounter{enumii}{4}\n",
+                "\\item five\n",
+                "\\end{enumerate}\n",
+                "\\end{enumerate}\n\n",
+                "\\begin{itemize}\n",
+                "\\item{}[draft] notes\n",
+                "\\item{}[ ] todo\n",
+                "\\end{itemize}\n\n",
+                "\\includegraphics[width=\\linewidth]{img/50\\%.png}\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_standalone_document_and_code_block() {
+        let latex = markdown_to_latex("## Code\n\n```\nlet x = a_b & c;\n```\n", false);
+        assert!(latex.starts_with("\\documentclass{article}\n"));
+        assert!(latex.contains(
+            "\\subsection{Code}\n\n\\begin{verbatim}\nlet x = a_b & c;\n\\end{verbatim}\n"
+        ));
+        assert!(latex.ends_with("\\end{document}\n"));
+    }
+}
@@ -1,9 +1,42 @@
 use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
+use serde::Serialize;
+
+pub mod csv;
 pub mod docx;
+pub mod epub;
+pub mod html;
+pub mod latex;
+pub mod odt;
 pub mod xlsx;
 pub mod pdf;
 pub mod pptx;
+pub mod rtf;
+
+/// Appended after an imported table whose merged cells were flattened
+const MERGED_CELLS_NOTE: &str =
+    "\n> **Note**: Merged cells were flattened; their text is repeated in each cell they covered.\n";
+
+/// Make text safe to write between the `|` delimiters of a GFM table row:
+/// pipes are escaped and line breaks become `<br>`.
+fn escape_table_cell(text: &str) -> String {
+    text.replace('|', "\\|")
+        .replace("\r\n", "\n")
+        .replace('\n', "<br>")
+}
+
+/// Whether inline HTML is the `<br>` that [`escape_table_cell`] writes for a
+/// line break inside a cell
+fn is_cell_line_break(html: &str) -> bool {
+    matches!(
+        html.trim().to_lowercase().as_str(),
+        "<br>" | "<br/>" | "<br />"
+    )
+}
 
 #[derive(Debug)]
 pub struct ConversionError(pub String);
@@ -31,3 +64,657 @@ impl From<&str> for ConversionError {
         ConversionError(s.to_string())
     }
 }
+
+/// Compound File (OLE2) header: legacy Office formats, and the wrapper around
+/// password-protected Word, Excel and PowerPoint files
+const OLE2_MAGIC: &[u8] = b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1";
+
+/// Returned for encrypted Office files; decrypting them isn't supported
+const PASSWORD_PROTECTED: &str = "This document is password-protected and can't be imported";
+
+/// How files of each importable binary format begin, and what that makes them
+fn signature(extension: &str) -> Option<(&'static [u8], &'static str)> {
+    match extension {
+        "docx" | "xlsx" | "pptx" | "odt" | "ods" => Some((b"PK\x03\x04", "a ZIP archive")),
+        "xls" => Some((OLE2_MAGIC, "an Excel 97-2003 workbook")),
+        "pdf" => Some((b"%PDF", "a PDF")),
+        "rtf" => Some((b"{\\rtf", "an RTF document")),
+        _ => None,
+    }
+}
+
+/// Fail early with a readable message when `path` doesn't start the way a
+/// `.{extension}` file must, e.g. a renamed text file or a password-protected
+/// document, rather than letting the full parser report something cryptic.
+pub(crate) fn check_signature(path: &str, extension: &str) -> Result<(), ConversionError> {
+    let Some((magic, kind)) = signature(extension) else {
+        return Ok(());
+    };
+    let read_error = |e: std::io::Error| ConversionError(format!("Failed to read file: {}", e));
+    let mut head = Vec::with_capacity(OLE2_MAGIC.len());
+    std::fs::File::open(path)
+        .and_then(|file| file.take(OLE2_MAGIC.len() as u64).read_to_end(&mut head))
+        .map_err(read_error)?;
+    if head.starts_with(magic) {
+        return Ok(());
+    }
+    if matches!(extension, "docx" | "xlsx" | "pptx")
+        && head == OLE2_MAGIC
+        && has_encrypted_package(&std::fs::read(path).map_err(read_error)?)
+    {
+        return Err(ConversionError(PASSWORD_PROTECTED.to_string()));
+    }
+    Err(invalid_file(extension, format!("it isn't {}", kind)))
+}
+
+/// Whether a compound file has the `EncryptedPackage` stream in which Office
+/// stores the encrypted ZIP. Stream names are UTF-16LE in the directory.
+fn has_encrypted_package(bytes: &[u8]) -> bool {
+    let name: Vec<u8> = "EncryptedPackage"
+        .encode_utf16()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    bytes.windows(name.len()).any(|window| window == name)
+}
+
+/// The error for a file that isn't what its extension claims, with the
+/// underlying cause kept in parentheses for debugging
+pub(crate) fn invalid_file(extension: &str, cause: impl fmt::Display) -> ConversionError {
+    ConversionError(format!(
+        "This file doesn't look like a valid .{} ({})",
+        extension, cause
+    ))
+}
+
+/// Lowercased extension of `path` without the leading dot, if it has one.
+pub fn extension_of(path: &str) -> Option<String> {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+}
+
+/// Convert any supported document to Markdown, picking the importer from the
+/// file extension. Markdown files are read through unchanged.
+pub fn import_to_markdown(path: &str) -> Result<String, ConversionError> {
+    match extension_of(path) {
+        Some(ext) => import_with_format(path, &ext),
+        None => Err(ConversionError(
+            "Unsupported format: file has no extension".to_string(),
+        )),
+    }
+}
+
+/// Shown for presentations, which have no notice of their own in the Markdown
+const PPTX_IMPORT_WARNING: &str = "Slides were imported as text; layout and images are not kept.";
+
+/// Caveats about an import worth telling the user: the `> **Import Notice**`
+/// and `> **Note**` lines importers add to the Markdown, plus a fixed warning
+/// for formats that are only converted on a best-effort basis.
+pub fn import_warnings(format: &str, markdown: &str) -> Vec<String> {
+    let mut warnings: Vec<String> = Vec::new();
+    if format == "pptx" {
+        warnings.push(PPTX_IMPORT_WARNING.to_string());
+    }
+    for line in markdown.lines() {
+        let notice = line
+            .strip_prefix("> **Import Notice**:")
+            .or_else(|| line.strip_prefix("> **Note**:"));
+        if let Some(text) = notice {
+            let text = text.trim().to_string();
+            if !text.is_empty() && !warnings.contains(&text) {
+                warnings.push(text);
+            }
+        }
+    }
+    warnings
+}
+
+/// Format-specific import settings; each importer only reads its own.
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    /// Discard underline instead of writing `<u>` tags (DOCX)
+    pub drop_underline: bool,
+    /// Progress reporting and cancellation (PDF, spreadsheets)
+    pub control: ImportControl,
+}
+
+/// How far a long import has got
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ImportProgress {
+    /// "pages" for PDF, "rows" for spreadsheets
+    pub unit: &'static str,
+    pub done: usize,
+    /// Known up front for PDF; spreadsheets only learn each sheet's size as it loads
+    pub total: Option<usize>,
+}
+
+/// Lets the caller follow a long import and stop it. Importers check for
+/// cancellation between pages, sheets or blocks of rows and then fail with
+/// "Import cancelled".
+#[derive(Clone, Default)]
+pub struct ImportControl {
+    pub cancel: Arc<AtomicBool>,
+    pub progress: Option<Arc<dyn Fn(ImportProgress) + Send + Sync>>,
+}
+
+impl ImportControl {
+    pub fn report(&self, unit: &'static str, done: usize, total: Option<usize>) {
+        if let Some(progress) = &self.progress {
+            progress(ImportProgress { unit, done, total });
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    /// `Err` once cancellation was requested, for use with `?`
+    pub fn check_cancelled(&self) -> Result<(), ConversionError> {
+        if self.is_cancelled() {
+            Err(ConversionError("Import cancelled".to_string()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl fmt::Debug for ImportControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImportControl")
+            .field("cancelled", &self.is_cancelled())
+            .field("progress", &self.progress.is_some())
+            .finish()
+    }
+}
+
+/// Convert `path` to Markdown using the importer for an explicit `format`.
+pub fn import_with_format(path: &str, format: &str) -> Result<String, ConversionError> {
+    import_with_options(path, format, &ImportOptions::default())
+}
+
+/// [`import_with_format`] with non-default importer settings.
+pub fn import_with_options(
+    path: &str,
+    format: &str,
+    options: &ImportOptions,
+) -> Result<String, ConversionError> {
+    match format {
+        "docx" => docx::docx_to_markdown(path, !options.drop_underline),
+        "csv" => csv::csv_to_markdown(path, None),
+        // The spreadsheet import dialog offers .csv files too
+        "xlsx" | "xls" | "ods" if extension_of(path).as_deref() == Some("csv") => {
+            csv::csv_to_markdown(path, None)
+        }
+        "xlsx" | "xls" | "ods" => xlsx::xlsx_to_markdown_with_control(
+            path,
+            Some(xlsx::DEFAULT_MAX_ROWS_PER_SHEET),
+            &options.control,
+        ),
+        "pptx" => pptx::pptx_to_markdown(path),
+        "pdf" => {
+            pdf::pdf_to_markdown_with_control(path, pdf::PageSeparator::None, &options.control)
+        }
+        "html" | "htm" => html::html_to_markdown(path),
+        "rtf" => rtf::rtf_to_markdown(path),
+        "odt" => odt::odt_to_markdown(path),
+        "md" | "markdown" => std::fs::read_to_string(path)
+            .map_err(|e| ConversionError(format!("Failed to read file: {}", e))),
+        other => Err(ConversionError(format!("Unsupported format: .{}", other))),
+    }
+}
+
+/// Format-specific export settings; each exporter only reads its own.
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Page breaks for DOCX
+    pub docx: Option<docx::DocxOptions>,
+    /// Page layout for PDF
+    pub pdf: Option<pdf::PdfOptions>,
+    /// Frozen header and filters for XLSX
+    pub xlsx: Option<xlsx::XlsxOptions>,
+    /// Title, author and language for EPUB
+    pub epub: Option<epub::EpubMeta>,
+    /// Body font for PDF, as a file path or system font name
+    pub font: Option<String>,
+    /// Editor theme whose colours are embedded in HTML
+    pub theme: Option<String>,
+    /// Export only this byte range of the Markdown, widened to whole lines
+    pub byte_range: Option<(usize, usize)>,
+    /// Which table (0-based) goes into a CSV file; the first by default
+    pub table_index: Option<usize>,
+}
+
+/// Write Markdown to `path` using the exporter for `format`.
+pub fn export_with_format(
+    markdown: &str,
+    path: &str,
+    format: &str,
+    options: &ExportOptions,
+) -> Result<(), ConversionError> {
+    let markdown = match options.byte_range {
+        Some(range) => select_lines(markdown, range)?,
+        None => markdown,
+    };
+    match format {
+        "docx" => docx::markdown_to_docx_with_options(
+            markdown,
+            path,
+            &options.docx.clone().unwrap_or_default(),
+        ),
+        "csv" => csv::markdown_to_csv(markdown, path, options.table_index.unwrap_or(0)),
+        "xlsx" => xlsx::markdown_to_xlsx_with_options(
+            markdown,
+            path,
+            &options.xlsx.clone().unwrap_or_default(),
+        ),
+        "pptx" => pptx::markdown_to_pptx(markdown, path),
+        "pdf" => pdf::markdown_to_pdf_with_options(
+            markdown,
+            path,
+            &options.pdf.clone().unwrap_or_default(),
+            options.font.as_deref(),
+        ),
+        "html" | "htm" => html::markdown_to_html_file(markdown, path, options.theme.as_deref()),
+        "epub" => epub::markdown_to_epub(markdown, path, &options.epub.clone().unwrap_or_default()),
+        "odt" => odt::markdown_to_odt(markdown, path),
+        "tex" | "latex" => latex::markdown_to_latex_file(markdown, path),
+        other => Err(ConversionError(format!("Unsupported format: .{}", other))),
+    }
+}
+
+/// The lines of `markdown` touched by the byte range `start..end`, e.g. a
+/// section between two outline offsets
+pub fn select_lines(markdown: &str, (start, end): (usize, usize)) -> Result<&str, ConversionError> {
+    if start > end || end > markdown.len() {
+        return Err(ConversionError(format!(
+            "Byte range {}..{} is outside the document ({} bytes)",
+            start,
+            end,
+            markdown.len()
+        )));
+    }
+    // Line breaks are ASCII, so the byte after one is always a character boundary
+    let bytes = markdown.as_bytes();
+    let line_start = bytes[..start]
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |i| i + 1);
+    let line_end = if end == line_start || bytes[..end].ends_with(b"\n") {
+        end
+    } else {
+        bytes[end..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(bytes.len(), |i| end + i + 1)
+    };
+    Ok(&markdown[line_start..line_end])
+}
+
+/// Convert the document at `path` to `target_format`, writing it into
+/// `output_dir` under the same file stem. Returns the path written.
+pub fn convert_file(
+    path: &str,
+    target_format: &str,
+    output_dir: &Path,
+) -> Result<PathBuf, ConversionError> {
+    let target = target_format.trim().trim_start_matches('.').to_lowercase();
+    let stem = Path::new(path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .ok_or_else(|| ConversionError(format!("Invalid file name: {}", path)))?;
+    let output = output_dir.join(format!("{}.{}", stem, target));
+
+    let same_file = match (Path::new(path).canonicalize(), output.canonicalize()) {
+        (Ok(source), Ok(dest)) => source == dest,
+        _ => false,
+    };
+    if same_file {
+        return Err(ConversionError(
+            "Output would overwrite the source file".to_string(),
+        ));
+    }
+
+    let markdown = import_to_markdown(path)?;
+    let output_str = output.to_string_lossy();
+    match target.as_str() {
+        "md" | "markdown" => std::fs::write(&output, markdown)
+            .map_err(|e| ConversionError(format!("Failed to write file: {}", e)))?,
+        _ => export_with_format(&markdown, &output_str, &target, &ExportOptions::default())?,
+    }
+    Ok(output)
+}
+
+/// Progress through the notes of a folder export
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct FolderProgress {
+    pub done: usize,
+    pub total: usize,
+    /// The note just added
+    pub path: String,
+}
+
+/// Lists the notes of a folder export, in order, when placed in its root
+const MANIFEST_FILE: &str = "manifest.md";
+
+/// The notes under `root` in export order: the Markdown files `manifest.md`
+/// links to, in the order it links them, or else every Markdown file sorted by
+/// path. Manifest links to other files or to anything outside `root` are skipped.
+pub fn folder_notes(root: &Path) -> Result<Vec<PathBuf>, ConversionError> {
+    let manifest_path = root.join(MANIFEST_FILE);
+    if let Ok(manifest) = std::fs::read_to_string(&manifest_path) {
+        let root = crate::files::resolve_path(root).map_err(ConversionError)?;
+        let mut notes: Vec<PathBuf> = Vec::new();
+        for status in crate::links::check_links(&manifest_path.to_string_lossy(), &manifest) {
+            let Some(target) = status.target.filter(|_| status.exists) else {
+                continue;
+            };
+            if !crate::files::is_markdown_file(&target) {
+                continue;
+            }
+            if let Ok(target) = crate::files::resolve_path(Path::new(&target)) {
+                if target.starts_with(&root) && !notes.contains(&target) {
+                    notes.push(target);
+                }
+            }
+        }
+        return Ok(notes);
+    }
+
+    let mut notes: Vec<PathBuf> = crate::files::markdown_files(root)
+        .map_err(ConversionError)?
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+    notes.sort();
+    Ok(notes)
+}
+
+/// Concatenate the notes under `root` into one document, separated by
+/// horizontal rules. Front matter is dropped, and a note that doesn't open
+/// with a heading gets one from its file name. `progress` is called after
+/// each note.
+pub fn combine_folder(
+    root: &Path,
+    mut progress: impl FnMut(FolderProgress),
+) -> Result<String, ConversionError> {
+    let notes = folder_notes(root)?;
+    if notes.is_empty() {
+        return Err(ConversionError(format!(
+            "No Markdown files to export in {}",
+            root.display()
+        )));
+    }
+
+    let mut combined = String::new();
+    for (idx, note) in notes.iter().enumerate() {
+        let bytes = std::fs::read(note)
+            .map_err(|e| ConversionError(format!("Failed to read {}: {}", note.display(), e)))?;
+        let (text, _) = crate::files::decode_text(bytes);
+        let body = match crate::markdown::parse_front_matter(&text) {
+            Ok((_, body)) => body,
+            Err(_) => text,
+        };
+
+        if idx > 0 {
+            combined.push_str("\n\n---\n\n");
+        }
+        if !starts_with_heading(&body) {
+            let title = note
+                .file_stem()
+                .map(|s| s.to_string_lossy())
+                .unwrap_or_default();
+            combined.push_str(&format!("# {}\n\n", title));
+        }
+        combined.push_str(body.trim());
+
+        progress(FolderProgress {
+            done: idx + 1,
+            total: notes.len(),
+            path: note.to_string_lossy().into_owned(),
+        });
+    }
+    combined.push('\n');
+    Ok(combined)
+}
+
+/// Whether the Markdown opens with a heading; `#tag` and the like don't count
+fn starts_with_heading(markdown: &str) -> bool {
+    matches!(
+        pulldown_cmark::Parser::new_ext(markdown, crate::markdown::parser_options()).next(),
+        Some(pulldown_cmark::Event::Start(
+            pulldown_cmark::Tag::Heading { .. }
+        ))
+    )
+}
+
+/// Export the notes under `root` as a single document at `target`.
+pub fn export_folder(
+    root: &Path,
+    target: &str,
+    format: &str,
+    progress: impl FnMut(FolderProgress),
+) -> Result<(), ConversionError> {
+    let markdown = combine_folder(root, progress)?;
+    // Each note starts on a new page where the format has pages
+    let options = ExportOptions {
+        docx: Some(docx::DocxOptions {
+            page_break_on_rule: true,
+        }),
+        ..Default::default()
+    };
+    export_with_format(&markdown, target, format, &options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_to_markdown_reads_markdown_through() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("passthrough.md");
+        std::fs::write(&path, "# Title\n\nBody\n").unwrap();
+        let result = import_to_markdown(path.to_str().unwrap()).unwrap();
+        assert_eq!(result, "# Title\n\nBody\n");
+    }
+
+    #[test]
+    fn test_import_to_markdown_rejects_unknown_extension() {
+        let err = import_to_markdown("/tmp/notes.XYZ").unwrap_err();
+        assert_eq!(err.0, "Unsupported format: .xyz");
+    }
+
+    #[test]
+    fn test_import_warnings_collects_notices_once() {
+        let md = "> **Import Notice**: This PDF was imported as plain text.\n\n\
+                  | A |\n| - |\n| 1 |\n\n> **Note**: Merged cells were flattened.\n\n\
+                  | B |\n| - |\n| 2 |\n\n> **Note**: Merged cells were flattened.\n";
+        assert_eq!(
+            import_warnings("pdf", md),
+            vec![
+                "This PDF was imported as plain text.",
+                "Merged cells were flattened."
+            ]
+        );
+        assert_eq!(
+            import_warnings("pptx", "# Slide\n"),
+            ["Slides were imported as text; layout and images are not kept."]
+        );
+        assert!(import_warnings("docx", "# Title\n").is_empty());
+    }
+
+    #[test]
+    fn test_select_lines_snaps_to_line_boundaries() {
+        let md = "# One\ntext\n## Two\nmore\n";
+        assert_eq!(select_lines(md, (8, 15)).unwrap(), "text\n## Two\n");
+        assert_eq!(select_lines(md, (6, 11)).unwrap(), "text\n");
+        assert_eq!(select_lines(md, (0, md.len())).unwrap(), md);
+        assert!(select_lines(md, (4, 2)).is_err());
+        assert!(select_lines(md, (0, md.len() + 1)).is_err());
+    }
+
+    #[test]
+    fn test_export_byte_range_to_docx() {
+        let md = "# Intro\n\nFirst.\n\n# Details\n\nSecond.\n\n## More\n\nThird.\n\n# Appendix\n\nLast.\n";
+        let start = md.find("# Details").unwrap();
+        let end = md.find("# Appendix").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("export_range.docx");
+        let path_str = path.to_str().unwrap();
+
+        let options = ExportOptions {
+            byte_range: Some((start + 3, end)),
+            ..Default::default()
+        };
+        export_with_format(md, path_str, "docx", &options).unwrap();
+        let exported = import_to_markdown(path_str).unwrap();
+
+        let headings: Vec<&str> = exported
+            .lines()
+            .filter(|line| line.starts_with('#'))
+            .collect();
+        assert_eq!(headings, ["# Details", "## More"]);
+
+        let options = ExportOptions {
+            byte_range: Some((0, md.len() + 10)),
+            ..Default::default()
+        };
+        assert!(export_with_format(md, path_str, "docx", &options).is_err());
+    }
+
+    #[test]
+    fn test_importers_reject_text_files_with_binary_extensions() {
+        let dir = tempfile::tempdir().unwrap();
+        for extension in ["docx", "xlsx", "xls", "pptx", "pdf", "odt", "rtf"] {
+            let path = dir.path().join(format!("not_really.{}", extension));
+            std::fs::write(&path, "just some text\n").unwrap();
+            let err = import_to_markdown(path.to_str().unwrap()).unwrap_err();
+            assert!(
+                err.0.starts_with(&format!(
+                    "This file doesn't look like a valid .{} (it isn't ",
+                    extension
+                )),
+                "{}: {}",
+                extension,
+                err.0
+            );
+        }
+    }
+
+    #[test]
+    fn test_password_protected_office_files_are_detected() {
+        // Compound file header followed by a directory entry naming the encrypted stream
+        let mut fixture = OLE2_MAGIC.to_vec();
+        fixture.resize(512, 0);
+        fixture.extend("EncryptedPackage".encode_utf16().flat_map(u16::to_le_bytes));
+        fixture.resize(1024, 0);
+
+        let dir = tempfile::tempdir().unwrap();
+        for extension in ["docx", "xlsx", "pptx"] {
+            let path = dir.path().join(format!("encrypted.{}", extension));
+            std::fs::write(&path, &fixture).unwrap();
+            let err = import_to_markdown(path.to_str().unwrap()).unwrap_err();
+            assert_eq!(err.0, PASSWORD_PROTECTED);
+        }
+
+        // A compound file without the stream is just the wrong format
+        let path = dir.path().join("legacy.docx");
+        std::fs::write(&path, &fixture[..512]).unwrap();
+        let err = import_to_markdown(path.to_str().unwrap()).unwrap_err();
+        assert_eq!(
+            err.0,
+            "This file doesn't look like a valid .docx (it isn't a ZIP archive)"
+        );
+    }
+
+    #[test]
+    fn test_combine_folder_titles_notes_without_a_heading() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("a-ideas.md"), "#draft #later\n\nMaybe.\n").unwrap();
+        std::fs::write(dir.path().join("b-plan.md"), "Plan\n====\n\nSteps.\n").unwrap();
+        let combined = combine_folder(dir.path(), |_| {}).unwrap();
+        assert_eq!(
+            combined,
+            "# a-ideas\n\n#draft #later\n\nMaybe.\n\n---\n\nPlan\n====\n\nSteps.\n"
+        );
+    }
+
+    #[test]
+    fn test_export_folder_combines_notes_into_one_docx() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().join("notes");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("b-methods.md"),
+            "---\ntags: [draft]\n---\nWe measured twice.\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("a-intro.md"),
+            "# Introduction\n\nWhy this matters.\n",
+        )
+        .unwrap();
+        let target = temp.path().join("export.docx");
+
+        let mut reports = Vec::new();
+        export_folder(&dir, target.to_str().unwrap(), "docx", |p| {
+            reports.push((p.done, p.total))
+        })
+        .unwrap();
+        let exported = import_to_markdown(target.to_str().unwrap()).unwrap();
+
+        assert_eq!(reports, [(1, 2), (2, 2)]);
+        let intro = exported.find("# Introduction").unwrap();
+        let methods = exported.find("# b-methods").unwrap();
+        assert!(intro < exported.find("Why this matters.").unwrap());
+        assert!(intro < methods && methods < exported.find("We measured twice.").unwrap());
+        assert!(!exported.contains("tags"));
+
+        // A manifest picks and orders the notes, skipping links to other files and to notes outside the folder
+        std::fs::write(dir.join("picture.png"), "not a note").unwrap();
+        std::fs::write(temp.path().join("outside.md"), "# Outside\n").unwrap();
+        let manifest =
+            "- [Methods](b-methods.md)\n- [Picture](picture.png)\n- [Outside](../outside.md)\n";
+        std::fs::write(dir.join("manifest.md"), manifest).unwrap();
+        let combined = combine_folder(&dir, |_| {}).unwrap();
+        assert_eq!(combined, "# b-methods\n\nWe measured twice.\n");
+
+        let empty = dir.join("empty");
+        std::fs::create_dir_all(&empty).unwrap();
+        assert!(combine_folder(&empty, |_| {})
+            .unwrap_err()
+            .0
+            .starts_with("No Markdown files to export"));
+    }
+
+    #[test]
+    fn test_convert_file_writes_into_output_dir() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let source = dir.join("notes.md");
+        std::fs::write(&source, "# Notes\n\n| A | B |\n| - | - |\n| 1 | 2 |\n").unwrap();
+        let out_dir = dir.join("out");
+        std::fs::create_dir_all(&out_dir).unwrap();
+
+        let output = convert_file(source.to_str().unwrap(), ".DOCX", &out_dir).unwrap();
+        assert_eq!(output, out_dir.join("notes.docx"));
+        assert!(import_to_markdown(output.to_str().unwrap())
+            .unwrap()
+            .contains("# Notes"));
+
+        let err = convert_file(source.to_str().unwrap(), "md", dir).unwrap_err();
+        assert_eq!(err.0, "Output would overwrite the source file");
+    }
+
+    #[test]
+    fn test_escape_table_cell() {
+        assert_eq!(escape_table_cell("a | b"), "a \\| b");
+        assert_eq!(
+            escape_table_cell("first\r\nsecond\nthird"),
+            "first<br>second<br>third"
+        );
+        assert!(is_cell_line_break("<BR />"));
+        assert!(!is_cell_line_break("<b>"));
+    }
+}
@@ -0,0 +1,1012 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+use super::{check_signature, escape_table_cell, invalid_file, ConversionError, MERGED_CELLS_NOTE};
+
+/// Automatic text styles written by the exporter for emphasis
+const BOLD_STYLE: &str = "T_Bold";
+const ITALIC_STYLE: &str = "T_Italic";
+const BOLD_ITALIC_STYLE: &str = "T_BoldItalic";
+/// Paragraph style for code block lines
+const CODE_STYLE: &str = "Preformatted_20_Text";
+/// Paragraph styles for body text and block quotes
+const BODY_STYLE: &str = "Text_20_body";
+const QUOTE_STYLE: &str = "Quotations";
+/// List styles for bulleted and numbered lists
+const BULLET_LIST_STYLE: &str = "List_20_Bullet";
+const NUMBER_LIST_STYLE: &str = "List_20_Number";
+/// List nesting levels given their own indentation and marker
+const LIST_LEVELS: usize = 10;
+/// Most copies of one table cell read from `table:number-columns-repeated`
+const MAX_REPEATED_CELLS: usize = 1024;
+
+/// Character formatting of a run of text
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Format {
+    bold: bool,
+    italic: bool,
+}
+
+/// Convert an ODT (OpenDocument Text) file to Markdown.
+///
+/// Headings (`text:h` with `text:outline-level`), paragraphs, bulleted and
+/// numbered lists, tables and bold/italic spans are kept. Tables become pipe
+/// tables, with merged cells flattened like the DOCX importer does. Images,
+/// frames, notes and tracked changes are dropped.
+pub fn odt_to_markdown(path: &str) -> Result<String, ConversionError> {
+    check_signature(path, "odt")?;
+    let file = std::fs::File::open(path)
+        .map_err(|e| ConversionError(format!("Failed to open ODT: {}", e)))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| invalid_file("odt", format!("Failed to read ODT archive: {}", e)))?;
+
+    let content = read_entry(&mut archive, "content.xml")?.ok_or_else(|| {
+        ConversionError("Failed to parse ODT: content.xml is missing".to_string())
+    })?;
+    let mut styles = text_styles(&content);
+    let mut numbered_lists = numbered_list_levels(&content);
+    // Named styles (e.g. "Strong_20_Emphasis", "List_20_Number") live in styles.xml
+    if let Some(named) = read_entry(&mut archive, "styles.xml")? {
+        for (name, format) in text_styles(&named) {
+            styles.entry(name).or_insert(format);
+        }
+        for (name, levels) in numbered_list_levels(&named) {
+            numbered_lists.entry(name).or_insert(levels);
+        }
+    }
+    Ok(content_to_markdown(&content, &styles, &numbered_lists))
+}
+
+/// Write Markdown to `path` as an ODT document.
+pub fn markdown_to_odt(markdown: &str, path: &str) -> Result<(), ConversionError> {
+    let file = std::fs::File::create(path)
+        .map_err(|e| ConversionError(format!("Failed to create ODT file: {}", e)))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let stored =
+        zip::write::SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    let deflated = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    // The mimetype must come first and uncompressed so readers can sniff it
+    write_entry(
+        &mut zip,
+        "mimetype",
+        stored,
+        b"application/vnd.oasis.opendocument.text",
+    )?;
+    write_entry(
+        &mut zip,
+        "META-INF/manifest.xml",
+        deflated,
+        br#"<?xml version="1.0" encoding="UTF-8"?>
+<manifest:manifest xmlns:manifest="urn:oasis:names:tc:opendocument:xmlns:manifest:1.0" manifest:version="1.2">
+  <manifest:file-entry manifest:full-path="/" manifest:media-type="application/vnd.oasis.opendocument.text"/>
+  <manifest:file-entry manifest:full-path="content.xml" manifest:media-type="text/xml"/>
+  <manifest:file-entry manifest:full-path="styles.xml" manifest:media-type="text/xml"/>
+</manifest:manifest>"#,
+    )?;
+    write_entry(&mut zip, "styles.xml", deflated, styles_xml().as_bytes())?;
+    write_entry(
+        &mut zip,
+        "content.xml",
+        deflated,
+        content_xml(markdown).as_bytes(),
+    )?;
+
+    zip.finish()
+        .map_err(|e| ConversionError(format!("ZIP error: {}", e)))?;
+    Ok(())
+}
+
+type OdtArchive = zip::ZipArchive<std::fs::File>;
+
+/// Read an archive entry as text, or `None` if the package has no such part.
+fn read_entry(archive: &mut OdtArchive, name: &str) -> Result<Option<String>, ConversionError> {
+    let mut entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => {
+            return Err(ConversionError(format!(
+                "Failed to read archive entry: {}",
+                e
+            )))
+        }
+    };
+    let mut content = String::new();
+    entry
+        .read_to_string(&mut content)
+        .map_err(|e| ConversionError(format!("Failed to read {}: {}", name, e)))?;
+    Ok(Some(content))
+}
+
+fn write_entry(
+    zip: &mut zip::ZipWriter<std::fs::File>,
+    name: &str,
+    options: zip::write::SimpleFileOptions,
+    content: &[u8],
+) -> Result<(), ConversionError> {
+    zip.start_file(name, options)
+        .map_err(|e| ConversionError(format!("ZIP error: {}", e)))?;
+    zip.write_all(content)
+        .map_err(|e| ConversionError(format!("ZIP write error: {}", e)))
+}
+
+/// Bold/italic of each style that sets them, keyed by style name
+fn text_styles(xml: &str) -> HashMap<String, Format> {
+    let mut styles = HashMap::new();
+    for style in xml.split("<style:style ").skip(1) {
+        let Some(name) = xml_attr(style, "style:name") else {
+            continue;
+        };
+        let body = &style[..style.find("</style:style>").unwrap_or(style.len())];
+        let Some(start) = body.find("<style:text-properties ") else {
+            continue;
+        };
+        let props = &body[start + "<style:text-properties ".len()..];
+        let format = Format {
+            bold: xml_attr(props, "fo:font-weight")
+                .is_some_and(|w| w == "bold" || w.parse::<u32>().is_ok_and(|n| n >= 600)),
+            italic: xml_attr(props, "fo:font-style")
+                .is_some_and(|s| s == "italic" || s == "oblique"),
+        };
+        if format != Format::default() {
+            styles.insert(name, format);
+        }
+    }
+    styles
+}
+
+/// The numbered levels (1-based) of each list style that has any, keyed by
+/// style name
+fn numbered_list_levels(xml: &str) -> HashMap<String, Vec<usize>> {
+    let mut lists = HashMap::new();
+    for style in xml.split("<text:list-style ").skip(1) {
+        let Some(name) = xml_attr(style, "style:name") else {
+            continue;
+        };
+        let body = &style[..style.find("</text:list-style>").unwrap_or(style.len())];
+        let levels: Vec<usize> = body
+            .split("<text:list-level-style-number ")
+            .skip(1)
+            .filter_map(|level| xml_attr(level, "text:level")?.parse().ok())
+            .collect();
+        if !levels.is_empty() {
+            lists.insert(name, levels);
+        }
+    }
+    lists
+}
+
+/// An open `text:list`
+struct List {
+    /// List style, inherited from the enclosing list when not given
+    style: Option<String>,
+    numbered: bool,
+    /// Number of the current item
+    number: u64,
+    /// Whether the current item has had no text yet, so its next paragraph
+    /// gets the marker
+    item_start: bool,
+    /// Spaces in front of this list's markers
+    indent: String,
+}
+
+impl List {
+    fn marker(&self) -> String {
+        if self.numbered {
+            format!("{}. ", self.number)
+        } else {
+            "- ".to_string()
+        }
+    }
+}
+
+/// A `table:table` being read
+#[derive(Default)]
+struct Table {
+    rows: Vec<Vec<String>>,
+    row: Vec<String>,
+    /// Paragraphs of the open cell
+    cell: Vec<String>,
+    /// Columns spanned and times repeated of the open cell
+    cell_shape: (usize, usize),
+    /// Covered cells in this row that belong to the last column span
+    spanned: usize,
+    /// Whether merged cells were flattened
+    flattened: bool,
+    /// Tables nested in a cell, whose text goes into that cell
+    nested: usize,
+}
+
+impl Table {
+    fn end_cell(&mut self) {
+        let text = escape_table_cell(&self.cell.drain(..).collect::<Vec<_>>().join("\n"));
+        let (span, repeat) = self.cell_shape;
+        if span > 1 {
+            self.flattened = true;
+        }
+        for _ in 0..span * repeat {
+            self.row.push(text.clone());
+        }
+        self.spanned = span - 1;
+    }
+
+    /// A cell hidden by a merge: either part of the column span just read, or
+    /// the continuation of a cell spanning rows, whose text is in the row above
+    fn covered_cell(&mut self) {
+        if self.spanned > 0 {
+            self.spanned -= 1;
+            return;
+        }
+        let above = self
+            .rows
+            .last()
+            .and_then(|row| row.get(self.row.len()))
+            .cloned()
+            .unwrap_or_default();
+        self.row.push(above);
+        self.flattened = true;
+    }
+
+    /// GFM table with the first row as header; trailing empty columns, as
+    /// left by repeated cells, are dropped
+    fn to_markdown(&self) -> String {
+        let columns = self
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .rposition(|cell| !cell.is_empty())
+                    .map_or(0, |i| i + 1)
+            })
+            .max()
+            .unwrap_or(0);
+        if columns == 0 {
+            return String::new();
+        }
+        let line = |row: &[String]| {
+            let mut line = String::from("|");
+            for i in 0..columns {
+                line.push_str(&format!(" {} |", row.get(i).map_or("", String::as_str)));
+            }
+            line
+        };
+        let mut lines = vec![
+            line(&self.rows[0]),
+            format!("|{}", " --- |".repeat(columns)),
+        ];
+        lines.extend(self.rows[1..].iter().map(|row| line(row)));
+        lines.join("\n")
+    }
+}
+
+/// Cell attribute holding a count, 1 when absent
+fn count_attr(tag: &str, name: &str) -> usize {
+    xml_attr(tag, name)
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(1)
+        .clamp(1, MAX_REPEATED_CELLS)
+}
+
+/// Walk the `office:text` body, emitting one Markdown block per heading,
+/// paragraph or table. `numbered_lists` tells numbered list styles from
+/// bulleted ones.
+fn content_to_markdown(
+    xml: &str,
+    styles: &HashMap<String, Format>,
+    numbered_lists: &HashMap<String, Vec<usize>>,
+) -> String {
+    let body_start = xml.find("<office:text").unwrap_or(0);
+    let body = &xml[body_start..];
+
+    let mut blocks: Vec<String> = Vec::new();
+    let mut runs: Vec<(String, Format)> = Vec::new();
+    // Formats of the open spans; the innermost is last
+    let mut spans: Vec<Format> = Vec::new();
+    // Prefix for the block being read: `## ` for headings, `- ` or `1. ` for list items
+    let mut prefix = String::new();
+    // Open lists, innermost last
+    let mut lists: Vec<List> = Vec::new();
+    // Whether the last block written was a list item, so the next one continues the list
+    let mut last_in_list = false;
+    let mut table: Option<Table> = None;
+    // Inside a block that contributes text (text:p / text:h)
+    let mut in_block = false;
+    // Nested elements whose text is dropped (notes, frames, tracked changes)
+    let mut skip_depth = 0usize;
+
+    let mut rest = body;
+    while let Some(lt) = rest.find('<') {
+        let text = &rest[..lt];
+        if in_block && skip_depth == 0 && !text.is_empty() {
+            let format = spans.last().copied().unwrap_or_default();
+            push_text(&mut runs, format, &collapse_whitespace(&xml_unescape(text)));
+        }
+        let Some(gt) = rest[lt..].find('>') else {
+            break;
+        };
+        let tag = &rest[lt + 1..lt + gt];
+        rest = &rest[lt + gt + 1..];
+
+        let closing = tag.starts_with('/');
+        let self_closing = tag.ends_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("");
+
+        if matches!(
+            name,
+            "text:note" | "draw:frame" | "text:tracked-changes" | "office:annotation"
+        ) {
+            if closing {
+                skip_depth = skip_depth.saturating_sub(1);
+            } else if !self_closing {
+                skip_depth += 1;
+            }
+            continue;
+        }
+        if skip_depth > 0 {
+            continue;
+        }
+
+        match (name, closing) {
+            ("text:h", false) => {
+                let level = xml_attr(tag, "text:outline-level")
+                    .and_then(|l| l.parse::<usize>().ok())
+                    .unwrap_or(1)
+                    .clamp(1, 6);
+                prefix = format!("{} ", "#".repeat(level));
+                in_block = true;
+                spans.clear();
+            }
+            ("text:p", false) => {
+                prefix = match lists.last() {
+                    Some(list) if list.item_start => format!("{}{}", list.indent, list.marker()),
+                    // Later paragraphs of an item continue its text
+                    Some(list) => format!("{}{}", list.indent, " ".repeat(list.marker().len())),
+                    None => String::new(),
+                };
+                in_block = !self_closing;
+                spans.clear();
+            }
+            ("text:h" | "text:p", true) => {
+                in_block = false;
+                if let Some(table) = table.as_mut() {
+                    let text = block_text(&mut runs, "\n");
+                    if !text.trim().is_empty() {
+                        table.cell.push(text.trim().to_string());
+                    }
+                    continue;
+                }
+                let in_list = !lists.is_empty();
+                if end_block(&mut runs, &prefix, &mut blocks, in_list && last_in_list) {
+                    last_in_list = in_list;
+                    if let Some(list) = lists.last_mut() {
+                        list.item_start = false;
+                    }
+                }
+            }
+            ("text:list", false) if !self_closing => {
+                let parent = lists.last();
+                let style = xml_attr(tag, "text:style-name")
+                    .or_else(|| parent.and_then(|l| l.style.clone()));
+                let level = lists.len() + 1;
+                let numbered = style
+                    .as_ref()
+                    .and_then(|s| numbered_lists.get(s))
+                    .is_some_and(|levels| levels.contains(&level));
+                let indent = parent.map_or(String::new(), |l| {
+                    format!("{}{}", l.indent, " ".repeat(l.marker().len()))
+                });
+                lists.push(List {
+                    style,
+                    numbered,
+                    number: 0,
+                    item_start: false,
+                    indent,
+                });
+            }
+            ("text:list", true) => {
+                lists.pop();
+            }
+            ("text:list-item", false) if !self_closing => {
+                if let Some(list) = lists.last_mut() {
+                    list.number = xml_attr(tag, "text:start-value")
+                        .and_then(|n| n.parse().ok())
+                        .unwrap_or(list.number + 1);
+                    list.item_start = true;
+                }
+            }
+            ("table:table", false) if !self_closing => match table.as_mut() {
+                Some(outer) => outer.nested += 1,
+                None => table = Some(Table::default()),
+            },
+            ("table:table", true) => {
+                if let Some(outer) = table.as_mut().filter(|t| t.nested > 0) {
+                    outer.nested -= 1;
+                    continue;
+                }
+                let Some(finished) = table.take() else {
+                    continue;
+                };
+                let markdown = finished.to_markdown();
+                if !markdown.is_empty() {
+                    if !blocks.is_empty() {
+                        blocks.push(String::new());
+                    }
+                    blocks.push(markdown);
+                    if finished.flattened {
+                        blocks.push(String::new());
+                        blocks.push(MERGED_CELLS_NOTE.trim().to_string());
+                    }
+                    last_in_list = false;
+                }
+            }
+            ("table:table-row", false) => {
+                if let Some(table) = table.as_mut().filter(|t| t.nested == 0) {
+                    table.row.clear();
+                    table.spanned = 0;
+                }
+            }
+            ("table:table-row", true) => {
+                if let Some(table) = table.as_mut().filter(|t| t.nested == 0) {
+                    if !table.row.is_empty() {
+                        let row = std::mem::take(&mut table.row);
+                        table.rows.push(row);
+                    }
+                }
+            }
+            ("table:table-cell", false) => {
+                if let Some(table) = table.as_mut().filter(|t| t.nested == 0) {
+                    table.cell_shape = (
+                        count_attr(tag, "table:number-columns-spanned"),
+                        count_attr(tag, "table:number-columns-repeated"),
+                    );
+                    if self_closing {
+                        table.end_cell();
+                    }
+                }
+            }
+            ("table:table-cell", true) => {
+                if let Some(table) = table.as_mut().filter(|t| t.nested == 0) {
+                    table.end_cell();
+                }
+            }
+            ("table:covered-table-cell", false) => {
+                if let Some(table) = table.as_mut().filter(|t| t.nested == 0) {
+                    for _ in 0..count_attr(tag, "table:number-columns-repeated") {
+                        table.covered_cell();
+                    }
+                }
+            }
+            ("text:span", false) if !self_closing => {
+                let outer = spans.last().copied().unwrap_or_default();
+                let own = xml_attr(tag, "text:style-name").and_then(|s| styles.get(&s).copied());
+                spans.push(own.map_or(outer, |f| Format {
+                    bold: outer.bold || f.bold,
+                    italic: outer.italic || f.italic,
+                }));
+            }
+            ("text:span", true) => {
+                spans.pop();
+            }
+            ("text:s", _) if in_block => {
+                let count = xml_attr(tag, "text:c")
+                    .and_then(|c| c.parse().ok())
+                    .unwrap_or(1);
+                let format = spans.last().copied().unwrap_or_default();
+                push_text(&mut runs, format, &" ".repeat(count));
+            }
+            ("text:tab", _) if in_block => {
+                push_text(&mut runs, spans.last().copied().unwrap_or_default(), "\t")
+            }
+            ("text:line-break", _) if in_block => push_text(&mut runs, Format::default(), "\n"),
+            _ => {}
+        }
+    }
+
+    let mut markdown = String::new();
+    for block in blocks {
+        markdown.push_str(&block);
+        markdown.push('\n');
+    }
+    markdown.trim_start_matches('\n').to_string()
+}
+
+fn push_text(runs: &mut Vec<(String, Format)>, format: Format, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match runs.last_mut() {
+        Some((last, last_format)) if *last_format == format => last.push_str(text),
+        _ => runs.push((text.to_string(), format)),
+    }
+}
+
+/// Text of the finished block with emphasis applied, its line breaks
+/// written as `line_break`
+fn block_text(runs: &mut Vec<(String, Format)>, line_break: &str) -> String {
+    let text: String = runs
+        .drain(..)
+        .map(|(text, format)| emphasize(&text, format))
+        .collect();
+    text.split('\n')
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join(line_break)
+}
+
+/// Append the finished block, returning whether it had any text. Items of one
+/// list are kept together; other blocks are separated by a blank line.
+fn end_block(
+    runs: &mut Vec<(String, Format)>,
+    prefix: &str,
+    blocks: &mut Vec<String>,
+    continues_list: bool,
+) -> bool {
+    let text = block_text(runs, "  \n");
+    if text.trim().is_empty() {
+        return false;
+    }
+    if !blocks.is_empty() && !continues_list {
+        blocks.push(String::new());
+    }
+    blocks.push(format!("{}{}", prefix, text.trim()));
+    true
+}
+
+/// Wrap a run in emphasis markers, keeping surrounding whitespace outside them
+fn emphasize(text: &str, format: Format) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() || format == Format::default() {
+        return text.to_string();
+    }
+    let leading = &text[..text.len() - text.trim_start().len()];
+    let trailing = &text[text.trim_end().len()..];
+    let marker = match (format.bold, format.italic) {
+        (true, true) => "***",
+        (true, false) => "**",
+        _ => "*",
+    };
+    format!("{}{}{}{}{}", leading, marker, trimmed, marker, trailing)
+}
+
+/// Whitespace in ODF text content collapses to single spaces; explicit
+/// spaces are written as `<text:s/>`
+fn collapse_whitespace(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last_space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !last_space {
+                out.push(' ');
+            }
+            last_space = true;
+        } else {
+            out.push(c);
+            last_space = false;
+        }
+    }
+    out
+}
+
+/// Value of `name="..."` within a single XML start tag.
+fn xml_attr(tag: &str, name: &str) -> Option<String> {
+    let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+    let pattern = format!(" {}=\"", name);
+    // The split in the callers removes the element name, so also match at the start
+    let start = if tag.starts_with(&pattern[1..]) {
+        pattern.len() - 1
+    } else {
+        tag.find(&pattern)? + pattern.len()
+    };
+    let len = tag[start..].find('"')?;
+    Some(xml_unescape(&tag[start..start + len]))
+}
+
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Build content.xml from Markdown: headings, paragraphs, bulleted and
+/// numbered lists, block quotes (in the "Quotations" style), tables with one
+/// cell per column and code blocks (one preformatted paragraph per line), with
+/// bold/italic spans
+fn content_xml(markdown: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    let mut body = String::new();
+    let mut bold = 0usize;
+    let mut italic = 0usize;
+    // Open paragraph-level element to close on End, if any
+    let mut open_block: Option<&'static str> = None;
+    let mut in_code_block = false;
+    let mut quote_depth = 0usize;
+    // Start number still to put on the first item of each open list, innermost last
+    let mut list_starts: Vec<Option<u64>> = Vec::new();
+    let mut table_count = 0usize;
+
+    for event in Parser::new_ext(markdown, options) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                let level = heading_number(level);
+                body.push_str(&format!(
+                    "<text:h text:style-name=\"Heading_20_{0}\" text:outline-level=\"{0}\">",
+                    level
+                ));
+                open_block = Some("</text:h>");
+            }
+            Event::Start(Tag::Paragraph) => open_paragraph(&mut body, &mut open_block, quote_depth),
+            Event::End(TagEnd::Heading(_)) | Event::End(TagEnd::Paragraph) => {
+                if let Some(close) = open_block.take() {
+                    body.push_str(close);
+                }
+            }
+            Event::Start(Tag::List(start)) => {
+                // A nested list ends the text of the item it belongs to
+                if let Some(close) = open_block.take() {
+                    body.push_str(close);
+                }
+                let style = if start.is_some() {
+                    NUMBER_LIST_STYLE
+                } else {
+                    BULLET_LIST_STYLE
+                };
+                body.push_str(&format!("<text:list text:style-name=\"{}\">", style));
+                list_starts.push(start.filter(|&n| n != 1));
+            }
+            Event::End(TagEnd::List(_)) => {
+                list_starts.pop();
+                body.push_str("</text:list>");
+            }
+            Event::Start(Tag::Item) => match list_starts.last_mut().and_then(Option::take) {
+                Some(start) => {
+                    body.push_str(&format!("<text:list-item text:start-value=\"{}\">", start))
+                }
+                None => body.push_str("<text:list-item>"),
+            },
+            Event::End(TagEnd::Item) => {
+                // Tight list items have no Paragraph events around their text
+                if let Some(close) = open_block.take() {
+                    body.push_str(close);
+                }
+                body.push_str("</text:list-item>");
+            }
+            Event::Start(Tag::BlockQuote(_)) => quote_depth += 1,
+            Event::End(TagEnd::BlockQuote(_)) => quote_depth = quote_depth.saturating_sub(1),
+            Event::Start(Tag::Table(alignments)) => {
+                table_count += 1;
+                body.push_str(&format!(
+                    "<table:table table:name=\"Table{}\"><table:table-column table:number-columns-repeated=\"{}\"/>",
+                    table_count,
+                    alignments.len()
+                ));
+            }
+            Event::End(TagEnd::Table) => body.push_str("</table:table>"),
+            // The header cells come straight inside TableHead, without a row
+            Event::Start(Tag::TableHead) => {
+                body.push_str("<table:table-header-rows><table:table-row>")
+            }
+            Event::End(TagEnd::TableHead) => {
+                body.push_str("</table:table-row></table:table-header-rows>")
+            }
+            Event::Start(Tag::TableRow) => body.push_str("<table:table-row>"),
+            Event::End(TagEnd::TableRow) => body.push_str("</table:table-row>"),
+            Event::Start(Tag::TableCell) => {
+                body.push_str("<table:table-cell office:value-type=\"string\">");
+                open_paragraph(&mut body, &mut open_block, 0);
+            }
+            Event::End(TagEnd::TableCell) => {
+                if let Some(close) = open_block.take() {
+                    body.push_str(close);
+                }
+                body.push_str("</table:table-cell>");
+            }
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::End(TagEnd::CodeBlock) => in_code_block = false,
+            Event::Start(Tag::Strong) => bold += 1,
+            Event::End(TagEnd::Strong) => bold = bold.saturating_sub(1),
+            Event::Start(Tag::Emphasis) => italic += 1,
+            Event::End(TagEnd::Emphasis) => italic = italic.saturating_sub(1),
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                open_paragraph(&mut body, &mut open_block, quote_depth);
+                body.push_str(&format!(
+                    "<text:a xlink:type=\"simple\" xlink:href=\"{}\">",
+                    escape_xml(&dest_url)
+                ));
+            }
+            Event::End(TagEnd::Link) => body.push_str("</text:a>"),
+            Event::Text(text) if in_code_block => {
+                for line in text.lines() {
+                    body.push_str(&format!(
+                        "<text:p text:style-name=\"{}\">{}</text:p>",
+                        CODE_STYLE,
+                        odt_text(line)
+                    ));
+                }
+            }
+            Event::Text(text) | Event::Code(text) => {
+                open_paragraph(&mut body, &mut open_block, quote_depth);
+                let style = match (bold > 0, italic > 0) {
+                    (true, true) => Some(BOLD_ITALIC_STYLE),
+                    (true, false) => Some(BOLD_STYLE),
+                    (false, true) => Some(ITALIC_STYLE),
+                    (false, false) => None,
+                };
+                match style {
+                    Some(style) => body.push_str(&format!(
+                        "<text:span text:style-name=\"{}\">{}</text:span>",
+                        style,
+                        odt_text(&text)
+                    )),
+                    None => body.push_str(&odt_text(&text)),
+                }
+            }
+            Event::SoftBreak => body.push(' '),
+            Event::HardBreak => body.push_str("<text:line-break/>"),
+            Event::Rule => body.push_str("<text:p text:style-name=\"Horizontal_20_Line\"/>"),
+            _ => {}
+        }
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:table="urn:oasis:names:tc:opendocument:xmlns:table:1.0" xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" xmlns:xlink="http://www.w3.org/1999/xlink" office:version="1.2">
+  <office:automatic-styles>
+    <style:style style:name="{}" style:family="text"><style:text-properties fo:font-weight="bold"/></style:style>
+    <style:style style:name="{}" style:family="text"><style:text-properties fo:font-style="italic"/></style:style>
+    <style:style style:name="{}" style:family="text"><style:text-properties fo:font-weight="bold" fo:font-style="italic"/></style:style>
+  </office:automatic-styles>
+  <office:body>
+    <office:text>{}</office:text>
+  </office:body>
+</office:document-content>
+"#,
+        BOLD_STYLE, ITALIC_STYLE, BOLD_ITALIC_STYLE, body
+    )
+}
+
+/// Start a body paragraph unless text is already going into one; inside a
+/// block quote it takes the quotation style
+fn open_paragraph(body: &mut String, open_block: &mut Option<&'static str>, quote_depth: usize) {
+    if open_block.is_none() {
+        let style = if quote_depth > 0 {
+            QUOTE_STYLE
+        } else {
+            BODY_STYLE
+        };
+        body.push_str(&format!("<text:p text:style-name=\"{}\">", style));
+        *open_block = Some("</text:p>");
+    }
+}
+
+fn heading_number(level: HeadingLevel) -> usize {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+/// Escape text for content.xml, keeping runs of spaces and tabs, which ODF
+/// would otherwise collapse
+fn odt_text(text: &str) -> String {
+    let mut out = String::new();
+    let mut spaces = 0;
+    let flush = |out: &mut String, spaces: &mut usize| {
+        if *spaces > 0 {
+            out.push(' ');
+            if *spaces > 1 {
+                out.push_str(&format!("<text:s text:c=\"{}\"/>", *spaces - 1));
+            }
+            *spaces = 0;
+        }
+    };
+    for c in text.chars() {
+        match c {
+            ' ' => spaces += 1,
+            '\t' => {
+                flush(&mut out, &mut spaces);
+                out.push_str("<text:tab/>");
+            }
+            _ => {
+                flush(&mut out, &mut spaces);
+                out.push_str(&escape_xml(&c.to_string()));
+            }
+        }
+    }
+    flush(&mut out, &mut spaces);
+    out
+}
+
+/// Paragraph and list styles referenced by content.xml
+fn styles_xml() -> String {
+    let levels =
+        |level_xml: fn(usize) -> String| (1..=LIST_LEVELS).map(level_xml).collect::<String>();
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-styles xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0" xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0" office:version="1.2">
+  <office:styles>
+    <style:style style:name="Standard" style:family="paragraph"/>
+    <style:style style:name="Text_20_body" style:display-name="Text body" style:family="paragraph" style:parent-style-name="Standard">
+      <style:paragraph-properties fo:margin-top="0in" fo:margin-bottom="0.1in"/>
+    </style:style>
+    <style:style style:name="Heading" style:family="paragraph" style:parent-style-name="Standard" style:next-style-name="Text_20_body">
+      <style:paragraph-properties fo:margin-top="0.17in" fo:margin-bottom="0.08in" fo:keep-with-next="always"/>
+      <style:text-properties fo:font-weight="bold"/>
+    </style:style>
+    <style:style style:name="Heading_20_1" style:display-name="Heading 1" style:family="paragraph" style:parent-style-name="Heading" style:default-outline-level="1"><style:text-properties fo:font-size="200%"/></style:style>
+    <style:style style:name="Heading_20_2" style:display-name="Heading 2" style:family="paragraph" style:parent-style-name="Heading" style:default-outline-level="2"><style:text-properties fo:font-size="160%"/></style:style>
+    <style:style style:name="Heading_20_3" style:display-name="Heading 3" style:family="paragraph" style:parent-style-name="Heading" style:default-outline-level="3"><style:text-properties fo:font-size="130%"/></style:style>
+    <style:style style:name="Heading_20_4" style:display-name="Heading 4" style:family="paragraph" style:parent-style-name="Heading" style:default-outline-level="4"><style:text-properties fo:font-size="115%"/></style:style>
+    <style:style style:name="Heading_20_5" style:display-name="Heading 5" style:family="paragraph" style:parent-style-name="Heading" style:default-outline-level="5"><style:text-properties fo:font-size="100%"/></style:style>
+    <style:style style:name="Heading_20_6" style:display-name="Heading 6" style:family="paragraph" style:parent-style-name="Heading" style:default-outline-level="6"><style:text-properties fo:font-size="90%"/></style:style>
+    <style:style style:name="Preformatted_20_Text" style:display-name="Preformatted Text" style:family="paragraph" style:parent-style-name="Standard">
+      <style:text-properties style:font-name="Liberation Mono" fo:font-family="'Liberation Mono', monospace"/>
+    </style:style>
+    <style:style style:name="Quotations" style:family="paragraph" style:parent-style-name="Standard">
+      <style:paragraph-properties fo:margin-left="0.39in" fo:margin-right="0.39in" fo:margin-top="0in" fo:margin-bottom="0.1in"/>
+    </style:style>
+    <style:style style:name="Horizontal_20_Line" style:display-name="Horizontal Line" style:family="paragraph" style:parent-style-name="Standard">
+      <style:paragraph-properties fo:border-bottom="0.5pt solid #808080"/>
+    </style:style>
+    <text:list-style style:name="{bullet_name}" style:display-name="List Bullet">{bullet_levels}</text:list-style>
+    <text:list-style style:name="{number_name}" style:display-name="List Number">{number_levels}</text:list-style>
+  </office:styles>
+</office:document-styles>
+"#,
+        bullet_name = BULLET_LIST_STYLE,
+        bullet_levels = levels(|level| {
+            let bullet = ["•", "◦", "▪"][(level - 1) % 3];
+            format!(
+                "<text:list-level-style-bullet text:level=\"{}\" text:bullet-char=\"{}\">{}</text:list-level-style-bullet>",
+                level,
+                bullet,
+                list_level_properties(level)
+            )
+        }),
+        number_name = NUMBER_LIST_STYLE,
+        number_levels = levels(|level| {
+            format!(
+                "<text:list-level-style-number text:level=\"{}\" style:num-suffix=\".\" style:num-format=\"1\">{}</text:list-level-style-number>",
+                level,
+                list_level_properties(level)
+            )
+        }),
+    )
+}
+
+/// Indentation of a list level: a quarter inch per level, marker hanging in front
+fn list_level_properties(level: usize) -> String {
+    format!(
+        "<style:list-level-properties text:list-level-position-and-space-mode=\"label-alignment\">\
+         <style:list-level-label-alignment text:label-followed-by=\"listtab\" fo:text-indent=\"-0.25in\" fo:margin-left=\"{:.2}in\"/>\
+         </style:list-level-properties>",
+        0.25 * level as f64
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_headings_and_emphasis() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("odt.odt");
+        let md = "# Title\n\nSome **bold** and *italic* and ***both*** text.\n\n## Section & more\n\n- one\n- two\n";
+        markdown_to_odt(md, path.to_str().unwrap()).unwrap();
+
+        {
+            let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+            let first = archive.by_index(0).unwrap();
+            assert_eq!(first.name(), "mimetype");
+            assert_eq!(first.compression(), zip::CompressionMethod::Stored);
+        }
+
+        let imported = odt_to_markdown(path.to_str().unwrap()).unwrap();
+        assert_eq!(imported, md);
+    }
+
+    #[test]
+    fn test_export_quotes_numbered_lists_and_tables() {
+        let md =
+            "> Quoted *text*\n\n3. three\n4. four\n\n- dot\n\n| A | B |\n| - | - |\n| 1 | 2 |\n";
+        let xml = content_xml(md);
+
+        assert!(xml.contains("<text:p text:style-name=\"Quotations\">Quoted <text:span"));
+        assert!(xml.contains(
+            "<text:list text:style-name=\"List_20_Number\"><text:list-item text:start-value=\"3\">\
+             <text:p text:style-name=\"Text_20_body\">three</text:p></text:list-item>\
+             <text:list-item><text:p text:style-name=\"Text_20_body\">four</text:p></text:list-item></text:list>"
+        ));
+        assert!(xml.contains("<text:list text:style-name=\"List_20_Bullet\"><text:list-item>"));
+        assert!(xml.contains(
+            "<table:table table:name=\"Table1\"><table:table-column table:number-columns-repeated=\"2\"/>\
+             <table:table-header-rows><table:table-row>\
+             <table:table-cell office:value-type=\"string\"><text:p text:style-name=\"Text_20_body\">A</text:p></table:table-cell>\
+             <table:table-cell office:value-type=\"string\"><text:p text:style-name=\"Text_20_body\">B</text:p></table:table-cell>\
+             </table:table-row></table:table-header-rows>"
+        ));
+        assert_eq!(xml.matches("<table:table-cell ").count(), 4);
+
+        let styles = styles_xml();
+        assert_eq!(
+            styles.matches("<text:list-level-style-number ").count(),
+            LIST_LEVELS
+        );
+        assert!(styles.contains("text:level=\"2\" text:bullet-char=\"◦\""));
+    }
+
+    #[test]
+    fn test_import_named_styles_and_spaces() {
+        let content = r#"<office:document-content><office:automatic-styles>
+<style:style style:name="T1" style:family="text"><style:text-properties fo:font-weight="700"/></style:style>
+</office:automatic-styles><office:body><office:text>
+<text:h text:style-name="P1" text:outline-level="3">Deep <text:span text:style-name="T1">heading</text:span></text:h>
+<text:p text:style-name="P2">a<text:s text:c="2"/>b <text:span text:style-name="Emphasis">word</text:span><text:note><text:note-body><text:p>note</text:p></text:note-body></text:note></text:p>
+<text:p/>
+</office:text></office:body></office:document-content>"#;
+        let mut styles = text_styles(content);
+        styles.insert(
+            "Emphasis".to_string(),
+            Format {
+                bold: false,
+                italic: true,
+            },
+        );
+        assert_eq!(
+            content_to_markdown(content, &styles, &HashMap::new()),
+            "### Deep **heading**\n\na  b *word*\n"
+        );
+    }
+
+    #[test]
+    fn test_round_trip_numbered_lists_and_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("lists.odt");
+        let md = "3. three\n4. four\n   - dot\n   1. nested\n\n| A | B |\n| --- | --- |\n| *x* | y \\| z |\n";
+        markdown_to_odt(md, path.to_str().unwrap()).unwrap();
+
+        let imported = odt_to_markdown(path.to_str().unwrap()).unwrap();
+        assert_eq!(imported, md);
+    }
+
+    #[test]
+    fn test_import_table_with_merged_cells() {
+        let content = r#"<office:document-content><office:body><office:text>
+<table:table table:name="T"><table:table-column table:number-columns-repeated="3"/>
+<table:table-row><table:table-cell table:number-columns-spanned="2"><text:p>Wide</text:p></table:table-cell><table:covered-table-cell/><table:table-cell/></table:table-row>
+<table:table-row><table:table-cell><text:p>a|b</text:p><text:p>c</text:p></table:table-cell><table:table-cell table:number-rows-spanned="2"><text:p>Tall</text:p></table:table-cell><table:table-cell/></table:table-row>
+<table:table-row><table:table-cell/><table:covered-table-cell/><table:table-cell table:number-columns-repeated="2"/></table:table-row>
+</table:table>
+<text:p>After</text:p>
+</office:text></office:body></office:document-content>"#;
+        assert_eq!(
+            content_to_markdown(content, &HashMap::new(), &HashMap::new()),
+            "| Wide | Wide |\n| --- | --- |\n| a\\|b<br>c | Tall |\n|  | Tall |\n\n\
+             > **Note**: Merged cells were flattened; their text is repeated in each cell they covered.\n\nAfter\n"
+        );
+    }
+}
@@ -1,27 +1,646 @@
+use std::path::{Path, PathBuf};
+
+use genpdfi::PaperSize;
 use markdown2pdf::config::ConfigSource;
+use pdf_extract::{MediaBox, OutputDev, OutputError, Transform};
+use serde::Deserialize;
 
-use super::ConversionError;
+use super::{check_signature, ConversionError, ImportControl};
 
 const PDF_IMPORT_NOTICE: &str = "> **Import Notice**: This PDF was imported as plain text.\n\
 > Images, tables, and complex formatting have been removed.\n\n";
 
+/// Lines set at least this much larger than the body text are headings
+const HEADING_SIZE_RATIO: f64 = 1.2;
+
+/// TrueType/OpenType fonts with CJK coverage that ship with common systems,
+/// in order of preference. Font collections (`.ttc`) can't be embedded, which
+/// rules out msjh/msyh on Windows and the Noto CJK packages on Linux.
+const CJK_FONT_CANDIDATES: &[&str] = &[
+    // macOS
+    "/System/Library/Fonts/Supplemental/Arial Unicode.ttf",
+    "/Library/Fonts/Arial Unicode.ttf",
+    // Windows
+    "C:\\Windows\\Fonts\\Deng.ttf",
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "C:\\Windows\\Fonts\\kaiu.ttf",
+    "C:\\Windows\\Fonts\\simkai.ttf",
+    // Linux
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+    "/usr/share/fonts/google-droid-sans-fonts/DroidSansFallbackFull.ttf",
+    "/usr/share/fonts/TTF/DroidSansFallbackFull.ttf",
+];
+
+/// Font file name looked for in a `fonts` directory bundled next to the app.
+const BUNDLED_CJK_FONT: &str = "NotoSansCJK-Regular.otf";
+
+/// Body text size markdown2pdf uses by default, in points
+const DEFAULT_FONT_SIZE: f32 = 8.0;
+
+/// Default heading sizes relative to body text (14pt/12pt/10pt over 8pt)
+const HEADING_SCALES: [f32; 3] = [1.75, 1.5, 1.25];
+
+/// Page layout for PDF export.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PdfOptions {
+    /// `a4`, `letter` or `legal`
+    pub page_size: String,
+    /// Margin on every side, in millimetres
+    pub margin_mm: f32,
+    /// Body text size, in points; headings scale with it
+    pub font_size: f32,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        Self {
+            page_size: "a4".to_string(),
+            margin_mm: 8.0,
+            font_size: DEFAULT_FONT_SIZE,
+        }
+    }
+}
+
+impl PdfOptions {
+    fn paper_size(&self) -> Result<PaperSize, ConversionError> {
+        match self.page_size.trim().to_lowercase().as_str() {
+            "a4" => Ok(PaperSize::A4),
+            "letter" => Ok(PaperSize::Letter),
+            "legal" => Ok(PaperSize::Legal),
+            other => Err(ConversionError(format!(
+                "Unsupported page size: {} (expected a4, letter or legal)",
+                other
+            ))),
+        }
+    }
+
+    /// markdown2pdf style configuration (TOML) for these margins and sizes.
+    fn style_config(&self) -> Result<String, ConversionError> {
+        if !(0.0..=50.0).contains(&self.margin_mm) {
+            return Err(ConversionError(format!(
+                "Invalid margin: {} mm",
+                self.margin_mm
+            )));
+        }
+        if !(4.0..=72.0).contains(&self.font_size) {
+            return Err(ConversionError(format!(
+                "Invalid font size: {} pt",
+                self.font_size
+            )));
+        }
+
+        // markdown2pdf only accepts whole-point sizes
+        let size = |scale: f32| (self.font_size * scale).round() as u8;
+        let mut config = format!(
+            "[margin]\ntop = {m:.1}\nright = {m:.1}\nbottom = {m:.1}\nleft = {m:.1}\n",
+            m = self.margin_mm
+        );
+        for (level, scale) in HEADING_SCALES.iter().enumerate() {
+            config.push_str(&format!(
+                "\n[heading.{}]\nsize = {}\n",
+                level + 1,
+                size(*scale)
+            ));
+        }
+        for section in [
+            "text",
+            "emphasis",
+            "strong_emphasis",
+            "code",
+            "block_quote",
+            "list_item",
+            "link",
+            "image",
+            "table.header",
+            "table.cell",
+            "horizontal_rule",
+        ] {
+            config.push_str(&format!("\n[{}]\nsize = {}\n", section, size(1.0)));
+        }
+        Ok(config)
+    }
+}
+
 /// Convert Markdown to a PDF file.
 pub fn markdown_to_pdf(markdown: &str, path: &str) -> Result<(), ConversionError> {
-    markdown2pdf::parse_into_file(markdown.to_string(), path, ConfigSource::Default, None)
+    markdown_to_pdf_with_options(markdown, path, &PdfOptions::default(), None)
+}
+
+/// Convert Markdown to a PDF file with the given page layout and body font.
+/// `font` may be a font file path or a system font name. Without one,
+/// a CJK-capable font is picked automatically when the text needs it,
+/// since the built-in PDF fonts only cover Latin script.
+pub fn markdown_to_pdf_with_options(
+    markdown: &str,
+    path: &str,
+    options: &PdfOptions,
+    font: Option<&str>,
+) -> Result<(), ConversionError> {
+    let paper_size = options.paper_size()?;
+    let config = options.style_config()?;
+
+    let font = match font {
+        Some(name) if !name.trim().is_empty() => Some(name.trim().to_string()),
+        _ if contains_cjk(markdown) => find_cjk_font(),
+        _ => None,
+    };
+    let font_config =
+        font.map(|name| markdown2pdf::fonts::FontConfig::new().with_default_font(name));
+
+    // Same pipeline as markdown2pdf::parse_into_file, which has no way to set the paper size
+    let tokens = markdown2pdf::markdown::Lexer::new(markdown.to_string())
+        .parse()
+        .map_err(|e| ConversionError(format!("PDF export failed: {:?}", e)))?;
+    let style = markdown2pdf::config::load_config_from_source(ConfigSource::Embedded(&config));
+    let pdf = markdown2pdf::pdf::Pdf::new(tokens, style, font_config.as_ref())
+        .map_err(|e| ConversionError(format!("PDF export failed: {}", e)))?;
+
+    let mut document = pdf.render_into_document();
+    document.set_paper_size(paper_size);
+    document
+        .render_to_file(path)
         .map_err(|e| ConversionError(format!("PDF export failed: {}", e)))
 }
 
+/// Whether the text contains Han, kana or Hangul characters.
+fn contains_cjk(text: &str) -> bool {
+    text.chars().any(|c| {
+        crate::markdown::is_cjk(c)
+            || matches!(c as u32, 0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF)
+    })
+}
+
+/// The first CJK-capable font available: one bundled with the app, then
+/// well-known system fonts.
+fn find_cjk_font() -> Option<String> {
+    // Windows/Linux keep resources beside the binary, macOS in Contents/Resources
+    let font_dirs: Vec<PathBuf> = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        .map(|dir| vec![dir.join("fonts"), dir.join("../Resources/fonts")])
+        .unwrap_or_default();
+    find_font_in(&font_dirs, CJK_FONT_CANDIDATES)
+}
+
+/// `BUNDLED_CJK_FONT` from the first of `font_dirs` that has it, else the
+/// first of `candidates` that exists
+fn find_font_in(font_dirs: &[PathBuf], candidates: &[&str]) -> Option<String> {
+    font_dirs
+        .iter()
+        .map(|dir| dir.join(BUNDLED_CJK_FONT))
+        .find(|p| p.is_file())
+        .map(|p| p.to_string_lossy().into_owned())
+        .or_else(|| {
+            candidates
+                .iter()
+                .find(|p| Path::new(p).is_file())
+                .map(|p| p.to_string())
+        })
+}
+
+/// What to put between pages when importing a PDF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PageSeparator {
+    /// Pages run together
+    #[default]
+    None,
+    /// A `---` horizontal rule
+    Rule,
+    /// A `---` rule followed by `<!-- page N -->`
+    RuleWithPageNumber,
+}
+
+/// Convert a PDF file to Markdown (plain text extraction), with pages run together.
+pub fn pdf_to_markdown(path: &str) -> Result<String, ConversionError> {
+    pdf_to_markdown_with_separator(path, PageSeparator::None)
+}
+
 /// Convert a PDF file to Markdown (plain text extraction).
 /// Prepends a blockquote warning about quality limitations.
-pub fn pdf_to_markdown(path: &str) -> Result<String, ConversionError> {
+/// Lines set noticeably larger than the body text become `#`/`##`/`###`
+/// headings by size tier; everything else is joined into paragraphs.
+/// `separator` marks where each page ends.
+pub fn pdf_to_markdown_with_separator(
+    path: &str,
+    separator: PageSeparator,
+) -> Result<String, ConversionError> {
+    pdf_to_markdown_with_control(path, separator, &ImportControl::default())
+}
+
+/// [`pdf_to_markdown_with_separator`] reporting each page as it is read and
+/// stopping at the next page once `control` is cancelled.
+pub fn pdf_to_markdown_with_control(
+    path: &str,
+    separator: PageSeparator,
+    control: &ImportControl,
+) -> Result<String, ConversionError> {
+    check_signature(path, "pdf")?;
     let bytes = std::fs::read(path)
         .map_err(|e| ConversionError(format!("Failed to read PDF: {}", e)))?;
 
-    let text = pdf_extract::extract_text_from_mem(&bytes)
-        .map_err(|e| ConversionError(format!("Failed to extract PDF text: {}", e)))?;
+    let pages = extract_lines(&bytes, control)?;
 
     let mut output = String::from(PDF_IMPORT_NOTICE);
-    output.push_str(&text);
+    output.push_str(&pages_to_markdown(&pages, separator));
 
     Ok(output)
 }
+
+/// A line of text on a page with the largest font size used in it.
+#[derive(Debug, Clone)]
+struct TextLine {
+    text: String,
+    font_size: f64,
+    /// Baseline position, in PDF units from the bottom of the page
+    y: f64,
+    /// Vertical distance from the previous line on the page, if any
+    gap_before: Option<f64>,
+}
+
+/// Collects characters into lines per page, keeping their font sizes.
+/// Word and line breaks are inferred from glyph positions the same way as
+/// pdf-extract's plain text output.
+#[derive(Default)]
+struct LineCollector {
+    pages: Vec<Vec<TextLine>>,
+    last_end: f64,
+    last_y: f64,
+    word_start: bool,
+    control: ImportControl,
+    total_pages: usize,
+}
+
+impl OutputDev for LineCollector {
+    fn begin_page(
+        &mut self,
+        _page_num: u32,
+        _media_box: &MediaBox,
+        _art_box: Option<(f64, f64, f64, f64)>,
+    ) -> Result<(), OutputError> {
+        // Aborts output_doc; extract_lines turns this into "Import cancelled"
+        if self.control.is_cancelled() {
+            return Err(OutputError::FormatError(std::fmt::Error));
+        }
+        self.pages.push(Vec::new());
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), OutputError> {
+        self.control
+            .report("pages", self.pages.len(), Some(self.total_pages));
+        Ok(())
+    }
+
+    fn output_character(
+        &mut self,
+        trm: &Transform,
+        width: f64,
+        _spacing: f64,
+        font_size: f64,
+        char: &str,
+    ) -> Result<(), OutputError> {
+        // Effective size after the text matrix's scaling
+        let size = font_size * (trm.m11 * trm.m22 - trm.m12 * trm.m21).abs().sqrt();
+        let (x, y) = (trm.m31, trm.m32);
+        let Some(page) = self.pages.last_mut() else {
+            return Ok(());
+        };
+
+        let starts_line = match page.last() {
+            None => true,
+            Some(_) => self.word_start && (y - self.last_y).abs() > size * 0.5,
+        };
+        if starts_line {
+            let gap_before = page.last().map(|prev| (prev.y - y).abs());
+            page.push(TextLine {
+                text: String::new(),
+                font_size: size,
+                y,
+                gap_before,
+            });
+        } else if self.word_start && x > self.last_end + size * 0.1 {
+            if let Some(line) = page.last_mut() {
+                line.text.push(' ');
+            }
+        }
+
+        if let Some(line) = page.last_mut() {
+            line.text.push_str(char);
+            if !char.trim().is_empty() {
+                line.font_size = line.font_size.max(size);
+            }
+        }
+        self.word_start = false;
+        self.last_y = y;
+        self.last_end = x + width * size;
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), OutputError> {
+        self.word_start = true;
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+}
+
+/// Text lines of each page, in page order.
+fn extract_lines(
+    bytes: &[u8],
+    control: &ImportControl,
+) -> Result<Vec<Vec<TextLine>>, ConversionError> {
+    let doc = pdf_extract::Document::load_mem(bytes)
+        .map_err(|e| ConversionError(format!("Failed to extract PDF text: {}", e)))?;
+    let mut collector = LineCollector {
+        control: control.clone(),
+        total_pages: doc.get_pages().len(),
+        ..LineCollector::default()
+    };
+    if let Err(e) = pdf_extract::output_doc(&doc, &mut collector) {
+        control.check_cancelled()?;
+        return Err(ConversionError(format!(
+            "Failed to extract PDF text: {}",
+            e
+        )));
+    }
+
+    for page in &mut collector.pages {
+        page.retain(|line| !line.text.trim().is_empty());
+    }
+    Ok(collector.pages)
+}
+
+/// Rounded to half a point so tiny size differences don't create extra tiers.
+fn size_key(size: f64) -> i64 {
+    (size * 2.0).round() as i64
+}
+
+/// Build Markdown from extracted page lines. The size covering the most
+/// characters is the body size; larger sizes are heading tiers, largest first.
+/// Body lines are joined into a paragraph until a gap wider than normal line
+/// spacing. Paragraphs don't continue across pages.
+fn pages_to_markdown(pages: &[Vec<TextLine>], separator: PageSeparator) -> String {
+    let mut chars_by_size: Vec<(i64, usize)> = Vec::new();
+    for line in pages.iter().flatten() {
+        let key = size_key(line.font_size);
+        let count = line.text.chars().filter(|c| !c.is_whitespace()).count();
+        match chars_by_size.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, n)) => *n += count,
+            None => chars_by_size.push((key, count)),
+        }
+    }
+    let Some(&(body_key, _)) = chars_by_size.iter().max_by_key(|(_, n)| *n) else {
+        return String::new();
+    };
+    let body_size = body_key as f64 / 2.0;
+
+    let mut heading_tiers: Vec<i64> = chars_by_size
+        .iter()
+        .map(|(k, _)| *k)
+        .filter(|k| *k as f64 / 2.0 >= body_size * HEADING_SIZE_RATIO)
+        .collect();
+    heading_tiers.sort_unstable_by(|a, b| b.cmp(a));
+
+    let mut page_markdown: Vec<String> = Vec::new();
+    for lines in pages {
+        let blocks = lines_to_blocks(lines, &heading_tiers);
+        if !blocks.is_empty() || separator != PageSeparator::None {
+            page_markdown.push(blocks.join("\n\n"));
+        }
+    }
+
+    let mut md = String::new();
+    for (i, page) in page_markdown.iter().enumerate() {
+        if i > 0 {
+            match separator {
+                PageSeparator::None => md.push_str("\n\n"),
+                PageSeparator::Rule => md.push_str("\n\n---\n\n"),
+                PageSeparator::RuleWithPageNumber => {
+                    md.push_str(&format!("\n\n---\n\n<!-- page {} -->\n\n", i + 1))
+                }
+            }
+        }
+        md.push_str(page);
+    }
+    let mut md = md.trim_end().to_string();
+    if !md.is_empty() {
+        md.push('\n');
+    }
+    md
+}
+
+/// Headings and paragraphs of a single page.
+fn lines_to_blocks(lines: &[TextLine], heading_tiers: &[i64]) -> Vec<String> {
+    let mut blocks: Vec<String> = Vec::new();
+    let mut paragraph = String::new();
+    for line in lines {
+        let text = line.text.trim();
+        let tier = heading_tiers
+            .iter()
+            .position(|k| *k == size_key(line.font_size));
+        let paragraph_break = line.gap_before.is_none_or(|gap| gap > line.font_size * 1.5);
+
+        if (tier.is_some() || paragraph_break) && !paragraph.is_empty() {
+            blocks.push(std::mem::take(&mut paragraph));
+        }
+        if let Some(tier) = tier {
+            blocks.push(format!("{} {}", "#".repeat((tier + 1).min(3)), text));
+            continue;
+        }
+        if !paragraph.is_empty() {
+            paragraph.push(' ');
+        }
+        paragraph.push_str(text);
+    }
+    if !paragraph.is_empty() {
+        blocks.push(paragraph);
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::convert::ImportProgress;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn temp_pdf() -> tempfile::TempPath {
+        tempfile::Builder::new()
+            .suffix(".pdf")
+            .tempfile()
+            .unwrap()
+            .into_temp_path()
+    }
+
+    #[test]
+    fn test_pdf_to_markdown_detects_headings_by_font_size() {
+        let path = temp_pdf();
+        let path_str = path.to_str().unwrap();
+        markdown_to_pdf(
+            "# Quarterly Report\n\nRevenue grew this quarter.\n\n## Outlook\n\nSteady.\n",
+            path_str,
+        )
+        .unwrap();
+
+        let md = pdf_to_markdown(path_str).unwrap();
+
+        assert!(md.starts_with(PDF_IMPORT_NOTICE));
+        let body = &md[PDF_IMPORT_NOTICE.len()..];
+        assert!(body.starts_with("# Quarterly Report\n\n"), "{}", body);
+        assert!(body.contains("\n## Outlook\n"), "{}", body);
+        assert!(body.contains("Revenue grew this quarter."), "{}", body);
+    }
+
+    #[test]
+    fn test_pdf_import_stops_when_cancelled() {
+        let path = temp_pdf();
+        let path_str = path.to_str().unwrap();
+        let md: String = (1..=200).map(|i| format!("Line {}\n\n", i)).collect();
+        markdown_to_pdf(&md, path_str).unwrap();
+
+        // Cancel once the first page is done
+        let pages_done = Arc::new(AtomicUsize::new(0));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let control = ImportControl {
+            cancel: cancel.clone(),
+            progress: Some(Arc::new({
+                let pages_done = pages_done.clone();
+                move |progress: ImportProgress| {
+                    assert!(progress.total.is_some_and(|total| total > 1));
+                    pages_done.store(progress.done, Ordering::Relaxed);
+                    cancel.store(true, Ordering::Relaxed);
+                }
+            })),
+        };
+        let result = pdf_to_markdown_with_control(path_str, PageSeparator::None, &control);
+
+        assert_eq!(result.unwrap_err().0, "Import cancelled");
+        assert_eq!(pages_done.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_pdf_to_markdown_page_separators() {
+        let path = temp_pdf();
+        let path_str = path.to_str().unwrap();
+        let md: String = (1..=200).map(|i| format!("Line {}\n\n", i)).collect();
+        markdown_to_pdf(&md, path_str).unwrap();
+
+        let plain = pdf_to_markdown(path_str).unwrap();
+        let ruled =
+            pdf_to_markdown_with_separator(path_str, PageSeparator::RuleWithPageNumber).unwrap();
+
+        assert!(!plain.contains("\n---\n"));
+        let (first_page, rest) = ruled.split_once("\n\n---\n\n<!-- page 2 -->\n\n").unwrap();
+        assert!(first_page.contains("Line 1 "));
+        assert!(rest.contains("Line 200"));
+        assert!(!first_page.contains("Line 200"));
+    }
+
+    #[test]
+    fn test_contains_cjk() {
+        assert!(contains_cjk("# 會議紀錄"));
+        assert!(contains_cjk("ひらがな"));
+        assert!(contains_cjk("한국어"));
+        assert!(!contains_cjk("# Meeting notes — café"));
+    }
+
+    /// A small OFL-licensed TrueType font checked in for tests
+    const FIXTURE_FONT: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/fonts/Montserrat-Regular.ttf"
+    );
+
+    /// An OFL-licensed Adobe test font with glyphs for all of U+4E00–U+9FFF
+    const CJK_FIXTURE_FONT: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/fonts/FDArrayTest257.otf"
+    );
+
+    #[test]
+    fn test_markdown_to_pdf_with_cjk_text() {
+        let path = temp_pdf();
+        let path_str = path.to_str().unwrap();
+        let md = "# 會議紀錄\n\n今天的議程\n";
+        markdown_to_pdf_with_options(md, path_str, &PdfOptions::default(), Some(CJK_FIXTURE_FONT))
+            .unwrap();
+
+        let imported = pdf_to_markdown(path_str).unwrap();
+        let body = &imported[PDF_IMPORT_NOTICE.len()..];
+        assert!(body.contains("會議紀錄"), "{}", body);
+        assert!(body.contains("今天的議程"), "{}", body);
+    }
+
+    #[test]
+    fn test_find_font_in_prefers_bundled_font() {
+        let dir = tempfile::tempdir().unwrap();
+        let fonts = dir.path().join("fonts");
+        std::fs::create_dir_all(&fonts).unwrap();
+        let missing = dir.path().join("missing");
+
+        let system_fonts = ["/no/such/font.ttf", FIXTURE_FONT];
+        assert_eq!(
+            find_font_in(&[missing.clone()], &system_fonts).as_deref(),
+            Some(FIXTURE_FONT)
+        );
+        assert_eq!(find_font_in(&[missing.clone()], &system_fonts[..1]), None);
+
+        std::fs::copy(FIXTURE_FONT, fonts.join(BUNDLED_CJK_FONT)).unwrap();
+        let found = find_font_in(&[missing, fonts.clone()], &[FIXTURE_FONT]);
+        assert_eq!(
+            found,
+            Some(fonts.join(BUNDLED_CJK_FONT).to_string_lossy().into_owned())
+        );
+    }
+
+    #[test]
+    fn test_markdown_to_pdf_with_options_sets_page_size() {
+        let path = temp_pdf();
+        let options = PdfOptions {
+            page_size: "Letter".to_string(),
+            margin_mm: 20.0,
+            font_size: 11.0,
+        };
+        markdown_to_pdf_with_options(
+            "# Title\n\nBody text.\n",
+            path.to_str().unwrap(),
+            &options,
+            None,
+        )
+        .unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+
+        // 216 x 279 mm in points
+        let pdf = String::from_utf8_lossy(&bytes);
+        assert!(pdf.contains("/MediaBox[0 0 612.2835 790.8662]"));
+    }
+
+    #[test]
+    fn test_pdf_options_validation() {
+        let path = temp_pdf();
+        let path_str = path.to_str().unwrap();
+
+        let tabloid = PdfOptions {
+            page_size: "tabloid".to_string(),
+            ..PdfOptions::default()
+        };
+        let err = markdown_to_pdf_with_options("Text", path_str, &tabloid, None).unwrap_err();
+        assert_eq!(
+            err.0,
+            "Unsupported page size: tabloid (expected a4, letter or legal)"
+        );
+
+        let margin = PdfOptions {
+            margin_mm: -1.0,
+            ..PdfOptions::default()
+        };
+        assert!(markdown_to_pdf_with_options("Text", path_str, &margin, None).is_err());
+        assert!(!path.exists());
+    }
+}
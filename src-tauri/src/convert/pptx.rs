@@ -1,129 +1,439 @@
-use super::ConversionError;
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+use super::{check_signature, invalid_file, ConversionError};
 
 /// Convert a PPTX file to Markdown.
-/// Each slide's text content becomes a section.
+/// Each slide's text content becomes a section. When the deck has a title in
+/// its document properties, that is the single `# ` heading, slide titles are
+/// `## ` and slides are separated by `---`; otherwise each slide title is `# `.
+/// A slide with speaker notes but no text keeps just its notes, set off by
+/// `---` when slides aren't already separated that way.
 pub fn pptx_to_markdown(path: &str) -> Result<String, ConversionError> {
+    check_signature(path, "pptx")?;
     // Parse PPTX as a ZIP archive and extract text from slide XML
     let file = std::fs::File::open(path)
         .map_err(|e| ConversionError(format!("Failed to open PPTX: {}", e)))?;
 
     let mut archive = zip::ZipArchive::new(file)
-        .map_err(|e| ConversionError(format!("Failed to read PPTX archive: {}", e)))?;
+        .map_err(|e| invalid_file("pptx", format!("Failed to read PPTX archive: {}", e)))?;
+
+    let title = presentation_title(&mut archive)?;
+    let slide_level = if title.is_some() { 2 } else { 1 };
+
+    let mut slides: Vec<String> = Vec::new();
+    for slide_path in slide_order(&mut archive)? {
+        let Some(content) = read_entry(&mut archive, &slide_path)? else {
+            continue;
+        };
+        let mut text = extract_text_from_slide_xml(&content, slide_level);
+        let notes = read_slide_notes(&mut archive, &slide_path)?;
+        match notes {
+            Some(notes) if text.is_empty() => {
+                if title.is_none() {
+                    text.push_str("---\n\n");
+                }
+                text.push_str(&notes_to_markdown(&notes));
+            }
+            Some(notes) => {
+                text.push('\n');
+                text.push_str(&notes_to_markdown(&notes));
+            }
+            None if text.is_empty() => continue,
+            None => {}
+        }
+        slides.push(text);
+    }
+
+    Ok(match title {
+        Some(title) if slides.is_empty() => format!("# {}\n", title),
+        Some(title) => format!("# {}\n\n{}", title, slides.join("\n---\n\n")),
+        None => slides.join("\n"),
+    })
+}
+
+/// The deck title from `<dc:title>` in docProps/core.xml, if it has one.
+fn presentation_title(archive: &mut PptxArchive) -> Result<Option<String>, ConversionError> {
+    let Some(core) = read_entry(archive, "docProps/core.xml")? else {
+        return Ok(None);
+    };
+    let title = core
+        .find("<dc:title>")
+        .map(|i| &core[i + "<dc:title>".len()..])
+        .and_then(|rest| {
+            rest.find("</dc:title>")
+                .map(|end| xml_unescape(rest[..end].trim()))
+        });
+    Ok(title.filter(|t| !t.is_empty()))
+}
+
+type PptxArchive = zip::ZipArchive<std::fs::File>;
 
-    let mut slides: Vec<(usize, String)> = Vec::new();
+/// Read an archive entry as text, or `None` if the package has no such part.
+fn read_entry(archive: &mut PptxArchive, name: &str) -> Result<Option<String>, ConversionError> {
+    use std::io::Read;
 
-    for i in 0..archive.len() {
-        let mut entry = archive
-            .by_index(i)
-            .map_err(|e| ConversionError(format!("Failed to read archive entry: {}", e)))?;
+    let mut entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => {
+            return Err(ConversionError(format!(
+                "Failed to read archive entry: {}",
+                e
+            )))
+        }
+    };
+    let mut content = String::new();
+    entry
+        .read_to_string(&mut content)
+        .map_err(|e| ConversionError(format!("Failed to read {}: {}", name, e)))?;
+    Ok(Some(content))
+}
+
+/// Slide part paths in display order.
+/// The order is defined by `<p:sldIdLst>` in presentation.xml, whose entries
+/// point at slide parts through presentation.xml.rels. Slide file names say
+/// nothing about order, so they're only sorted by number as a fallback when the
+/// presentation part is missing or unreadable.
+fn slide_order(archive: &mut PptxArchive) -> Result<Vec<String>, ConversionError> {
+    let presentation = read_entry(archive, "ppt/presentation.xml")?;
+    let rels = read_entry(archive, "ppt/_rels/presentation.xml.rels")?;
+
+    if let (Some(presentation), Some(rels)) = (presentation, rels) {
+        let targets: Vec<(String, String)> = rels
+            .split("<Relationship ")
+            .skip(1)
+            .filter_map(|tag| Some((xml_attr(tag, "Id")?, xml_attr(tag, "Target")?)))
+            .collect();
+
+        let ordered: Vec<String> = presentation
+            .split("<p:sldId ")
+            .skip(1)
+            .filter_map(|tag| xml_attr(tag, "r:id"))
+            .filter_map(|rid| targets.iter().find(|(id, _)| *id == rid))
+            .map(|(_, target)| resolve_part_path("ppt", target))
+            .collect();
+        if !ordered.is_empty() {
+            return Ok(ordered);
+        }
+    }
 
-        let name = entry.name().to_string();
-        // Slide XML files are at ppt/slides/slide{N}.xml
-        if !name.starts_with("ppt/slides/slide") || !name.ends_with(".xml") {
+    let mut numbered: Vec<(usize, String)> = archive
+        .file_names()
+        .filter(|name| name.starts_with("ppt/slides/slide") && name.ends_with(".xml"))
+        .map(|name| {
+            let num = name
+                .trim_start_matches("ppt/slides/slide")
+                .trim_end_matches(".xml")
+                .parse()
+                .unwrap_or(0);
+            (num, name.to_string())
+        })
+        .collect();
+    numbered.sort_by_key(|(n, _)| *n);
+    Ok(numbered.into_iter().map(|(_, name)| name).collect())
+}
+
+/// Speaker notes of a slide as plain lines, found through the slide's
+/// notesSlide relationship. Only the notes body placeholder is read, so the
+/// slide thumbnail and slide number placeholders are skipped.
+fn read_slide_notes(
+    archive: &mut PptxArchive,
+    slide_path: &str,
+) -> Result<Option<Vec<String>>, ConversionError> {
+    let (slide_dir, slide_file) = slide_path.rsplit_once('/').unwrap_or(("", slide_path));
+    let rels_path = format!("{}/_rels/{}.rels", slide_dir, slide_file);
+    let Some(rels) = read_entry(archive, &rels_path)? else {
+        return Ok(None);
+    };
+    let Some(target) = rels
+        .split("<Relationship ")
+        .skip(1)
+        .filter(|tag| xml_attr(tag, "Type").is_some_and(|t| t.ends_with("/notesSlide")))
+        .find_map(|tag| xml_attr(tag, "Target"))
+    else {
+        return Ok(None);
+    };
+    let Some(notes_xml) = read_entry(archive, &resolve_part_path(slide_dir, &target))? else {
+        return Ok(None);
+    };
+
+    let mut lines: Vec<String> = Vec::new();
+    for shape in notes_xml.split("<p:sp>").skip(1) {
+        if !shape.contains(r#"<p:ph type="body""#) {
             continue;
         }
+        for para in shape.split("<a:p>").skip(1) {
+            let para = &para[..para.find("</a:p>").unwrap_or(para.len())];
+            let text: String = para
+                .split("<a:t>")
+                .filter_map(|part| part.find("</a:t>").map(|end| xml_unescape(&part[..end])))
+                .collect();
+            lines.push(text.trim().to_string());
+        }
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    while lines.first().is_some_and(|l| l.is_empty()) {
+        lines.remove(0);
+    }
+
+    Ok(if lines.is_empty() { None } else { Some(lines) })
+}
 
-        // Extract slide number from filename
-        let slide_num: usize = name
-            .trim_start_matches("ppt/slides/slide")
-            .trim_end_matches(".xml")
-            .parse()
-            .unwrap_or(0);
-
-        let mut content = String::new();
-        use std::io::Read;
-        entry
-            .read_to_string(&mut content)
-            .map_err(|e| ConversionError(format!("Failed to read slide XML: {}", e)))?;
-
-        let text = extract_text_from_slide_xml(&content);
-        if !text.is_empty() {
-            slides.push((slide_num, text));
+/// Speaker notes rendered as a `> Notes:` blockquote.
+fn notes_to_markdown(lines: &[String]) -> String {
+    let mut md = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        let line = if i == 0 {
+            format!("Notes: {}", line)
+        } else {
+            line.clone()
+        };
+        md.push_str(format!("> {}", line).trim_end());
+        md.push('\n');
+    }
+    md
+}
+
+/// Value of `name="..."` within a single XML start tag.
+fn xml_attr(tag: &str, name: &str) -> Option<String> {
+    let tag = &tag[..tag.find('>').unwrap_or(tag.len())];
+    let pattern = format!(" {}=\"", name);
+    // The split in the callers removes the element name, so also match at the start
+    let start = if tag.starts_with(&pattern[1..]) {
+        pattern.len() - 1
+    } else {
+        tag.find(&pattern)? + pattern.len()
+    };
+    let len = tag[start..].find('"')?;
+    Some(xml_unescape(&tag[start..start + len]))
+}
+
+/// Resolve a relationship target against the directory of the part that owns it.
+fn resolve_part_path(base_dir: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut parts: Vec<&str> = base_dir.split('/').filter(|p| !p.is_empty()).collect();
+    for segment in target.split('/') {
+        match segment {
+            ".." => {
+                parts.pop();
+            }
+            "." | "" => {}
+            _ => parts.push(segment),
         }
     }
+    parts.join("/")
+}
 
-    // Sort slides by number
-    slides.sort_by_key(|(n, _)| *n);
+fn xml_unescape(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
 
-    let mut output = String::new();
-    for (i, (_, text)) in slides.iter().enumerate() {
-        if i > 0 {
-            output.push('\n');
+/// A `<p:sp>` shape and what decides where it comes in reading order
+struct Shape<'a> {
+    xml: &'a str,
+    /// A title or centered title placeholder
+    is_title: bool,
+    /// `<a:off>` as (y, x), so sorting goes top to bottom, then left to right
+    offset: Option<(i64, i64)>,
+}
+
+impl<'a> Shape<'a> {
+    fn parse(xml: &'a str) -> Self {
+        let tag_after = |name: &str| xml.find(name).map(|i| &xml[i + name.len()..]);
+        let is_title = tag_after("<p:ph")
+            .and_then(|tag| xml_attr(tag, "type"))
+            .is_some_and(|kind| kind == "title" || kind == "ctrTitle");
+        let offset = tag_after("<a:off").and_then(|tag| {
+            let coordinate = |name| xml_attr(tag, name).and_then(|v| v.parse::<i64>().ok());
+            Some((coordinate("y")?, coordinate("x")?))
+        });
+        Shape {
+            xml,
+            is_title,
+            offset,
         }
-        output.push_str(text);
-        output.push('\n');
     }
+}
+
+/// The slide XML with its shapes in reading order: title placeholders first,
+/// then top to bottom and left to right. Shapes keep their XML order when any
+/// of them has no position. Content outside shapes, such as tables, follows.
+fn slide_xml_in_reading_order(xml: &str) -> String {
+    let mut shapes = Vec::new();
+    let mut outside = Vec::new();
+    let mut rest = xml;
+    // `<p:sp` alone would also match `<p:spTree>` and `<p:spPr>`
+    while let Some(start) = ["<p:sp>", "<p:sp "]
+        .iter()
+        .filter_map(|tag| rest.find(tag))
+        .min()
+    {
+        let end = rest[start..]
+            .find("</p:sp>")
+            .map_or(rest.len(), |i| start + i + "</p:sp>".len());
+        outside.push(&rest[..start]);
+        shapes.push(Shape::parse(&rest[start..end]));
+        rest = &rest[end..];
+    }
+    outside.push(rest);
 
-    Ok(output)
+    if shapes.iter().all(|shape| shape.offset.is_some()) {
+        shapes.sort_by_key(|shape| (!shape.is_title, shape.offset));
+    } else {
+        shapes.sort_by_key(|shape| !shape.is_title);
+    }
+    shapes
+        .iter()
+        .map(|shape| shape.xml)
+        .chain(outside)
+        .collect()
 }
 
-/// Extract plain text from PPTX slide XML.
-/// Looks for <a:t> tags which contain the actual text content.
-fn extract_text_from_slide_xml(xml: &str) -> String {
-    let mut output = String::new();
-    let mut first_para = true;
+/// Extract slide text from PPTX slide XML as Markdown.
+/// Looks for <a:t> tags which contain the actual text content, reading shapes
+/// in [`slide_xml_in_reading_order`]. The first non-empty paragraph is the
+/// slide title, a heading of `title_level`; the rest become `- ` bullets
+/// indented by their `<a:pPr lvl>`, with empty paragraphs kept as blank
+/// separators.
+fn extract_text_from_slide_xml(xml: &str, title_level: usize) -> String {
+    let mut title: Option<String> = None;
+    let mut body: Vec<String> = Vec::new();
+    let xml = slide_xml_in_reading_order(xml);
 
     // Simple XML text extraction: find <a:t>...</a:t> within <a:p> blocks
-    for para in xml.split("<a:p>") {
+    for para in xml.split("<a:p>").skip(1) {
+        let para = &para[..para.find("</a:p>").unwrap_or(para.len())];
         let mut para_text = String::new();
         for part in para.split("<a:t>") {
             if let Some(end) = part.find("</a:t>") {
-                let text = &part[..end];
-                // Decode basic XML entities
-                let decoded = text
-                    .replace("&amp;", "&")
-                    .replace("&lt;", "<")
-                    .replace("&gt;", ">")
-                    .replace("&quot;", "\"")
-                    .replace("&apos;", "'");
-                para_text.push_str(&decoded);
+                para_text.push_str(&xml_unescape(&part[..end]));
             }
         }
-        let trimmed = para_text.trim().to_string();
-        if !trimmed.is_empty() {
-            if first_para {
-                // First non-empty paragraph becomes a heading
-                output.push_str(&format!("# {}\n", trimmed));
-                first_para = false;
-            } else {
-                output.push_str(&format!("\n{}", trimmed));
+        let trimmed = para_text.trim();
+
+        if title.is_none() {
+            if !trimmed.is_empty() {
+                title = Some(trimmed.to_string());
             }
+        } else if trimmed.is_empty() {
+            if body.last().is_some_and(|l| !l.is_empty()) {
+                body.push(String::new());
+            }
+        } else {
+            let level = para
+                .find("<a:pPr")
+                .and_then(|i| xml_attr(&para[i + "<a:pPr".len()..], "lvl"))
+                .and_then(|lvl| lvl.parse::<usize>().ok())
+                .unwrap_or(0);
+            body.push(format!("{}- {}", "  ".repeat(level), trimmed));
         }
     }
 
+    let Some(title) = title else {
+        return String::new();
+    };
+    while body.last().is_some_and(|l| l.is_empty()) {
+        body.pop();
+    }
+
+    let mut output = format!("{} {}\n", "#".repeat(title_level), title);
+    if !body.is_empty() {
+        output.push('\n');
+        for line in body {
+            output.push_str(&line);
+            output.push('\n');
+        }
+    }
     output
 }
 
+/// Line that separates a slide's content from its speaker notes
+const NOTES_MARKER: &str = "---notes";
+
+/// One slide's content when exporting.
+struct Slide {
+    title: String,
+    /// A `## ` line directly after the title
+    subtitle: Option<String>,
+    body: Vec<String>,
+    /// Speaker notes, one paragraph per line
+    notes: Vec<String>,
+}
+
 /// Convert Markdown to a PPTX file.
 /// `# Heading` boundaries define slide splits.
 /// Each heading starts a new slide; remaining content goes in the slide body.
+/// A `## ` line right after the title becomes the slide subtitle; later ones
+/// become bold paragraphs in the body. Lines after a `---notes` line, up to the
+/// next heading, become the slide's speaker notes.
 pub fn markdown_to_pptx(markdown: &str, path: &str) -> Result<(), ConversionError> {
     // Parse slides from markdown: split on H1 headings
-    let mut slides: Vec<(String, Vec<String>)> = Vec::new();
+    let mut slides: Vec<Slide> = Vec::new();
     let mut current_title = String::new();
+    let mut current_subtitle: Option<String> = None;
     let mut current_body: Vec<String> = Vec::new();
+    let mut current_notes: Vec<String> = Vec::new();
+    let mut in_notes = false;
 
     for line in markdown.lines() {
         if line.starts_with("# ") && !line.starts_with("## ") {
             // New slide
             if !current_title.is_empty() || !current_body.is_empty() {
-                slides.push((current_title.clone(), current_body.clone()));
+                slides.push(Slide {
+                    title: current_title.clone(),
+                    subtitle: current_subtitle.take(),
+                    body: current_body.clone(),
+                    notes: std::mem::take(&mut current_notes),
+                });
             }
             current_title = line.trim_start_matches("# ").to_string();
+            current_subtitle = None;
             current_body.clear();
+            current_notes.clear();
+            in_notes = false;
+        } else if line.trim() == NOTES_MARKER {
+            in_notes = true;
+        } else if line.starts_with("## ")
+            && !in_notes
+            && current_subtitle.is_none()
+            && current_body.is_empty()
+            && !current_title.is_empty()
+        {
+            current_subtitle = Some(inline_text(line.trim_start_matches("## ")));
         } else if !line.trim().is_empty() {
-            current_body.push(line.to_string());
+            if in_notes {
+                current_notes.push(line.trim().to_string());
+            } else {
+                current_body.push(line.to_string());
+            }
         }
     }
     // Last slide
     if !current_title.is_empty() || !current_body.is_empty() {
-        slides.push((current_title, current_body));
+        slides.push(Slide {
+            title: current_title,
+            subtitle: current_subtitle,
+            body: current_body,
+            notes: current_notes,
+        });
     }
 
     if slides.is_empty() {
         // Create a single slide with the entire content as body
-        slides.push(("Presentation".to_string(),
-                      markdown.lines().map(|l| l.to_string()).collect()));
+        slides.push(Slide {
+            title: "Presentation".to_string(),
+            subtitle: None,
+            body: markdown.lines().map(|l| l.to_string()).collect(),
+            notes: Vec::new(),
+        });
     }
 
     build_pptx(slides, path)
@@ -131,7 +441,7 @@ pub fn markdown_to_pptx(markdown: &str, path: &str) -> Result<(), ConversionErro
 
 /// Build a minimal PPTX file from slide data.
 /// PPTX is a ZIP file with specific XML structure.
-fn build_pptx(slides: Vec<(String, Vec<String>)>, path: &str) -> Result<(), ConversionError> {
+fn build_pptx(slides: Vec<Slide>, path: &str) -> Result<(), ConversionError> {
     use std::io::Write;
 
     let file = std::fs::File::create(path)
@@ -142,6 +452,11 @@ fn build_pptx(slides: Vec<(String, Vec<String>)>, path: &str) -> Result<(), Conv
     let options = zip::write::SimpleFileOptions::default()
         .compression_method(zip::CompressionMethod::Deflated);
 
+    // The notes master is only written when some slide has notes. Its
+    // relationship id follows the slide relationships.
+    let has_notes = slides.iter().any(|s| !s.notes.is_empty());
+    let notes_master_rid = slides.len() + 2;
+
     // [Content_Types].xml
     zip.start_file("[Content_Types].xml", options)
         .map_err(|e| ConversionError(format!("ZIP error: {}", e)))?;
@@ -155,12 +470,25 @@ fn build_pptx(slides: Vec<(String, Vec<String>)>, path: &str) -> Result<(), Conv
   <Override PartName="/ppt/slideMasters/slideMaster1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slideMaster+xml"/>
 "#,
     );
-    for i in 0..slides.len() {
+    for (i, slide) in slides.iter().enumerate() {
         content_types.push_str(&format!(
             r#"  <Override PartName="/ppt/slides/slide{}.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.slide+xml"/>
 "#,
             i + 1
         ));
+        if !slide.notes.is_empty() {
+            content_types.push_str(&format!(
+                r#"  <Override PartName="/ppt/notesSlides/notesSlide{}.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.notesSlide+xml"/>
+"#,
+                i + 1
+            ));
+        }
+    }
+    if has_notes {
+        content_types.push_str(
+            r#"  <Override PartName="/ppt/notesMasters/notesMaster1.xml" ContentType="application/vnd.openxmlformats-officedocument.presentationml.notesMaster+xml"/>
+"#,
+        );
     }
     content_types.push_str("</Types>");
     zip.write_all(content_types.as_bytes())
@@ -194,6 +522,13 @@ fn build_pptx(slides: Vec<(String, Vec<String>)>, path: &str) -> Result<(), Conv
             i + 1
         ));
     }
+    if has_notes {
+        pres_rels.push_str(&format!(
+            r#"  <Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/notesMaster" Target="notesMasters/notesMaster1.xml"/>
+"#,
+            notes_master_rid
+        ));
+    }
     pres_rels.push_str("</Relationships>");
     zip.write_all(pres_rels.as_bytes())
         .map_err(|e| ConversionError(format!("ZIP write error: {}", e)))?;
@@ -210,6 +545,17 @@ fn build_pptx(slides: Vec<(String, Vec<String>)>, path: &str) -> Result<(), Conv
             i + 2
         ));
     }
+    let notes_master_list = if has_notes {
+        format!(
+            r#"  <p:notesMasterIdLst>
+    <p:notesMasterId r:id="rId{}"/>
+  </p:notesMasterIdLst>
+"#,
+            notes_master_rid
+        )
+    } else {
+        String::new()
+    };
     let pres_xml = format!(
         r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
 <p:presentation xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main"
@@ -218,12 +564,12 @@ fn build_pptx(slides: Vec<(String, Vec<String>)>, path: &str) -> Result<(), Conv
   <p:sldMasterIdLst>
     <p:sldMasterId id="2147483648" r:id="rId1"/>
   </p:sldMasterIdLst>
-  <p:sldIdLst>
+{}  <p:sldIdLst>
 {}  </p:sldIdLst>
   <p:sldSz cx="9144000" cy="6858000"/>
   <p:notesSz cx="6858000" cy="9144000"/>
 </p:presentation>"#,
-        slide_list
+        notes_master_list, slide_list
     );
     zip.write_all(pres_xml.as_bytes())
         .map_err(|e| ConversionError(format!("ZIP write error: {}", e)))?;
@@ -276,26 +622,82 @@ fn build_pptx(slides: Vec<(String, Vec<String>)>, path: &str) -> Result<(), Conv
     )
     .map_err(|e| ConversionError(format!("ZIP write error: {}", e)))?;
 
+    if has_notes {
+        // Minimal notes master
+        zip.start_file("ppt/notesMasters/notesMaster1.xml", options)
+            .map_err(|e| ConversionError(format!("ZIP error: {}", e)))?;
+        zip.write_all(
+            br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:notesMaster xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main"
+  xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"
+  xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+  <p:cSld><p:spTree><p:nvGrpSpPr><p:cNvPr id="1" name=""/><p:cNvGrpSpPr/><p:nvPr/></p:nvGrpSpPr><p:grpSpPr/></p:spTree></p:cSld>
+  <p:clrMap bg1="lt1" tx1="dk1" bg2="lt2" tx2="dk2" accent1="accent1" accent2="accent2" accent3="accent3" accent4="accent4" accent5="accent5" accent6="accent6" hlink="hlink" folHlink="folHlink"/>
+</p:notesMaster>"#,
+        )
+        .map_err(|e| ConversionError(format!("ZIP write error: {}", e)))?;
+    }
+
     // Individual slides
-    for (i, (title, body_lines)) in slides.iter().enumerate() {
+    for (i, slide) in slides.iter().enumerate() {
         let slide_path = format!("ppt/slides/slide{}.xml", i + 1);
         let rels_path = format!("ppt/slides/_rels/slide{}.xml.rels", i + 1);
 
         // Slide rels
+        let notes_rel = if slide.notes.is_empty() {
+            String::new()
+        } else {
+            format!(
+                r#"  <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/notesSlide" Target="../notesSlides/notesSlide{}.xml"/>
+"#,
+                i + 1
+            )
+        };
         zip.start_file(&rels_path, options)
             .map_err(|e| ConversionError(format!("ZIP error: {}", e)))?;
         zip.write_all(
-            br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
 <Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
   <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slideLayout" Target="../slideLayouts/slideLayout1.xml"/>
-</Relationships>"#,
+{}</Relationships>"#,
+                notes_rel
+            )
+            .as_bytes(),
         )
         .map_err(|e| ConversionError(format!("ZIP write error: {}", e)))?;
 
+        if !slide.notes.is_empty() {
+            write_notes_slide(&mut zip, options, i + 1, &slide.notes)?;
+        }
+
         // Slide XML
-        let title_escaped = xml_escape(title);
-        let body_text = body_lines.join("\n");
-        let body_escaped = xml_escape(&body_text);
+        let title_escaped = xml_escape(&slide.title);
+        let body_paragraphs: String = slide
+            .body
+            .iter()
+            .map(|line| body_paragraph_xml(line))
+            .collect();
+
+        // With a subtitle, the body moves down to make room for it
+        let (subtitle_shape, body_y, body_cy) = match &slide.subtitle {
+            Some(subtitle) => (
+                format!(
+                    r#"
+      <p:sp>
+        <p:nvSpPr><p:cNvPr id="4" name="Subtitle"/><p:cNvSpPr><a:spLocks noGrp="1"/></p:cNvSpPr><p:nvPr><p:ph type="subTitle" idx="2"/></p:nvPr></p:nvSpPr>
+        <p:spPr><a:xfrm><a:off x="457200" y="1417638"/><a:ext cx="8229600" cy="533400"/></a:xfrm></p:spPr>
+        <p:txBody><a:bodyPr/><a:lstStyle/>
+          <a:p><a:r><a:rPr lang="en-US" dirty="0"/><a:t>{}</a:t></a:r></a:p>
+        </p:txBody>
+      </p:sp>"#,
+                    xml_escape(subtitle)
+                ),
+                2057400,
+                4068763,
+            ),
+            None => (String::new(), 1600200, 4525963),
+        };
 
         let slide_xml = format!(
             r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
@@ -312,18 +714,18 @@ fn build_pptx(slides: Vec<(String, Vec<String>)>, path: &str) -> Result<(), Conv
         <p:txBody><a:bodyPr/><a:lstStyle/>
           <a:p><a:r><a:rPr lang="en-US" dirty="0"/><a:t>{}</a:t></a:r></a:p>
         </p:txBody>
-      </p:sp>
+      </p:sp>{}
       <p:sp>
         <p:nvSpPr><p:cNvPr id="3" name="Body"/><p:cNvSpPr><a:spLocks noGrp="1"/></p:cNvSpPr><p:nvPr><p:ph idx="1"/></p:nvPr></p:nvSpPr>
-        <p:spPr><a:xfrm><a:off x="457200" y="1600200"/><a:ext cx="8229600" cy="4525963"/></a:xfrm></p:spPr>
+        <p:spPr><a:xfrm><a:off x="457200" y="{}"/><a:ext cx="8229600" cy="{}"/></a:xfrm></p:spPr>
         <p:txBody><a:bodyPr/><a:lstStyle/>
-          <a:p><a:r><a:rPr lang="en-US" dirty="0"/><a:t>{}</a:t></a:r></a:p>
+          {}
         </p:txBody>
       </p:sp>
     </p:spTree>
   </p:cSld>
 </p:sld>"#,
-            title_escaped, body_escaped
+            title_escaped, subtitle_shape, body_y, body_cy, body_paragraphs
         );
 
         zip.start_file(&slide_path, options)
@@ -338,6 +740,175 @@ fn build_pptx(slides: Vec<(String, Vec<String>)>, path: &str) -> Result<(), Conv
     Ok(())
 }
 
+/// Left margin per bullet level and the hanging indent for the bullet, in EMU
+const BULLET_MARGIN: usize = 457200;
+const BULLET_HANGING: usize = 342900;
+
+/// One `<a:p>` for a line of slide body Markdown. List items become bullets
+/// at their nesting level (two spaces per level), `##`-`######` lines become
+/// bold paragraphs, and inline Markdown syntax is stripped.
+fn body_paragraph_xml(line: &str) -> String {
+    let indent = line.len() - line.trim_start().len();
+    let trimmed = line.trim();
+
+    if let Some(item) = list_item_text(trimmed) {
+        let level = indent / 2;
+        return format!(
+            r#"<a:p><a:pPr marL="{}" lvl="{}" indent="-{}"><a:buChar char="&#8226;"/></a:pPr>{}</a:p>"#,
+            BULLET_HANGING + level * BULLET_MARGIN,
+            level,
+            BULLET_HANGING,
+            inline_runs_xml(item, false)
+        );
+    }
+    let heading_marks = trimmed.chars().take_while(|c| *c == '#').count();
+    if (2..=6).contains(&heading_marks) && trimmed[heading_marks..].starts_with(' ') {
+        return format!(
+            "<a:p>{}</a:p>",
+            inline_runs_xml(trimmed[heading_marks..].trim(), true)
+        );
+    }
+    format!("<a:p>{}</a:p>", inline_runs_xml(trimmed, false))
+}
+
+/// `<a:r>` runs for an inline Markdown fragment, bold/italic where the Markdown
+/// has `**`/`*` emphasis. Text without emphasis stays a single run.
+fn inline_runs_xml(markdown: &str, bold: bool) -> String {
+    let events: Vec<Event> = Parser::new(markdown).collect();
+    let has_emphasis = events
+        .iter()
+        .any(|e| matches!(e, Event::Start(Tag::Strong | Tag::Emphasis)));
+    if !has_emphasis {
+        return run_xml(&inline_text(markdown), bold, false);
+    }
+
+    let mut runs = String::new();
+    let mut strong_depth = 0;
+    let mut emphasis_depth = 0;
+    for event in events {
+        match event {
+            Event::Start(Tag::Strong) => strong_depth += 1,
+            Event::End(TagEnd::Strong) => strong_depth -= 1,
+            Event::Start(Tag::Emphasis) => emphasis_depth += 1,
+            Event::End(TagEnd::Emphasis) => emphasis_depth -= 1,
+            Event::Text(t) | Event::Code(t) => {
+                runs.push_str(&run_xml(&t, bold || strong_depth > 0, emphasis_depth > 0));
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                runs.push_str(&run_xml(" ", bold || strong_depth > 0, emphasis_depth > 0));
+            }
+            _ => {}
+        }
+    }
+    runs
+}
+
+fn run_xml(text: &str, bold: bool, italic: bool) -> String {
+    let bold_attr = if bold { r#" b="1""# } else { "" };
+    let italic_attr = if italic { r#" i="1""# } else { "" };
+    format!(
+        r#"<a:r><a:rPr lang="en-US"{}{} dirty="0"/><a:t>{}</a:t></a:r>"#,
+        bold_attr,
+        italic_attr,
+        xml_escape(text)
+    )
+}
+
+/// Text after a `- `, `* `, `+ ` or `1. ` list marker.
+fn list_item_text(line: &str) -> Option<&str> {
+    if let Some(rest) = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))
+    {
+        return Some(rest);
+    }
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    if digits > 0 {
+        return line[digits..]
+            .strip_prefix(". ")
+            .or_else(|| line[digits..].strip_prefix(") "));
+    }
+    None
+}
+
+/// Plain text of an inline Markdown fragment, without emphasis or code markers.
+fn inline_text(markdown: &str) -> String {
+    let mut text = String::new();
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Write `ppt/notesSlides/notesSlide{n}.xml` and its relationships to the
+/// slide and the notes master.
+fn write_notes_slide(
+    zip: &mut zip::ZipWriter<std::fs::File>,
+    options: zip::write::SimpleFileOptions,
+    n: usize,
+    notes: &[String],
+) -> Result<(), ConversionError> {
+    use std::io::Write;
+
+    zip.start_file(
+        format!("ppt/notesSlides/_rels/notesSlide{}.xml.rels", n),
+        options,
+    )
+    .map_err(|e| ConversionError(format!("ZIP error: {}", e)))?;
+    zip.write_all(
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/notesMaster" Target="../notesMasters/notesMaster1.xml"/>
+  <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/slide" Target="../slides/slide{}.xml"/>
+</Relationships>"#,
+            n
+        )
+        .as_bytes(),
+    )
+    .map_err(|e| ConversionError(format!("ZIP write error: {}", e)))?;
+
+    let paragraphs: String = notes
+        .iter()
+        .map(|line| {
+            format!(
+                r#"<a:p><a:r><a:rPr lang="en-US" dirty="0"/><a:t>{}</a:t></a:r></a:p>"#,
+                xml_escape(line)
+            )
+        })
+        .collect();
+    let notes_xml = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<p:notes xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main"
+  xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"
+  xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+  <p:cSld>
+    <p:spTree>
+      <p:nvGrpSpPr><p:cNvPr id="1" name=""/><p:cNvGrpSpPr/><p:nvPr/></p:nvGrpSpPr>
+      <p:grpSpPr/>
+      <p:sp>
+        <p:nvSpPr><p:cNvPr id="2" name="Notes Placeholder"/><p:cNvSpPr><a:spLocks noGrp="1"/></p:cNvSpPr><p:nvPr><p:ph type="body" idx="1"/></p:nvPr></p:nvSpPr>
+        <p:spPr/>
+        <p:txBody><a:bodyPr/><a:lstStyle/>{}</p:txBody>
+      </p:sp>
+    </p:spTree>
+  </p:cSld>
+</p:notes>"#,
+        paragraphs
+    );
+    zip.start_file(format!("ppt/notesSlides/notesSlide{}.xml", n), options)
+        .map_err(|e| ConversionError(format!("ZIP error: {}", e)))?;
+    zip.write_all(notes_xml.as_bytes())
+        .map_err(|e| ConversionError(format!("ZIP write error: {}", e)))?;
+
+    Ok(())
+}
+
 fn xml_escape(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -345,3 +916,237 @@ fn xml_escape(s: &str) -> String {
         .replace('"', "&quot;")
         .replace('\'', "&apos;")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn slide_xml(title: &str) -> String {
+        format!(
+            r#"<p:sld><p:cSld><p:spTree><p:sp><p:txBody><a:p><a:r><a:t>{}</a:t></a:r></a:p></p:txBody></p:sp></p:spTree></p:cSld></p:sld>"#,
+            title
+        )
+    }
+
+    /// A ZIP of `parts` in a temporary file, deleted when the returned path is dropped
+    fn write_package(parts: &[(&str, String)]) -> tempfile::TempPath {
+        let file = tempfile::Builder::new().suffix(".pptx").tempfile().unwrap();
+        let mut zip = zip::ZipWriter::new(file.as_file());
+        for (part, content) in parts {
+            zip.start_file(*part, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        file.into_temp_path()
+    }
+
+    #[test]
+    fn test_pptx_to_markdown_follows_presentation_slide_order() {
+        let presentation = r#"<p:presentation><p:sldIdLst>
+            <p:sldId id="256" r:id="rId3"/><p:sldId id="257" r:id="rId4"/><p:sldId id="258" r:id="rId2"/>
+            </p:sldIdLst></p:presentation>"#;
+        let rels = r#"<Relationships>
+            <Relationship Id="rId2" Type="slide" Target="slides/slide1.xml"/>
+            <Relationship Id="rId3" Type="slide" Target="slides/slide10.xml"/>
+            <Relationship Id="rId4" Type="slide" Target="/ppt/slides/slide2.xml"/>
+            </Relationships>"#;
+        let path = write_package(&[
+            ("ppt/presentation.xml", presentation.to_string()),
+            ("ppt/_rels/presentation.xml.rels", rels.to_string()),
+            ("ppt/slides/slide1.xml", slide_xml("Closing")),
+            ("ppt/slides/slide2.xml", slide_xml("Middle")),
+            ("ppt/slides/slide10.xml", slide_xml("Opening")),
+        ]);
+
+        let md = pptx_to_markdown(path.to_str().unwrap()).unwrap();
+
+        assert_eq!(md, "# Opening\n\n# Middle\n\n# Closing\n");
+    }
+
+    #[test]
+    fn test_pptx_to_markdown_uses_deck_title() {
+        let core =
+            r#"<cp:coreProperties><dc:title>Q3 Review &amp; Plan</dc:title></cp:coreProperties>"#;
+        let path = write_package(&[
+            ("docProps/core.xml", core.to_string()),
+            ("ppt/slides/slide1.xml", slide_xml("Results")),
+            ("ppt/slides/slide2.xml", slide_xml("Next steps")),
+        ]);
+        let md = pptx_to_markdown(path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            md,
+            "# Q3 Review & Plan\n\n## Results\n\n---\n\n## Next steps\n"
+        );
+
+        let untitled = write_package(&[
+            (
+                "docProps/core.xml",
+                "<cp:coreProperties><dc:title></dc:title></cp:coreProperties>".to_string(),
+            ),
+            ("ppt/slides/slide1.xml", slide_xml("Results")),
+        ]);
+        let md = pptx_to_markdown(untitled.to_str().unwrap()).unwrap();
+        assert_eq!(md, "# Results\n");
+    }
+
+    #[test]
+    fn test_extract_text_from_slide_xml_nested_bullets() {
+        let xml = r#"<p:sld><p:sp><p:txBody><a:p><a:r><a:t>Agenda</a:t></a:r></a:p></p:txBody></p:sp>
+            <p:sp><p:txBody>
+            <a:p><a:r><a:t>Status</a:t></a:r></a:p>
+            <a:p><a:pPr lvl="1"/><a:r><a:t>Budget &amp; costs</a:t></a:r></a:p>
+            <a:p><a:pPr marL="0" lvl="1" indent="0"/><a:r><a:t>Timeline</a:t></a:r></a:p>
+            <a:p><a:endParaRPr/></a:p>
+            <a:p><a:r><a:t>Next steps</a:t></a:r></a:p>
+            <a:p></a:p>
+            </p:txBody></p:sp></p:sld>"#;
+
+        assert_eq!(
+            extract_text_from_slide_xml(xml, 1),
+            "# Agenda\n\n- Status\n  - Budget & costs\n  - Timeline\n\n- Next steps\n"
+        );
+    }
+
+    #[test]
+    fn test_extract_text_from_slide_xml_reading_order() {
+        let shape = |ph: &str, x: u32, y: u32, text: &str| {
+            format!(
+                r#"<p:sp><p:nvSpPr><p:nvPr>{}</p:nvPr></p:nvSpPr><p:spPr><a:xfrm><a:off x="{}" y="{}"/></a:xfrm></p:spPr><p:txBody><a:p><a:r><a:t>{}</a:t></a:r></a:p></p:txBody></p:sp>"#,
+                ph, x, y, text
+            )
+        };
+        // Body shapes before the title in the XML, the right column first
+        let xml = format!(
+            "<p:sld><p:cSld><p:spTree>{}{}{}</p:spTree></p:cSld></p:sld>",
+            shape(r#"<p:ph idx="2"/>"#, 4_800_000, 1_600_000, "Right column"),
+            shape(r#"<p:ph idx="1"/>"#, 400_000, 1_600_000, "Left column"),
+            shape(r#"<p:ph type="title"/>"#, 400_000, 300_000, "Overview"),
+        );
+        assert_eq!(
+            extract_text_from_slide_xml(&xml, 1),
+            "# Overview\n\n- Left column\n- Right column\n"
+        );
+
+        // Without positions only the title placeholder moves
+        let unpositioned = xml.replace("<a:off", "<a:ext");
+        assert_eq!(
+            extract_text_from_slide_xml(&unpositioned, 1),
+            "# Overview\n\n- Right column\n- Left column\n"
+        );
+    }
+
+    #[test]
+    fn test_speaker_notes_round_trip() {
+        let md = "# Welcome\n\n---notes\nThank the organisers\nMention Q&A at the end\n";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.pptx");
+        let path_str = path.to_str().unwrap();
+
+        markdown_to_pptx(md, path_str).unwrap();
+        let imported = pptx_to_markdown(path_str).unwrap();
+
+        assert_eq!(
+            imported,
+            "# Welcome\n\n> Notes: Thank the organisers\n> Mention Q&A at the end\n"
+        );
+    }
+
+    #[test]
+    fn test_pptx_to_markdown_keeps_notes_of_slides_without_text() {
+        let notes_rels = r#"<Relationships><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/notesSlide" Target="../notesSlides/notesSlide2.xml"/></Relationships>"#;
+        let notes = r#"<p:notes><p:cSld><p:spTree><p:sp><p:nvSpPr><p:nvPr><p:ph type="body" idx="1"/></p:nvPr></p:nvSpPr><p:txBody><a:p><a:r><a:t>Pause for questions</a:t></a:r></a:p></p:txBody></p:sp></p:spTree></p:cSld></p:notes>"#;
+        let parts = [
+            ("ppt/slides/slide1.xml", slide_xml("Intro")),
+            (
+                "ppt/slides/slide2.xml",
+                "<p:sld><p:cSld><p:spTree/></p:cSld></p:sld>".to_string(),
+            ),
+            ("ppt/slides/_rels/slide2.xml.rels", notes_rels.to_string()),
+            ("ppt/notesSlides/notesSlide2.xml", notes.to_string()),
+            ("ppt/slides/slide3.xml", slide_xml("Outro")),
+        ];
+        let path = write_package(&parts);
+        let md = pptx_to_markdown(path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            md,
+            "# Intro\n\n---\n\n> Notes: Pause for questions\n\n# Outro\n"
+        );
+
+        let mut titled = parts.to_vec();
+        titled.push((
+            "docProps/core.xml",
+            "<cp:coreProperties><dc:title>Talk</dc:title></cp:coreProperties>".to_string(),
+        ));
+        let path = write_package(&titled);
+        let md = pptx_to_markdown(path.to_str().unwrap()).unwrap();
+        assert_eq!(
+            md,
+            "# Talk\n\n## Intro\n\n---\n\n> Notes: Pause for questions\n\n---\n\n## Outro\n"
+        );
+    }
+
+    fn read_part(path: &std::path::Path, part: &str) -> String {
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(path).unwrap()).unwrap();
+        read_entry(&mut archive, part).unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_markdown_to_pptx_subtitle_and_clean_body() {
+        let md = "# Roadmap\n## Q3 priorities\n- **Ship** sync\n  - Conflict UI\n## Risks\nStaffing is *tight*\n";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("subtitle.pptx");
+
+        markdown_to_pptx(md, path.to_str().unwrap()).unwrap();
+        let slide = read_part(&path, "ppt/slides/slide1.xml");
+
+        let subtitle = slide
+            .split("<p:sp>")
+            .find(|sp| sp.contains(r#"type="subTitle""#))
+            .unwrap();
+        assert!(subtitle.contains("<a:t>Q3 priorities</a:t>"));
+        assert!(slide.contains(r#"lvl="1""#));
+        assert!(slide.contains(r#"b="1" dirty="0"/><a:t>Ship</a:t>"#));
+        assert!(slide.contains(r#"b="1" dirty="0"/><a:t>Risks</a:t>"#));
+        assert!(slide.contains(r#"i="1" dirty="0"/><a:t>tight</a:t>"#));
+        assert!(!slide.contains("##") && !slide.contains("**") && !slide.contains("- "));
+    }
+
+    #[test]
+    fn test_inline_runs_xml_emphasis() {
+        let runs = inline_runs_xml("Ship **by Friday** or *later*", false);
+        assert_eq!(
+            runs,
+            concat!(
+                r#"<a:r><a:rPr lang="en-US" dirty="0"/><a:t>Ship </a:t></a:r>"#,
+                r#"<a:r><a:rPr lang="en-US" b="1" dirty="0"/><a:t>by Friday</a:t></a:r>"#,
+                r#"<a:r><a:rPr lang="en-US" dirty="0"/><a:t> or </a:t></a:r>"#,
+                r#"<a:r><a:rPr lang="en-US" i="1" dirty="0"/><a:t>later</a:t></a:r>"#,
+            )
+        );
+        // No emphasis: one run
+        assert_eq!(
+            inline_runs_xml("plain `code`", false)
+                .matches("<a:r>")
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_resolve_part_path() {
+        assert_eq!(
+            resolve_part_path("ppt", "slides/slide1.xml"),
+            "ppt/slides/slide1.xml"
+        );
+        assert_eq!(
+            resolve_part_path("ppt/slides", "../notesSlides/notesSlide1.xml"),
+            "ppt/notesSlides/notesSlide1.xml"
+        );
+        assert_eq!(
+            resolve_part_path("ppt", "/ppt/slides/slide2.xml"),
+            "ppt/slides/slide2.xml"
+        );
+    }
+}
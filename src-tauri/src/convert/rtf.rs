@@ -0,0 +1,404 @@
+use encoding_rs::{Encoding, BIG5, EUC_KR, GBK, MACINTOSH, SHIFT_JIS, UTF_8, WINDOWS_1252};
+
+use super::{check_signature, ConversionError};
+
+/// Groups whose content is not document text: fonts, colours, styles,
+/// metadata, pictures and embedded objects
+const SKIPPED_DESTINATIONS: &[&str] = &[
+    "fonttbl",
+    "colortbl",
+    "stylesheet",
+    "info",
+    "pict",
+    "object",
+    "header",
+    "headerl",
+    "headerr",
+    "headerf",
+    "footer",
+    "footerl",
+    "footerr",
+    "footerf",
+    "footnote",
+    "listtable",
+    "listoverridetable",
+    "rsidtbl",
+    "generator",
+    "themedata",
+    "colorschememapping",
+    "datastore",
+    "latentstyles",
+    "xmlnstbl",
+    "filetbl",
+    "revtbl",
+    "fldinst",
+    "bkmkstart",
+    "bkmkend",
+    "nonshppict",
+    "shp",
+];
+
+/// Character formatting in effect for a group
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Format {
+    bold: bool,
+    italic: bool,
+    underline: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Group {
+    format: Format,
+    /// Inside a destination whose text is dropped
+    skip: bool,
+    /// Fallback characters to drop after a `\u` character (`\ucN`)
+    unicode_skip: usize,
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Group {
+            format: Format::default(),
+            skip: false,
+            unicode_skip: 1,
+        }
+    }
+}
+
+/// Convert an RTF file to Markdown text.
+///
+/// Best effort, text only: paragraphs, line breaks and bold/italic/underline
+/// are kept; fonts, colours, images and embedded objects are dropped. Tables
+/// are not rebuilt: the text of a row's cells runs together in one paragraph.
+pub fn rtf_to_markdown(path: &str) -> Result<String, ConversionError> {
+    check_signature(path, "rtf")?;
+    let bytes =
+        std::fs::read(path).map_err(|e| ConversionError(format!("Failed to read file: {}", e)))?;
+    Ok(rtf_text_to_markdown(&bytes))
+}
+
+/// Convert RTF source to Markdown. `\'hh` escapes and 8-bit text are decoded
+/// in the `\ansicpg` code page, Windows-1252 when the document names none.
+fn rtf_text_to_markdown(rtf: &[u8]) -> String {
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut runs: Vec<(String, Format)> = Vec::new();
+    let mut stack: Vec<Group> = Vec::new();
+    let mut group = Group::default();
+    // Fallback characters still to drop after a `\u` character
+    let mut pending_skip = 0;
+    // Set by `\*`: the group is an optional destination we don't understand
+    let mut ignorable = false;
+    // Nothing but `\*` seen since the last `{`: a control word here names a destination
+    let mut group_start = false;
+    let mut code_page = WINDOWS_1252;
+    // Consecutive `\'hh` bytes, decoded together so double-byte characters survive
+    let mut bytes: Vec<u8> = Vec::new();
+
+    let mut i = 0;
+    while i < rtf.len() {
+        let byte = rtf[i];
+        let continues_bytes = byte == b'\\' && rtf.get(i + 1) == Some(&b'\'');
+        if !bytes.is_empty() && !continues_bytes {
+            let (text, _, _) = code_page.decode(&bytes);
+            push_text(&mut runs, &group, &text);
+            bytes.clear();
+        }
+        match byte {
+            b'{' => {
+                stack.push(group);
+                ignorable = false;
+                group_start = true;
+                i += 1;
+            }
+            b'}' => {
+                group = stack.pop().unwrap_or_default();
+                i += 1;
+            }
+            b'\\' => {
+                let (token, next) = control(rtf, i + 1);
+                i = next;
+                let first_in_group = std::mem::replace(&mut group_start, false);
+                match token {
+                    Control::Symbol(c) => {
+                        if !take_skip(&mut pending_skip) {
+                            push_text(&mut runs, &group, &c.to_string());
+                        }
+                    }
+                    Control::Byte(b) => {
+                        if !take_skip(&mut pending_skip) {
+                            bytes.push(b);
+                        }
+                    }
+                    Control::Ignorable => {
+                        ignorable = true;
+                        group_start = first_in_group;
+                    }
+                    Control::Word(word, param) => {
+                        if ignorable
+                            || (first_in_group && SKIPPED_DESTINATIONS.contains(&word.as_str()))
+                        {
+                            group.skip = true;
+                        }
+                        ignorable = false;
+                        if word == "ansicpg" {
+                            code_page = param.and_then(code_page_encoding).unwrap_or(code_page);
+                        }
+                        apply_word(
+                            &word,
+                            param,
+                            &mut group,
+                            &mut runs,
+                            &mut paragraphs,
+                            &mut pending_skip,
+                        );
+                    }
+                }
+            }
+            // Line breaks in the source are insignificant
+            b'\r' | b'\n' => i += 1,
+            _ => {
+                group_start = false;
+                let start = i;
+                while i < rtf.len() && !matches!(rtf[i], b'{' | b'}' | b'\\' | b'\r' | b'\n') {
+                    i += 1;
+                }
+                let chunk = &rtf[start..i];
+                let skipped = chunk.len().min(pending_skip);
+                pending_skip -= skipped;
+                let (text, _, _) = code_page.decode(&chunk[skipped..]);
+                push_text(&mut runs, &group, &text);
+            }
+        }
+    }
+    let (text, _, _) = code_page.decode(&bytes);
+    push_text(&mut runs, &group, &text);
+    end_paragraph(&mut runs, &mut paragraphs);
+
+    let mut markdown = paragraphs.join("\n\n");
+    if !markdown.is_empty() {
+        markdown.push('\n');
+    }
+    markdown
+}
+
+/// The encoding of a Windows code page number as used by `\ansicpg`
+fn code_page_encoding(code_page: i32) -> Option<&'static Encoding> {
+    match code_page {
+        932 => Some(SHIFT_JIS),
+        936 => Some(GBK),
+        949 => Some(EUC_KR),
+        950 => Some(BIG5),
+        10000 => Some(MACINTOSH),
+        65001 => Some(UTF_8),
+        // windows-874 and windows-1250 to windows-1258
+        _ => Encoding::for_label(format!("windows-{}", code_page).as_bytes()),
+    }
+}
+
+enum Control {
+    /// `\word` with its optional numeric parameter
+    Word(String, Option<i32>),
+    /// A character produced by a control symbol such as `\{` or `\~`
+    Symbol(char),
+    /// `\'hh`
+    Byte(u8),
+    /// `\*`
+    Ignorable,
+}
+
+/// Parse the control word or symbol starting after a backslash at `start`.
+/// Returns it with the index just past it (and its delimiting space, if any).
+fn control(rtf: &[u8], start: usize) -> (Control, usize) {
+    let Some(&first) = rtf.get(start) else {
+        return (Control::Symbol('\\'), start);
+    };
+    if !first.is_ascii_alphabetic() {
+        let token = match first {
+            b'\'' => {
+                let hex = rtf
+                    .get(start + 1..start + 3)
+                    .and_then(|h| std::str::from_utf8(h).ok());
+                if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    return (Control::Byte(byte), start + 3);
+                }
+                Control::Word(String::new(), None)
+            }
+            b'*' => Control::Ignorable,
+            b'~' => Control::Symbol('\u{a0}'),
+            b'_' => Control::Symbol('\u{2011}'),
+            // A backslash before a line break is a paragraph break
+            b'\r' | b'\n' => Control::Word("par".to_string(), None),
+            // Optional hyphens and formula markers have no text
+            b'-' | b'|' | b':' => Control::Word(String::new(), None),
+            other => Control::Symbol(other as char),
+        };
+        return (token, start + 1);
+    }
+
+    let mut end = start;
+    while end < rtf.len() && rtf[end].is_ascii_alphabetic() {
+        end += 1;
+    }
+    let word = String::from_utf8_lossy(&rtf[start..end]).to_string();
+    let param_start = end;
+    if rtf.get(end) == Some(&b'-') {
+        end += 1;
+    }
+    while end < rtf.len() && rtf[end].is_ascii_digit() {
+        end += 1;
+    }
+    let param = std::str::from_utf8(&rtf[param_start..end])
+        .ok()
+        .and_then(|p| p.parse().ok());
+    if rtf.get(end) == Some(&b' ') {
+        end += 1;
+    }
+    (Control::Word(word, param), end)
+}
+
+fn take_skip(pending_skip: &mut usize) -> bool {
+    if *pending_skip > 0 {
+        *pending_skip -= 1;
+        true
+    } else {
+        false
+    }
+}
+
+fn apply_word(
+    word: &str,
+    param: Option<i32>,
+    group: &mut Group,
+    runs: &mut Vec<(String, Format)>,
+    paragraphs: &mut Vec<String>,
+    pending_skip: &mut usize,
+) {
+    // `\b`, `\i`, `\ul` turn formatting on; a `0` parameter turns it off
+    let on = param != Some(0);
+    match word {
+        "b" => group.format.bold = on,
+        "i" => group.format.italic = on,
+        "ul" => group.format.underline = on,
+        "ulnone" => group.format.underline = false,
+        "plain" => group.format = Format::default(),
+        "par" | "sect" | "page" if !group.skip => end_paragraph(runs, paragraphs),
+        "line" => push_text(runs, group, "\n"),
+        "tab" => push_text(runs, group, "\t"),
+        "emdash" => push_text(runs, group, "\u{2014}"),
+        "endash" => push_text(runs, group, "\u{2013}"),
+        "bullet" => push_text(runs, group, "\u{2022}"),
+        "lquote" => push_text(runs, group, "\u{2018}"),
+        "rquote" => push_text(runs, group, "\u{2019}"),
+        "ldblquote" => push_text(runs, group, "\u{201c}"),
+        "rdblquote" => push_text(runs, group, "\u{201d}"),
+        "uc" => group.unicode_skip = param.unwrap_or(1).max(0) as usize,
+        "u" => {
+            // Code points above 32767 are written as negative numbers
+            let code = param.unwrap_or(0);
+            let code = if code < 0 { code + 65536 } else { code } as u32;
+            if let Some(c) = char::from_u32(code) {
+                push_text(runs, group, &c.to_string());
+            }
+            *pending_skip = group.unicode_skip;
+        }
+        _ => {}
+    }
+}
+
+/// Append document text, escaping the characters Markdown reads as emphasis
+fn push_text(runs: &mut Vec<(String, Format)>, group: &Group, text: &str) {
+    if group.skip || text.is_empty() {
+        return;
+    }
+    let text = text.replace('*', "\\*").replace('_', "\\_");
+    match runs.last_mut() {
+        Some((last, format)) if *format == group.format => last.push_str(&text),
+        _ => runs.push((text, group.format)),
+    }
+}
+
+/// Render the collected runs as one Markdown paragraph. Empty paragraphs are
+/// dropped, so blank lines between paragraphs don't pile up.
+fn end_paragraph(runs: &mut Vec<(String, Format)>, paragraphs: &mut Vec<String>) {
+    let text: String = runs
+        .drain(..)
+        .map(|(text, format)| emphasize(&text, format))
+        .collect();
+    let lines: Vec<&str> = text.split('\n').map(str::trim).collect();
+    // `\line` breaks become Markdown hard breaks
+    let paragraph = lines.join("  \n");
+    if !paragraph.trim().is_empty() {
+        paragraphs.push(paragraph.trim().to_string());
+    }
+}
+
+/// Wrap a run in emphasis markers, keeping surrounding whitespace outside
+/// them (`** bold**` would not render as bold)
+fn emphasize(text: &str, format: Format) -> String {
+    let trimmed = text.trim();
+    if trimmed.is_empty() || format == Format::default() {
+        return text.to_string();
+    }
+    let leading = &text[..text.len() - text.trim_start().len()];
+    let trailing = &text[text.trim_end().len()..];
+
+    let mut inner = trimmed.to_string();
+    if format.underline {
+        inner = format!("<u>{}</u>", inner);
+    }
+    let marker = match (format.bold, format.italic) {
+        (true, true) => "***",
+        (true, false) => "**",
+        (false, true) => "*",
+        (false, false) => "",
+    };
+    format!("{}{}{}{}{}", leading, marker, inner, marker, trailing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bold_run() {
+        let rtf = br"{\rtf1\ansi\deff0{\fonttbl{\f0 Times New Roman;}}
+{\colortbl;\red0\green0\blue0;}
+\f0\fs24 Plain text with {\b bold words} and \i italic\i0  text.\par
+Second \b bold\b0  paragraph.\par
+}";
+        assert_eq!(
+            rtf_text_to_markdown(rtf),
+            "Plain text with **bold words** and *italic* text.\n\nSecond **bold** paragraph.\n"
+        );
+    }
+
+    #[test]
+    fn test_underline_escapes_and_breaks() {
+        let rtf = br"{\rtf1\ansi {\ul under}{\b\i both}\line caf\'e9 \{x\}\par\par \u8364?5 {\*\generator Word;}\par}";
+        assert_eq!(
+            rtf_text_to_markdown(rtf),
+            "<u>under</u>***both***  \ncaf\u{e9} {x}\n\n\u{20ac}5\n"
+        );
+    }
+
+    #[test]
+    fn test_ansi_code_page_and_literal_emphasis_characters() {
+        // "日本" in Shift_JIS, then a cp1251 document with "Привет"
+        let rtf = br"{\rtf1\ansi\ansicpg932 \'93\'fa\'96\'7b 2*3 snake_case {\b bold}\par}";
+        assert_eq!(
+            rtf_text_to_markdown(rtf),
+            "\u{65e5}\u{672c} 2\\*3 snake\\_case **bold**\n"
+        );
+        let rtf = br"{\rtf1\ansi\ansicpg1251 \'cf\'f0\'e8\'e2\'e5\'f2\par}";
+        assert_eq!(
+            rtf_text_to_markdown(rtf),
+            "\u{41f}\u{440}\u{438}\u{432}\u{435}\u{442}\n"
+        );
+    }
+
+    #[test]
+    fn test_embedded_objects_are_dropped() {
+        let rtf = br"{\rtf1 Before{\pict\pngblip 89504e47}{\*\shppict{\pict 00}} after\par}";
+        assert_eq!(rtf_text_to_markdown(rtf), "Before after\n");
+    }
+}
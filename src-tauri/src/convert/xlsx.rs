@@ -1,27 +1,72 @@
-use calamine::{open_workbook_auto, Data, Reader};
-use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
-use rust_xlsxwriter::Workbook;
+use std::io::{Read, Seek};
 
-use super::ConversionError;
+use calamine::{
+    open_workbook_auto, Data, DataRef, ExcelDateTime, Range, Reader, Sheets, Xlsx, XlsxError,
+};
+use pulldown_cmark::{Alignment, Event, Options, Parser, Tag, TagEnd};
+use serde::Deserialize;
 
-const MAX_ROWS_PER_SHEET: usize = 500;
+use super::{
+    check_signature, escape_table_cell, extension_of, is_cell_line_break, ConversionError,
+    ImportControl, MERGED_CELLS_NOTE,
+};
 
-/// Convert an Excel file (xlsx/xls/ods/csv) to Markdown.
+/// Data row cap used when importing from the app
+pub const DEFAULT_MAX_ROWS_PER_SHEET: usize = 500;
+/// Rows streamed from an .xlsx sheet between progress reports and cancellation checks
+const PROGRESS_ROWS: usize = 1000;
+
+/// Convert an Excel file (xlsx/xls/ods) to Markdown.
 /// Each sheet becomes a ## heading followed by a GFM table.
-/// Rows are capped at MAX_ROWS_PER_SHEET with an inline note if truncated.
-pub fn xlsx_to_markdown(path: &str) -> Result<String, ConversionError> {
+/// Data rows are capped at `max_rows` (`None` for no cap) with an inline note
+/// when rows were actually cut. Merged ranges in .xlsx files are flattened,
+/// also with a note.
+pub fn xlsx_to_markdown(path: &str, max_rows: Option<usize>) -> Result<String, ConversionError> {
+    xlsx_to_markdown_with_control(path, max_rows, &ImportControl::default())
+}
+
+/// [`xlsx_to_markdown`] reporting the rows read after each sheet, and every
+/// `PROGRESS_ROWS` rows while streaming an .xlsx sheet, and stopping at those
+/// points once `control` is cancelled.
+pub fn xlsx_to_markdown_with_control(
+    path: &str,
+    max_rows: Option<usize>,
+    control: &ImportControl,
+) -> Result<String, ConversionError> {
+    // calamine picks the reader from the extension too
+    if let Some(extension) = extension_of(path) {
+        check_signature(path, &extension)?;
+    }
     let mut workbook = open_workbook_auto(path)
         .map_err(|e| ConversionError(format!("Failed to open spreadsheet: {}", e)))?;
 
     let sheet_names: Vec<String> = workbook.sheet_names().to_vec();
     let mut output = String::new();
+    let mut rows_read = 0;
 
     for sheet_name in sheet_names {
-        let range = workbook
-            .worksheet_range(&sheet_name)
-            .map_err(|e| ConversionError(format!("Failed to read sheet '{}': {}", sheet_name, e)))?;
+        control.check_cancelled()?;
+        let sheet_error = |e: &dyn std::fmt::Display| {
+            ConversionError(format!("Failed to read sheet '{}': {}", sheet_name, e))
+        };
+        // .xlsx sheets are streamed so rows past the cap are never held in memory
+        let (sheet, merges) = if let Sheets::Xlsx(xlsx) = &mut workbook {
+            let merges = xlsx
+                .worksheet_merge_cells(&sheet_name)
+                .and_then(Result::ok)
+                .unwrap_or_default();
+            let sheet = read_xlsx_sheet(xlsx, &sheet_name, max_rows, control, rows_read)?;
+            (sheet, merges)
+        } else {
+            let range = workbook
+                .worksheet_range(&sheet_name)
+                .map_err(|e| sheet_error(&e))?;
+            (CappedSheet::from_range(&range, max_rows), Vec::new())
+        };
 
-        let (total_rows, col_count) = range.get_size();
+        let (total_rows, col_count) = sheet.size();
+        rows_read += total_rows;
+        control.report("rows", rows_read, None);
 
         if col_count == 0 {
             continue;
@@ -37,8 +82,28 @@ pub fn xlsx_to_markdown(path: &str) -> Result<String, ConversionError> {
             continue;
         }
 
-        let rows_to_read = total_rows.min(MAX_ROWS_PER_SHEET + 1); // +1 for header
-        let mut rows_iter = range.rows().take(rows_to_read);
+        let data_rows_available = total_rows - 1;
+        let data_rows_shown =
+            max_rows.map_or(data_rows_available, |max| data_rows_available.min(max));
+        // Merged ranges are in absolute sheet coordinates; the sheet may not start at A1
+        let (first_row, first_col) = sheet.start.unwrap_or((0, 0));
+        let mut grid = sheet.into_grid(data_rows_shown + 1); // +1 for header
+
+        let merges: Vec<((usize, usize), (usize, usize))> = merges
+            .iter()
+            .map(|d| {
+                let relative = |(row, col): (u32, u32)| {
+                    (
+                        row.saturating_sub(first_row) as usize,
+                        col.saturating_sub(first_col) as usize,
+                    )
+                };
+                (relative(d.start), relative(d.end))
+            })
+            .collect();
+        let flattened = fill_merged_cells(&mut grid, &merges);
+
+        let mut rows_iter = grid.iter();
 
         // Header row
         let header = match rows_iter.next() {
@@ -48,7 +113,7 @@ pub fn xlsx_to_markdown(path: &str) -> Result<String, ConversionError> {
 
         output.push('|');
         for cell in header {
-            output.push_str(&format!(" {} |", cell_to_string(cell)));
+            output.push_str(&format!(" {} |", cell));
         }
         output.push('\n');
 
@@ -60,25 +125,24 @@ pub fn xlsx_to_markdown(path: &str) -> Result<String, ConversionError> {
         output.push('\n');
 
         // Data rows
-        let mut data_row_count = 0usize;
         for row in rows_iter {
-            if data_row_count >= MAX_ROWS_PER_SHEET {
-                break;
-            }
             output.push('|');
             for cell in row {
-                output.push_str(&format!(" {} |", cell_to_string(cell)));
+                output.push_str(&format!(" {} |", cell));
             }
             output.push('\n');
-            data_row_count += 1;
+        }
+
+        if flattened {
+            output.push_str(MERGED_CELLS_NOTE);
         }
 
         // Truncation notice
-        if total_rows > MAX_ROWS_PER_SHEET + 1 {
-            let omitted = total_rows - MAX_ROWS_PER_SHEET - 1;
+        if data_rows_shown < data_rows_available {
+            let omitted = data_rows_available - data_rows_shown;
             output.push_str(&format!(
                 "\n> **Note**: {} rows were omitted (showing first {} data rows).\n",
-                omitted, MAX_ROWS_PER_SHEET
+                omitted, data_rows_shown
             ));
         }
     }
@@ -86,6 +150,156 @@ pub fn xlsx_to_markdown(path: &str) -> Result<String, ConversionError> {
     Ok(output)
 }
 
+/// The cells of a sheet within the row cap, plus the sheet's full extent.
+/// Cells past the cap only widen the extent, which keeps the imported table
+/// the same shape as for an uncapped read.
+struct CappedSheet {
+    /// Escaped text of the non-empty cells kept, by absolute (row, col)
+    cells: Vec<((u32, u32), String)>,
+    /// First and last row and column holding a value, as in a calamine `Range`
+    start: Option<(u32, u32)>,
+    end: (u32, u32),
+    /// Rows kept counting from the first, header included
+    kept_rows: usize,
+}
+
+impl CappedSheet {
+    fn new(max_rows: Option<usize>) -> Self {
+        CappedSheet {
+            cells: Vec::new(),
+            start: None,
+            end: (0, 0),
+            kept_rows: max_rows.map_or(usize::MAX, |max| max.saturating_add(1)),
+        }
+    }
+
+    /// An already loaded range; only the cells within the cap are copied
+    fn from_range(range: &Range<Data>, max_rows: Option<usize>) -> Self {
+        let mut sheet = CappedSheet::new(max_rows);
+        let Some((first_row, first_col)) = range.start() else {
+            return sheet;
+        };
+        sheet.start = Some((first_row, first_col));
+        sheet.end = range.end().unwrap_or((first_row, first_col));
+        for (row, col, cell) in range.used_cells() {
+            let position = (first_row + row as u32, first_col + col as u32);
+            if sheet.include(position) {
+                sheet
+                    .cells
+                    .push((position, escape_table_cell(&cell_to_string(cell))));
+            }
+        }
+        sheet
+    }
+
+    /// Widen the extent to a non-empty cell at `position`; whether the cell is
+    /// within the cap. Sheets store their rows in order, so the first row is
+    /// known from the first cell.
+    fn include(&mut self, (row, col): (u32, u32)) -> bool {
+        let start = self.start.get_or_insert((row, col));
+        *start = (start.0.min(row), start.1.min(col));
+        self.end = (self.end.0.max(row), self.end.1.max(col));
+        ((row - start.0) as usize) < self.kept_rows
+    }
+
+    /// (rows, columns) from the first to the last cell holding a value
+    fn size(&self) -> (usize, usize) {
+        match self.start {
+            Some((row, col)) => (
+                (self.end.0 - row) as usize + 1,
+                (self.end.1 - col) as usize + 1,
+            ),
+            None => (0, 0),
+        }
+    }
+
+    /// The first `rows` rows as a full grid, empty cells included
+    fn into_grid(self, rows: usize) -> Vec<Vec<String>> {
+        let (total_rows, col_count) = self.size();
+        let (first_row, first_col) = self.start.unwrap_or((0, 0));
+        let mut grid = vec![vec![String::new(); col_count]; rows.min(total_rows)];
+        for ((row, col), text) in self.cells {
+            if let Some(cell) = grid
+                .get_mut((row - first_row) as usize)
+                .and_then(|cells| cells.get_mut((col - first_col) as usize))
+            {
+                *cell = text;
+            }
+        }
+        grid
+    }
+}
+
+/// Stream a .xlsx sheet cell by cell, keeping only the rows within `max_rows`.
+/// `rows_before` is the number of rows read from earlier sheets, for progress.
+fn read_xlsx_sheet<RS: Read + Seek>(
+    xlsx: &mut Xlsx<RS>,
+    sheet_name: &str,
+    max_rows: Option<usize>,
+    control: &ImportControl,
+    rows_before: usize,
+) -> Result<CappedSheet, ConversionError> {
+    let sheet_error =
+        |e: XlsxError| ConversionError(format!("Failed to read sheet '{}': {}", sheet_name, e));
+    let mut sheet = CappedSheet::new(max_rows);
+    let mut cells = xlsx
+        .worksheet_cells_reader(sheet_name)
+        .map_err(sheet_error)?;
+    let mut last_row = None;
+    let mut rows_seen = 0;
+    while let Some(cell) = cells.next_cell().map_err(sheet_error)? {
+        // calamine's own ranges leave out cells that only carry formatting
+        if matches!(cell.get_value(), DataRef::Empty) {
+            continue;
+        }
+        // Cells arrive row by row; a huge sheet can take a while on its own
+        let row = cell.get_position().0;
+        if last_row != Some(row) {
+            last_row = Some(row);
+            rows_seen += 1;
+            if rows_seen % PROGRESS_ROWS == 0 {
+                control.check_cancelled()?;
+                control.report("rows", rows_before + rows_seen, None);
+            }
+        }
+        if sheet.include(cell.get_position()) {
+            let text = cell_to_string(&Data::from(cell.get_value().clone()));
+            sheet
+                .cells
+                .push((cell.get_position(), escape_table_cell(&text)));
+        }
+    }
+    Ok(sheet)
+}
+
+/// GFM has no cell spans, so each merged range ((first row, first col),
+/// (last row, last col)) is flattened by repeating its top-left value in
+/// every cell it covers. Returns whether any shown cell was filled.
+fn fill_merged_cells(
+    grid: &mut [Vec<String>],
+    merges: &[((usize, usize), (usize, usize))],
+) -> bool {
+    let mut flattened = false;
+    for &((start_row, start_col), (end_row, end_col)) in merges {
+        let Some(value) = grid
+            .get(start_row)
+            .and_then(|row| row.get(start_col))
+            .cloned()
+        else {
+            continue;
+        };
+        for row in grid.iter_mut().take(end_row + 1).skip(start_row) {
+            for cell in row.iter_mut().take(end_col + 1).skip(start_col) {
+                if *cell != value {
+                    *cell = value.clone();
+                    flattened = true;
+                }
+            }
+        }
+    }
+    flattened
+}
+
 pub fn cell_to_string(cell: &Data) -> String {
     match cell {
         Data::Empty => String::new(),
@@ -100,15 +314,58 @@ pub fn cell_to_string(cell: &Data) -> String {
         Data::Int(i) => format!("{}", i),
         Data::Bool(b) => if *b { "TRUE".to_string() } else { "FALSE".to_string() },
         Data::Error(e) => format!("{:?}", e),
-        Data::DateTime(dt) => format!("{}", dt),
+        Data::DateTime(dt) => excel_datetime_to_string(dt),
         Data::DateTimeIso(s) => s.clone(),
         Data::DurationIso(s) => s.clone(),
     }
 }
 
+/// ISO 8601 text for an Excel serial date: `2023-03-15` for whole days,
+/// `18:00:00` for times (serials below one day), and `2023-03-15T12:00:00` otherwise.
+/// Durations become `H:MM:SS`. Falls back to the serial if it's out of range.
+fn excel_datetime_to_string(dt: &ExcelDateTime) -> String {
+    if dt.is_duration() {
+        if let Some(duration) = dt.as_duration() {
+            let secs = duration.num_seconds();
+            let sign = if secs < 0 { "-" } else { "" };
+            let secs = secs.abs();
+            return format!(
+                "{}{}:{:02}:{:02}",
+                sign,
+                secs / 3600,
+                secs / 60 % 60,
+                secs % 60
+            );
+        }
+    } else if let Some(datetime) = dt.as_datetime() {
+        if dt.as_f64() >= 0.0 && dt.as_f64() < 1.0 {
+            return datetime.format("%H:%M:%S").to_string();
+        }
+        if datetime.time() == chrono::NaiveTime::MIN {
+            return datetime.format("%Y-%m-%d").to_string();
+        }
+        return datetime.format("%Y-%m-%dT%H:%M:%S").to_string();
+    }
+    format!("{}", dt)
+}
+
+/// A GFM pipe table pulled out of a Markdown document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarkdownTable {
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// Per-column alignment from the `:---:` separator row
+    pub alignments: Vec<Alignment>,
+    /// Text of the heading directly before the table, if any
+    pub heading: Option<String>,
+    /// Text of the nearest heading above the table, even with other content in between
+    pub section_heading: Option<String>,
+    /// How many headings precede the table; tables with the same number share a section
+    pub section: usize,
+}
+
 /// Extract GFM pipe tables from Markdown text.
-/// Returns a list of (header_row, data_rows) where each row is Vec<String>.
-pub fn extract_tables_from_markdown(markdown: &str) -> Vec<(Vec<String>, Vec<Vec<String>>)> {
+pub fn extract_tables_from_markdown(markdown: &str) -> Vec<MarkdownTable> {
     let mut tables = Vec::new();
     let options = Options::ENABLE_TABLES;
     let parser = Parser::new_ext(markdown, options);
@@ -117,21 +374,57 @@ pub fn extract_tables_from_markdown(markdown: &str) -> Vec<(Vec<String>, Vec<Vec
     let mut in_table_head = false;
     let mut header_row: Vec<String> = Vec::new();
     let mut data_rows: Vec<Vec<String>> = Vec::new();
+    let mut alignments: Vec<Alignment> = Vec::new();
+    // Heading text being collected, and the last heading not yet followed by other content
+    let mut heading_text: Option<String> = None;
+    let mut last_heading: Option<String> = None;
+    let mut section_heading: Option<String> = None;
+    let mut section = 0;
     let mut current_row: Vec<String> = Vec::new();
     let mut current_cell = String::new();
 
     for event in parser {
         match event {
-            Event::Start(Tag::Table(_)) => {
+            Event::Start(Tag::Table(table_alignments)) => {
                 in_table = true;
                 header_row.clear();
                 data_rows.clear();
+                alignments = table_alignments;
             }
             Event::End(TagEnd::Table) => {
                 in_table = false;
-                tables.push((header_row.clone(), data_rows.clone()));
-                header_row.clear();
-                data_rows.clear();
+                tables.push(MarkdownTable {
+                    header: std::mem::take(&mut header_row),
+                    rows: std::mem::take(&mut data_rows),
+                    alignments: std::mem::take(&mut alignments),
+                    heading: last_heading.take(),
+                    section_heading: section_heading.clone(),
+                    section,
+                });
+            }
+            Event::Start(Tag::Heading { .. }) => {
+                heading_text = Some(String::new());
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                last_heading = heading_text.take().map(|t| t.trim().to_string());
+                section_heading = last_heading.clone();
+                section += 1;
+            }
+            Event::Text(text) | Event::Code(text) if heading_text.is_some() => {
+                if let Some(h) = heading_text.as_mut() {
+                    h.push_str(&text);
+                }
+            }
+            // Any other block between the heading and the table breaks the association
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::CodeBlock
+                | TagEnd::List(_)
+                | TagEnd::BlockQuote(_)
+                | TagEnd::HtmlBlock,
+            )
+            | Event::Rule => {
+                last_heading = None;
             }
             Event::Start(Tag::TableHead) => {
                 in_table_head = true;
@@ -162,6 +455,9 @@ pub fn extract_tables_from_markdown(markdown: &str) -> Vec<(Vec<String>, Vec<Vec
             Event::Text(text) if in_table => {
                 current_cell.push_str(&text);
             }
+            Event::InlineHtml(html) if in_table && is_cell_line_break(&html) => {
+                current_cell.push('\n');
+            }
             _ => {}
         }
     }
@@ -170,10 +466,123 @@ pub fn extract_tables_from_markdown(markdown: &str) -> Vec<(Vec<String>, Vec<Vec
     tables
 }
 
+/// Excel's limit on worksheet name length
+const MAX_SHEET_NAME_LEN: usize = 31;
+
+/// Make heading text a legal worksheet name: no `[]:*?/\`, no leading or
+/// trailing apostrophe, at most 31 characters, and not the reserved "History".
+fn sanitize_sheet_name(text: &str) -> Option<String> {
+    let cleaned: String = text
+        .chars()
+        .filter(|c| !matches!(c, '[' | ']' | ':' | '*' | '?' | '/' | '\\') && !c.is_control())
+        .take(MAX_SHEET_NAME_LEN)
+        .collect();
+    let cleaned = cleaned.trim().trim_matches('\'').trim();
+    if cleaned.is_empty() || cleaned.eq_ignore_ascii_case("history") {
+        None
+    } else {
+        Some(cleaned.to_string())
+    }
+}
+
+/// Sheet name for the table at `table_idx`: its heading when usable and not yet
+/// taken (names compare case-insensitively), otherwise `TableN`.
+/// `used_names` holds the lowercased names already assigned.
+fn unique_sheet_name(heading: Option<&str>, table_idx: usize, used_names: &[String]) -> String {
+    let is_free = |name: &str| !used_names.contains(&name.to_lowercase());
+    if let Some(name) = heading.and_then(sanitize_sheet_name).filter(|n| is_free(n)) {
+        return name;
+    }
+    let mut n = table_idx + 1;
+    loop {
+        let name = format!("Table{}", n);
+        if is_free(&name) {
+            return name;
+        }
+        n += 1;
+    }
+}
+
+/// Cell format carrying a column's GFM alignment. Unaligned columns use Excel's
+/// default (text left, numbers right).
+fn column_format(alignment: Alignment) -> Format {
+    match alignment {
+        Alignment::Left => Format::new().set_align(FormatAlign::Left),
+        Alignment::Center => Format::new().set_align(FormatAlign::Center),
+        Alignment::Right => Format::new().set_align(FormatAlign::Right),
+        Alignment::None => Format::new(),
+    }
+}
+
+/// Layout settings for XLSX export.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct XlsxOptions {
+    /// Keep the first table's header row in view while scrolling
+    pub freeze_header: bool,
+    /// Add filter dropdowns to the header of sheets holding a single table
+    pub autofilter: bool,
+}
+
+impl Default for XlsxOptions {
+    fn default() -> Self {
+        Self {
+            freeze_header: true,
+            autofilter: true,
+        }
+    }
+}
+
+/// Write `table` with its header row at `first_row`
+fn write_table(
+    sheet: &mut Worksheet,
+    table: &MarkdownTable,
+    first_row: u32,
+) -> Result<(), ConversionError> {
+    let formats: Vec<Format> = table.alignments.iter().map(|a| column_format(*a)).collect();
+    let default_format = Format::new();
+    let format_for = |col_idx: usize| formats.get(col_idx).unwrap_or(&default_format);
+
+    // Write header
+    for (col_idx, cell) in table.header.iter().enumerate() {
+        let header_format = format_for(col_idx).clone().set_bold();
+        sheet
+            .write_string_with_format(first_row, col_idx as u16, cell, &header_format)
+            .map_err(|e| ConversionError(format!("Failed to write header: {}", e)))?;
+    }
+
+    // Write data rows
+    for (row_idx, row) in table.rows.iter().enumerate() {
+        for (col_idx, cell) in row.iter().enumerate() {
+            sheet
+                .write_string_with_format(
+                    first_row + row_idx as u32 + 1,
+                    col_idx as u16,
+                    cell,
+                    format_for(col_idx),
+                )
+                .map_err(|e| ConversionError(format!("Failed to write data: {}", e)))?;
+        }
+    }
+    Ok(())
+}
+
 /// Convert Markdown to an XLSX file.
-/// GFM tables in the Markdown become worksheets.
+/// GFM tables in the Markdown become worksheets; consecutive tables in the
+/// same section share one sheet named after its heading, stacked with a blank
+/// row between them and each keeping its own header row.
 /// If no tables are found, writes all lines as plain text to Sheet1.
 pub fn markdown_to_xlsx(markdown: &str, path: &str) -> Result<(), ConversionError> {
+    markdown_to_xlsx_with_options(markdown, path, &XlsxOptions::default())
+}
+
+/// [`markdown_to_xlsx`] with control over the frozen header and filters.
+/// Columns are always sized to fit their longest cell.
+pub fn markdown_to_xlsx_with_options(
+    markdown: &str,
+    path: &str,
+    options: &XlsxOptions,
+) -> Result<(), ConversionError> {
     let mut workbook = Workbook::new();
     let tables = extract_tables_from_markdown(markdown);
 
@@ -190,28 +599,46 @@ pub fn markdown_to_xlsx(markdown: &str, path: &str) -> Result<(), ConversionErro
                 .map_err(|e| ConversionError(format!("Failed to write cell: {}", e)))?;
         }
     } else {
-        for (table_idx, (header, data_rows)) in tables.iter().enumerate() {
-            let sheet_name = format!("Table{}", table_idx + 1);
+        let mut used_names: Vec<String> = Vec::new();
+        let groups = tables.chunk_by(|a, b| a.section_heading.is_some() && a.section == b.section);
+        for (sheet_idx, group) in groups.enumerate() {
+            let heading = if group.len() > 1 {
+                &group[0].section_heading
+            } else {
+                &group[0].heading
+            };
+            let sheet_name = unique_sheet_name(heading.as_deref(), sheet_idx, &used_names);
+            used_names.push(sheet_name.to_lowercase());
             let sheet = workbook
                 .add_worksheet()
                 .set_name(&sheet_name)
                 .map_err(|e| ConversionError(format!("Failed to create sheet: {}", e)))?;
 
-            // Write header
-            for (col_idx, cell) in header.iter().enumerate() {
-                sheet
-                    .write_string(0, col_idx as u16, cell)
-                    .map_err(|e| ConversionError(format!("Failed to write header: {}", e)))?;
+            let mut first_row = 0u32;
+            for table in group {
+                write_table(sheet, table, first_row)?;
+                // One blank row before the next table's header
+                first_row += table.rows.len() as u32 + 2;
             }
 
-            // Write data rows
-            for (row_idx, row) in data_rows.iter().enumerate() {
-                for (col_idx, cell) in row.iter().enumerate() {
-                    sheet
-                        .write_string((row_idx + 1) as u32, col_idx as u16, cell)
-                        .map_err(|e| ConversionError(format!("Failed to write data: {}", e)))?;
-                }
+            if options.freeze_header {
+                sheet
+                    .set_freeze_panes(1, 0)
+                    .map_err(|e| ConversionError(format!("Failed to freeze header: {}", e)))?;
+            }
+            // A sheet has one filter range, which would mix up stacked tables
+            if options.autofilter && group.len() == 1 && !group[0].header.is_empty() {
+                let table = &group[0];
+                sheet
+                    .autofilter(
+                        0,
+                        0,
+                        table.rows.len() as u32,
+                        (table.header.len() - 1) as u16,
+                    )
+                    .map_err(|e| ConversionError(format!("Failed to add filter: {}", e)))?;
             }
+            sheet.autofit();
         }
     }
 
@@ -225,6 +652,9 @@ pub fn markdown_to_xlsx(markdown: &str, path: &str) -> Result<(), ConversionErro
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::convert::ImportProgress;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn test_cell_to_string_float() {
@@ -238,15 +668,327 @@ mod tests {
         assert_eq!(cell_to_string(&Data::Bool(false)), "FALSE");
     }
 
+    #[test]
+    fn test_cell_to_string_datetime_as_iso() {
+        use calamine::ExcelDateTimeType::{DateTime, TimeDelta};
+        let cell = |serial: f64, kind| Data::DateTime(ExcelDateTime::new(serial, kind, false));
+
+        assert_eq!(cell_to_string(&cell(45000.0, DateTime)), "2023-03-15");
+        assert_eq!(
+            cell_to_string(&cell(45000.5, DateTime)),
+            "2023-03-15T12:00:00"
+        );
+        assert_eq!(cell_to_string(&cell(0.75, DateTime)), "18:00:00");
+        assert_eq!(cell_to_string(&cell(1.5, TimeDelta)), "36:00:00");
+        assert_eq!(
+            cell_to_string(&Data::DateTimeIso("2023-03-15T08:30:00".into())),
+            "2023-03-15T08:30:00"
+        );
+    }
+
     #[test]
     fn test_extract_tables_from_markdown() {
         let md = "| Col1 | Col2 |\n| --- | --- |\n| A | B |\n| C | D |\n";
         let tables = extract_tables_from_markdown(md);
         assert_eq!(tables.len(), 1);
-        let (header, data) = &tables[0];
-        assert_eq!(header, &["Col1", "Col2"]);
-        assert_eq!(data.len(), 2);
-        assert_eq!(data[0], &["A", "B"]);
+        let table = &tables[0];
+        assert_eq!(table.header, ["Col1", "Col2"]);
+        assert_eq!(table.rows.len(), 2);
+        assert_eq!(table.rows[0], ["A", "B"]);
+    }
+
+    #[test]
+    fn test_markdown_to_xlsx_right_aligned_column() {
+        let md = "| Item | Price |\n| --- | ---: |\n| Tea | 3.50 |\n";
+        let tables = extract_tables_from_markdown(md);
+        assert_eq!(tables[0].alignments, [Alignment::None, Alignment::Right]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("xlsx_align.xlsx");
+        markdown_to_xlsx(md, path.to_str().unwrap()).unwrap();
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let mut styles = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("xl/styles.xml").unwrap(), &mut styles)
+            .unwrap();
+        let mut sheet = String::new();
+        std::io::Read::read_to_string(
+            &mut archive.by_name("xl/worksheets/sheet1.xml").unwrap(),
+            &mut sheet,
+        )
+        .unwrap();
+
+        // The cell's style index picks an entry of <cellXfs>
+        let cell_xfs =
+            &styles[styles.find("<cellXfs").unwrap()..styles.find("</cellXfs>").unwrap()];
+        let xfs: Vec<&str> = cell_xfs.split("<xf ").skip(1).collect();
+        let cell_xf = |cell: &str| {
+            let tag = &sheet[sheet.find(&format!(r#"<c r="{}""#, cell)).unwrap()..];
+            let tag = &tag[..tag.find('>').unwrap()];
+            let index = tag
+                .split(r#" s=""#)
+                .nth(1)
+                .map_or(0, |s| s[..s.find('"').unwrap()].parse::<usize>().unwrap());
+            xfs[index]
+        };
+        assert!(cell_xf("B2").contains(r#"<alignment horizontal="right"/>"#));
+        assert!(!cell_xf("A2").contains("<alignment"));
+    }
+
+    #[test]
+    fn test_xlsx_to_markdown_custom_row_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("xlsx_cap.xlsx");
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Data").unwrap();
+        sheet.write_string(0, 0, "N").unwrap();
+        for i in 1..=5u32 {
+            sheet.write_number(i, 0, i as f64).unwrap();
+        }
+        workbook.save(&path).unwrap();
+        let path_str = path.to_str().unwrap();
+
+        let capped = xlsx_to_markdown(path_str, Some(3)).unwrap();
+        let exact = xlsx_to_markdown(path_str, Some(5)).unwrap();
+        let unbounded = xlsx_to_markdown(path_str, None).unwrap();
+
+        assert!(capped.contains("| 3 |"));
+        assert!(!capped.contains("| 4 |"));
+        assert!(capped.contains("2 rows were omitted (showing first 3 data rows)"));
+        assert!(exact.contains("| 5 |"));
+        assert!(!exact.contains("omitted"));
+        assert_eq!(exact, unbounded);
+    }
+
+    #[test]
+    fn test_xlsx_import_streams_large_sheet() {
+        const ROWS: u32 = 2_000;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("xlsx_large.xlsx");
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Big").unwrap();
+        sheet.write_string(0, 0, "N").unwrap();
+        sheet.write_string(0, 1, "Square").unwrap();
+        for i in 1..=ROWS {
+            sheet.write_number(i, 0, i as f64).unwrap();
+            sheet.write_number(i, 1, (i as f64) * (i as f64)).unwrap();
+        }
+        // A value far below the cap still widens the table
+        sheet.write_string(ROWS, 2, "last").unwrap();
+        workbook.save(&path).unwrap();
+
+        let md = xlsx_to_markdown(path.to_str().unwrap(), Some(10)).unwrap();
+        let mut xlsx: calamine::Xlsx<_> = calamine::open_workbook(&path).unwrap();
+        let streamed =
+            read_xlsx_sheet(&mut xlsx, "Big", Some(10), &ImportControl::default(), 0).unwrap();
+
+        // Only the header and the first 10 data rows are ever kept
+        assert_eq!(streamed.cells.len(), 11 * 2);
+        assert!(md.starts_with("## Big\n\n| N | Square |  |\n| --- | --- | --- |\n| 1 | 1 |  |\n"));
+        assert!(md.contains(
+            "| 10 | 100 |  |\n\n> **Note**: 1990 rows were omitted (showing first 10 data rows).\n"
+        ));
+    }
+
+    #[test]
+    fn test_xlsx_to_markdown_flattens_merged_header() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("xlsx_merged.xlsx");
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet().set_name("Sales").unwrap();
+        sheet
+            .merge_range(0, 0, 0, 1, "Quarter", &Format::new())
+            .unwrap();
+        sheet.write_string(0, 2, "Total").unwrap();
+        sheet.write_string(1, 0, "Q1").unwrap();
+        sheet.write_string(1, 1, "Jan").unwrap();
+        sheet.write_number(1, 2, 10.0).unwrap();
+        workbook.save(&path).unwrap();
+
+        let md = xlsx_to_markdown(path.to_str().unwrap(), None).unwrap();
+
+        assert!(
+            md.contains("| Quarter | Quarter | Total |\n| --- | --- | --- |\n| Q1 | Jan | 10 |\n")
+        );
+        assert!(md.contains("Merged cells were flattened"));
+    }
+
+    #[test]
+    fn test_table_cells_with_pipes_and_line_breaks_round_trip() {
+        let md = "## Sheet1\n\n| Expr | Notes |\n| --- | --- |\n| a \\| b | first<br>second |\n";
+        let tables = extract_tables_from_markdown(md);
+        assert_eq!(
+            tables[0].rows,
+            vec![vec!["a | b".to_string(), "first\nsecond".to_string()]]
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("xlsx_pipes.xlsx");
+        markdown_to_xlsx(md, path.to_str().unwrap()).unwrap();
+        let result = xlsx_to_markdown(path.to_str().unwrap(), None).unwrap();
+        assert_eq!(result, md);
+    }
+
+    #[test]
+    fn test_xlsx_import_stops_when_cancelled() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("xlsx_cancel.xlsx");
+        let mut workbook = Workbook::new();
+        for name in ["One", "Two", "Three"] {
+            let sheet = workbook.add_worksheet().set_name(name).unwrap();
+            sheet.write_string(0, 0, "N").unwrap();
+            sheet.write_number(1, 0, 1.0).unwrap();
+        }
+        workbook.save(&path).unwrap();
+
+        // Cancel as soon as the first sheet has been read
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let control = ImportControl {
+            cancel: cancel.clone(),
+            progress: Some(Arc::new({
+                let reports = reports.clone();
+                move |progress: ImportProgress| {
+                    reports.lock().unwrap().push(progress);
+                    cancel.store(true, Ordering::Relaxed);
+                }
+            })),
+        };
+        let result = xlsx_to_markdown_with_control(path.to_str().unwrap(), None, &control);
+
+        assert_eq!(result.unwrap_err().0, "Import cancelled");
+        assert_eq!(
+            *reports.lock().unwrap(),
+            vec![ImportProgress {
+                unit: "rows",
+                done: 2,
+                total: None
+            }]
+        );
+    }
+
+    #[test]
+    fn test_xlsx_import_cancels_within_a_sheet() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("xlsx_cancel_rows.xlsx");
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        for row in 0..(PROGRESS_ROWS as u32 * 2 + 500) {
+            sheet.write_number(row, 0, row as f64).unwrap();
+        }
+        workbook.save(&path).unwrap();
+
+        // Cancel at the first report, which comes before the sheet is done
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let control = ImportControl {
+            cancel: cancel.clone(),
+            progress: Some(Arc::new({
+                let reports = reports.clone();
+                move |progress: ImportProgress| {
+                    reports.lock().unwrap().push(progress.done);
+                    cancel.store(true, Ordering::Relaxed);
+                }
+            })),
+        };
+        let result = xlsx_to_markdown_with_control(path.to_str().unwrap(), None, &control);
+
+        assert_eq!(result.unwrap_err().0, "Import cancelled");
+        assert_eq!(*reports.lock().unwrap(), vec![PROGRESS_ROWS]);
+    }
+
+    #[test]
+    fn test_markdown_to_xlsx_names_sheets_after_headings() {
+        let md = "## Sales\n\n| Q | Total |\n| --- | --- |\n| 1 | 10 |\n\n\
+                  ## Sales\n\n| Q | Total |\n| --- | --- |\n| 2 | 20 |\n\n\
+                  ## Notes\n\nSome text.\n\n| A |\n| --- |\n| x |\n\n\
+                  # Q1: Costs / Overheads [draft]\n\n| A |\n| --- |\n| y |\n";
+        let tables = extract_tables_from_markdown(md);
+        assert_eq!(tables[0].heading.as_deref(), Some("Sales"));
+        assert_eq!(tables[2].heading, None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("xlsx_sheets.xlsx");
+        markdown_to_xlsx(md, path.to_str().unwrap()).unwrap();
+        let workbook: calamine::Xlsx<_> = calamine::open_workbook(&path).unwrap();
+        let names = workbook.sheet_names().to_vec();
+
+        assert_eq!(
+            names,
+            ["Sales", "Table2", "Table3", "Q1 Costs  Overheads draft"]
+        );
+    }
+
+    #[test]
+    fn test_markdown_to_xlsx_stacks_tables_in_one_section() {
+        let md = "## Q1\n\n| Region | Sales |\n| --- | --- |\n| North | 10 |\n\n\
+                  Costs follow.\n\n| Item | Cost |\n| --- | --- |\n| Rent | 5 |\n| Power | 2 |\n\n\
+                  ## Q2\n\n| Region | Sales |\n| --- | --- |\n| South | 7 |\n";
+        let tables = extract_tables_from_markdown(md);
+        assert_eq!(tables[1].heading, None);
+        assert_eq!(tables[1].section_heading.as_deref(), Some("Q1"));
+        assert_eq!(tables[0].section, tables[1].section);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("xlsx_stacked.xlsx");
+        markdown_to_xlsx(md, path.to_str().unwrap()).unwrap();
+        let mut workbook: calamine::Xlsx<_> = calamine::open_workbook(&path).unwrap();
+        let names = workbook.sheet_names().to_vec();
+        let q1 = workbook.worksheet_range("Q1").unwrap();
+
+        assert_eq!(names, ["Q1", "Q2"]);
+        let rows: Vec<Vec<String>> = q1
+            .rows()
+            .map(|row| row.iter().map(cell_to_string).collect())
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ["Region", "Sales"],
+                ["North", "10"],
+                ["", ""],
+                ["Item", "Cost"],
+                ["Rent", "5"],
+                ["Power", "2"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_markdown_to_xlsx_freezes_and_filters_header() {
+        let md = "| Name | Qty |\n| --- | --- |\n| Tea | 2 |\n| Coffee | 3 |\n";
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("xlsx_filter.xlsx");
+        let sheet_xml = |options: &XlsxOptions| {
+            markdown_to_xlsx_with_options(md, path.to_str().unwrap(), options).unwrap();
+            let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+            let mut xml = String::new();
+            std::io::Read::read_to_string(
+                &mut archive.by_name("xl/worksheets/sheet1.xml").unwrap(),
+                &mut xml,
+            )
+            .unwrap();
+            xml
+        };
+
+        let xml = sheet_xml(&XlsxOptions::default());
+        assert!(xml.contains(
+            r#"<pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/>"#
+        ));
+        assert!(xml.contains(r#"<autoFilter ref="A1:B3"/>"#));
+
+        let xml = sheet_xml(&XlsxOptions {
+            freeze_header: false,
+            autofilter: false,
+        });
+        assert!(!xml.contains("<pane"));
+        assert!(!xml.contains("<autoFilter"));
+    }
+
+    #[test]
+    fn test_sanitize_sheet_name_limits_length() {
+        let name = sanitize_sheet_name(&"x".repeat(40)).unwrap();
+        assert_eq!(name.len(), MAX_SHEET_NAME_LEN);
+        assert_eq!(sanitize_sheet_name("'History'"), None);
     }
 
     #[test]
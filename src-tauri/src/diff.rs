@@ -0,0 +1,157 @@
+use std::ops::Range;
+
+use serde::Serialize;
+use similar::{DiffTag, TextDiff};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+/// A run of lines that are unchanged, added or removed. Line ranges are
+/// 0-based and end-exclusive; an insertion has an empty old range at the
+/// point it goes in, a deletion an empty new range.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DiffHunk {
+    pub kind: ChangeKind,
+    pub old_start: usize,
+    pub old_end: usize,
+    pub new_start: usize,
+    pub new_end: usize,
+    /// The lines themselves, without line endings: from `new` for insertions,
+    /// from `old` otherwise
+    pub lines: Vec<String>,
+}
+
+impl DiffHunk {
+    fn new(kind: ChangeKind, old: Range<usize>, new: Range<usize>, lines: Vec<String>) -> Self {
+        DiffHunk {
+            kind,
+            old_start: old.start,
+            old_end: old.end,
+            new_start: new.start,
+            new_end: new.end,
+            lines,
+        }
+    }
+}
+
+/// Line diff of two versions of a document. A changed block is reported as a
+/// deletion followed by an insertion.
+pub fn diff_markdown(old: &str, new: &str) -> Vec<DiffHunk> {
+    let diff = TextDiff::from_lines(old, new);
+    let lines = |slices: &[&str], range: Range<usize>| -> Vec<String> {
+        slices[range]
+            .iter()
+            .map(|line| line.trim_end_matches(['\n', '\r']).to_string())
+            .collect()
+    };
+
+    let mut hunks = Vec::new();
+    for op in diff.ops() {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        match tag {
+            DiffTag::Equal | DiffTag::Delete => {
+                let kind = if tag == DiffTag::Equal {
+                    ChangeKind::Equal
+                } else {
+                    ChangeKind::Delete
+                };
+                let text = lines(diff.old_slices(), old_range.clone());
+                hunks.push(DiffHunk::new(kind, old_range, new_range, text));
+            }
+            DiffTag::Insert => {
+                let text = lines(diff.new_slices(), new_range.clone());
+                hunks.push(DiffHunk::new(
+                    ChangeKind::Insert,
+                    old_range,
+                    new_range,
+                    text,
+                ));
+            }
+            DiffTag::Replace => {
+                let removed = lines(diff.old_slices(), old_range.clone());
+                let added = lines(diff.new_slices(), new_range.clone());
+                hunks.push(DiffHunk::new(
+                    ChangeKind::Delete,
+                    old_range.clone(),
+                    new_range.start..new_range.start,
+                    removed,
+                ));
+                hunks.push(DiffHunk::new(
+                    ChangeKind::Insert,
+                    old_range.end..old_range.end,
+                    new_range,
+                    added,
+                ));
+            }
+        }
+    }
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(hunks: &[DiffHunk]) -> Vec<ChangeKind> {
+        hunks.iter().map(|h| h.kind).collect()
+    }
+
+    #[test]
+    fn test_diff_markdown_insertion() {
+        let hunks = diff_markdown("# Title\n\nEnd\n", "# Title\n\nNew line\nEnd\n");
+        assert_eq!(
+            kinds(&hunks),
+            [ChangeKind::Equal, ChangeKind::Insert, ChangeKind::Equal]
+        );
+        let insert = &hunks[1];
+        assert_eq!(
+            (
+                insert.old_start,
+                insert.old_end,
+                insert.new_start,
+                insert.new_end
+            ),
+            (2, 2, 2, 3)
+        );
+        assert_eq!(insert.lines, ["New line"]);
+    }
+
+    #[test]
+    fn test_diff_markdown_deletion() {
+        let hunks = diff_markdown("one\ntwo\nthree\n", "one\nthree\n");
+        assert_eq!(
+            kinds(&hunks),
+            [ChangeKind::Equal, ChangeKind::Delete, ChangeKind::Equal]
+        );
+        let delete = &hunks[1];
+        assert_eq!(
+            (
+                delete.old_start,
+                delete.old_end,
+                delete.new_start,
+                delete.new_end
+            ),
+            (1, 2, 1, 1)
+        );
+        assert_eq!(delete.lines, ["two"]);
+    }
+
+    #[test]
+    fn test_diff_markdown_unchanged_and_replaced() {
+        let hunks = diff_markdown("a\r\nb\r\n", "a\r\nb\r\n");
+        assert_eq!(kinds(&hunks), [ChangeKind::Equal]);
+        assert_eq!(hunks[0].lines, ["a", "b"]);
+
+        let hunks = diff_markdown("a\nb\n", "a\nc\n");
+        assert_eq!(
+            kinds(&hunks),
+            [ChangeKind::Equal, ChangeKind::Delete, ChangeKind::Insert]
+        );
+        assert_eq!(hunks[2].lines, ["c"]);
+    }
+}
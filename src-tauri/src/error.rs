@@ -0,0 +1,119 @@
+use std::fmt;
+use std::io;
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+/// Error returned by Tauri commands. Serializes to `{ code, message }` so the
+/// frontend can branch on `code` while still showing `message` to the user.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppError {
+    NotFound(String),
+    PermissionDenied(String),
+    NotUtf8(String),
+    AlreadyExists(String),
+    // Writing was refused because the document is locked in read-only mode
+    ReadOnly(String),
+    Io(String),
+}
+
+impl AppError {
+    /// Classify an I/O error by its kind, prefixing the message with `context`
+    /// (e.g. "Failed to read file").
+    pub fn io(context: &str, err: io::Error) -> Self {
+        let message = format!("{}: {}", context, err);
+        match err.kind() {
+            io::ErrorKind::NotFound => AppError::NotFound(message),
+            io::ErrorKind::PermissionDenied => AppError::PermissionDenied(message),
+            io::ErrorKind::AlreadyExists => AppError::AlreadyExists(message),
+            // What `read_to_string` reports for bytes that aren't valid UTF-8
+            io::ErrorKind::InvalidData => AppError::NotUtf8(message),
+            _ => AppError::Io(message),
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "not_found",
+            AppError::PermissionDenied(_) => "permission_denied",
+            AppError::NotUtf8(_) => "not_utf8",
+            AppError::AlreadyExists(_) => "already_exists",
+            AppError::ReadOnly(_) => "read_only",
+            AppError::Io(_) => "io",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::NotFound(m)
+            | AppError::PermissionDenied(m)
+            | AppError::NotUtf8(m)
+            | AppError::AlreadyExists(m)
+            | AppError::ReadOnly(m)
+            | AppError::Io(m) => m,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<io::Error> for AppError {
+    fn from(err: io::Error) -> Self {
+        AppError::io("I/O error", err)
+    }
+}
+
+// Helpers that still report plain strings surface as generic errors
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Io(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Io(message.to_string())
+    }
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", self.message())?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_io_error_kinds_map_to_codes() {
+        let not_found = io::Error::new(io::ErrorKind::NotFound, "gone");
+        assert_eq!(
+            AppError::io("Failed to read file", not_found),
+            AppError::NotFound("Failed to read file: gone".to_string())
+        );
+        let denied = io::Error::new(io::ErrorKind::PermissionDenied, "nope");
+        assert_eq!(AppError::io("x", denied).code(), "permission_denied");
+        let other = io::Error::other("boom");
+        assert_eq!(AppError::io("x", other).code(), "io");
+    }
+
+    #[test]
+    fn test_serializes_code_and_message() {
+        let err = AppError::AlreadyExists("File already exists: a.md".to_string());
+        assert_eq!(
+            serde_json::to_value(&err).unwrap(),
+            serde_json::json!({ "code": "already_exists", "message": "File already exists: a.md" })
+        );
+    }
+}
@@ -0,0 +1,1467 @@
+use std::cmp::Ordering;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chardetng::{EncodingDetector, Iso2022JpDetection, Utf8Detection};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use serde::{Deserialize, Serialize};
+
+// File entry for directory listing
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FileEntry {
+    pub name: String,
+    pub path: String,
+    pub is_directory: bool,
+    /// Size in bytes, `0` when metadata can't be read
+    pub size: u64,
+    /// Last modification time in unix milliseconds
+    pub modified: Option<u64>,
+    /// Nested entries for directories when listed recursively, `None` otherwise
+    pub children: Option<Vec<FileEntry>>,
+}
+
+/// Metadata of a single path, read without opening the file
+#[derive(Serialize, Clone, Debug)]
+pub struct FileInfo {
+    pub size: u64,
+    /// Unix milliseconds; `None` where the platform doesn't record it
+    pub modified: Option<u64>,
+    pub created: Option<u64>,
+    pub is_directory: bool,
+    pub is_readonly: bool,
+    /// Lowercased extension without the dot
+    pub extension: Option<String>,
+}
+
+/// Stat `path` with a single `metadata()` call (following symlinks).
+pub fn file_info(path: &Path) -> std::io::Result<FileInfo> {
+    let metadata = fs::metadata(path)?;
+    Ok(FileInfo {
+        size: metadata.len(),
+        modified: modified_millis(&metadata),
+        created: metadata.created().ok().and_then(unix_millis),
+        is_directory: metadata.is_dir(),
+        is_readonly: metadata.permissions().readonly(),
+        extension: path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase()),
+    })
+}
+
+/// Key used to order entries within a listing. Directories always group first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortKey {
+    #[default]
+    Name,
+    Modified,
+    Size,
+}
+
+/// Sort key plus direction, parsed from strings like `"name"` or `"size_desc"`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SortMode {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+impl SortMode {
+    pub fn parse(mode: &str) -> Result<Self, String> {
+        let (key, descending) = match mode.strip_suffix("_desc") {
+            Some(key) => (key, true),
+            None => (mode.strip_suffix("_asc").unwrap_or(mode), false),
+        };
+        let key = match key {
+            "name" => SortKey::Name,
+            "modified" => SortKey::Modified,
+            "size" => SortKey::Size,
+            _ => return Err(format!("Invalid sort mode: {}", mode)),
+        };
+        Ok(SortMode { key, descending })
+    }
+}
+
+/// Options controlling how `list_directory` walks a folder.
+#[derive(Clone, Debug, Default)]
+pub struct ListOptions {
+    /// Descend into subdirectories and fill in `FileEntry::children`
+    pub recursive: bool,
+    /// Number of levels below the listed folder to descend; `None` is unbounded
+    pub max_depth: Option<usize>,
+    /// Ordering applied at every level
+    pub sort: SortMode,
+    /// Include dot-prefixed (and, on Windows, hidden-attribute) entries
+    pub show_hidden: bool,
+    /// Only keep files with one of these lowercase extensions (no dot);
+    /// directories are always kept, so an empty list lists directories only
+    pub extensions: Option<Vec<String>>,
+}
+
+/// List the contents of `dir`, directories first and then files, ordered by `options.sort`.
+/// Hidden entries are skipped unless `options.show_hidden` is set.
+pub fn list_directory(dir: &Path, options: &ListOptions) -> Result<Vec<FileEntry>, String> {
+    // Directories currently being walked, used to break symlink cycles
+    let mut ancestors: Vec<PathBuf> = Vec::new();
+    if let Ok(canonical) = fs::canonicalize(dir) {
+        ancestors.push(canonical);
+    }
+    list_level(dir, options, 0, &mut ancestors)
+}
+
+fn list_level(
+    dir: &Path,
+    options: &ListOptions,
+    depth: usize,
+    ancestors: &mut Vec<PathBuf>,
+) -> Result<Vec<FileEntry>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read directory: {}", e))?;
+
+    let descend = options.recursive && options.max_depth.is_none_or(|max| depth < max);
+    let mut file_entries = Vec::new();
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let metadata = entry.metadata().ok();
+
+        // Skip hidden files
+        if !options.show_hidden && is_hidden(&name, metadata.as_ref()) {
+            continue;
+        }
+
+        let is_directory = path.is_dir();
+        if !is_directory && !has_listed_extension(&path, options.extensions.as_deref()) {
+            continue;
+        }
+        let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+        let modified = metadata.as_ref().and_then(modified_millis);
+        let children = if is_directory && descend {
+            list_children(&path, options, depth + 1, ancestors)
+        } else {
+            None
+        };
+
+        file_entries.push(FileEntry {
+            name,
+            path: path.to_string_lossy().to_string(),
+            is_directory,
+            size,
+            modified,
+            children,
+        });
+    }
+
+    sort_entries(&mut file_entries, options.sort);
+    Ok(file_entries)
+}
+
+fn has_listed_extension(path: &Path, extensions: Option<&[String]>) -> bool {
+    let Some(extensions) = extensions else {
+        return true;
+    };
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+    extension.is_some_and(|ext| extensions.contains(&ext))
+}
+
+fn list_children(
+    dir: &Path,
+    options: &ListOptions,
+    depth: usize,
+    ancestors: &mut Vec<PathBuf>,
+) -> Option<Vec<FileEntry>> {
+    let canonical = fs::canonicalize(dir).ok()?;
+    // A directory that resolves to one of its own ancestors is a symlink cycle
+    if ancestors.contains(&canonical) {
+        return None;
+    }
+
+    ancestors.push(canonical);
+    let children = list_level(dir, options, depth, ancestors).ok();
+    ancestors.pop();
+    children
+}
+
+/// A window of a file too large to read at once
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct FileChunk {
+    pub content: String,
+    /// Byte offset where `content` starts, moved forward to a character boundary
+    pub offset: u64,
+    /// Byte offset just past `content`; pass it as the next window's offset
+    pub end: u64,
+    pub total_size: u64,
+}
+
+/// Read at most `max_bytes` of `path` starting at `offset`, trimmed at both
+/// ends so no UTF-8 character is split. Invalid bytes are replaced rather
+/// than failing, since a window can't be checked against the whole file.
+pub fn read_range(path: &Path, offset: u64, max_bytes: usize) -> std::io::Result<FileChunk> {
+    let mut file = fs::File::open(path)?;
+    let total_size = file.metadata()?.len();
+    let offset = offset.min(total_size);
+    // Room for at least one whole character, so paging always advances
+    let max_bytes = max_bytes.max(4) as u64;
+
+    let mut buf = Vec::new();
+    file.seek(SeekFrom::Start(offset))?;
+    file.take(max_bytes).read_to_end(&mut buf)?;
+
+    // Skip the tail of a character that started before the window
+    let start = buf
+        .iter()
+        .take(3)
+        .take_while(|b| is_continuation_byte(**b))
+        .count();
+    let mut end = buf.len();
+    if offset + (buf.len() as u64) < total_size {
+        // Leave a character cut off by the window for the next read
+        if let Some(lead) = (start..end)
+            .rev()
+            .take(4)
+            .find(|&i| !is_continuation_byte(buf[i]))
+        {
+            if lead + utf8_char_len(buf[lead]) > end {
+                end = lead;
+            }
+        }
+    }
+
+    Ok(FileChunk {
+        content: String::from_utf8_lossy(&buf[start..end]).into_owned(),
+        offset: offset + start as u64,
+        end: offset + end as u64,
+        total_size,
+    })
+}
+
+fn is_continuation_byte(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
+
+/// Length of the UTF-8 sequence a lead byte starts (1 for invalid leads)
+fn utf8_char_len(lead: u8) -> usize {
+    match lead {
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => 1,
+    }
+}
+
+/// Write `content` to `path` so that a crash never leaves a half-written file.
+/// The data goes to a sibling temp file that is renamed over the target, which is
+/// atomic on the same filesystem. If that fails (e.g. across filesystems, or on
+/// Windows while another process holds the file open), fall back to writing the
+/// target directly.
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<(), String> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid file path: {}", path.display()))?;
+    let mut temp_name = file_name.to_os_string();
+    temp_name.push(format!(".tmp-{}", std::process::id()));
+    let temp_path = path.with_file_name(temp_name);
+
+    if write_and_sync(&temp_path, content)
+        .and_then(|_| fs::rename(&temp_path, path))
+        .is_ok()
+    {
+        return Ok(());
+    }
+
+    // Never leave temp files behind in the user's folder
+    let _ = fs::remove_file(&temp_path);
+    fs::write(path, content).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+/// Resolve `path` to an absolute path with symlinks, `.` and `..` removed.
+/// The path may not exist yet (e.g. a file about to be created): its nearest
+/// existing ancestor is canonicalized and the rest is appended lexically.
+pub fn resolve_path(path: &Path) -> Result<PathBuf, String> {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .map_err(|e| format!("Failed to resolve path: {}", e))?
+            .join(path)
+    };
+
+    let mut missing = Vec::new();
+    let mut existing = absolute.as_path();
+    let mut resolved = loop {
+        match existing.canonicalize() {
+            Ok(canonical) => break canonical,
+            Err(_) => {
+                missing.push(existing.file_name());
+                existing = existing
+                    .parent()
+                    .ok_or_else(|| format!("Invalid file path: {}", path.display()))?;
+            }
+        }
+    };
+
+    // `file_name` is None for a trailing `..`, which steps up; `.` never shows up here
+    for name in missing.into_iter().rev() {
+        match name {
+            Some(name) => resolved.push(name),
+            None => {
+                resolved.pop();
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+/// The form paths are stored in (recent files, open documents): `~` expanded
+/// and the result passed through [`resolve_path`], so different spellings of
+/// the same file compare equal.
+pub fn canonicalize_path(path: &str) -> Result<String, String> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Err("Path cannot be empty".to_string());
+    }
+    Ok(resolve_path(&expand_home(path))?
+        .to_string_lossy()
+        .to_string())
+}
+
+/// Replace a leading `~` with the user's home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from);
+    expand_tilde(path, home.as_deref())
+}
+
+fn expand_tilde(path: &str, home: Option<&Path>) -> PathBuf {
+    let rest = match path.strip_prefix('~') {
+        Some("") => "",
+        // `~user` forms are left alone
+        Some(rest) if rest.starts_with('/') || rest.starts_with(std::path::MAIN_SEPARATOR) => {
+            &rest[1..]
+        }
+        _ => return PathBuf::from(path),
+    };
+    match home {
+        Some(home) => home.join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Resolve `path` and make sure it lies inside one of `allowed_roots`, which must
+/// already be resolved. An empty list allows any path.
+pub fn check_allowed_path(path: &Path, allowed_roots: &[PathBuf]) -> Result<PathBuf, String> {
+    let resolved = resolve_path(path)?;
+    if allowed_roots.is_empty() || allowed_roots.iter().any(|root| resolved.starts_with(root)) {
+        Ok(resolved)
+    } else {
+        Err(format!(
+            "Access denied: '{}' is outside the opened folders",
+            path.display()
+        ))
+    }
+}
+
+/// Create an empty file at `path`, creating missing parent directories.
+/// Fails with `ErrorKind::AlreadyExists` rather than truncating an existing file.
+pub fn create_empty_file(path: &Path) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    Ok(())
+}
+
+/// Rename `from` to `to`. Fails with `ErrorKind::AlreadyExists` when `to` is taken,
+/// unless `overwrite` is set. A case-only rename (`Note.md` → `note.md`) goes through
+/// a temporary name, since case-insensitive filesystems may otherwise ignore it.
+pub fn rename_path(from: &Path, to: &Path, overwrite: bool) -> std::io::Result<()> {
+    if from == to {
+        return Ok(());
+    }
+
+    let case_only = from.to_string_lossy().to_lowercase() == to.to_string_lossy().to_lowercase();
+    // On a case-insensitive filesystem `to.exists()` is true for the source itself
+    let taken = if case_only {
+        exists_with_exact_name(to)
+    } else {
+        to.exists()
+    };
+    if taken && !overwrite {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("'{}' already exists", to.display()),
+        ));
+    }
+    if !case_only {
+        return fs::rename(from, to);
+    }
+
+    let file_name = from.file_name().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Invalid file path: {}", from.display()),
+        )
+    })?;
+    let mut temp_name = file_name.to_os_string();
+    temp_name.push(format!(".rename-{}", std::process::id()));
+    let temp_path = from.with_file_name(temp_name);
+
+    fs::rename(from, &temp_path)?;
+    fs::rename(&temp_path, to).inspect_err(|_| {
+        let _ = fs::rename(&temp_path, from);
+    })
+}
+
+// Whether a directory entry spelled exactly like `path`'s file name exists
+fn exists_with_exact_name(path: &Path) -> bool {
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return false;
+    };
+    let parent = if parent.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        parent
+    };
+    fs::read_dir(parent)
+        .map(|entries| entries.flatten().any(|entry| entry.file_name() == name))
+        .unwrap_or(false)
+}
+
+/// Folder beside a document where pasted images are stored
+pub const ASSETS_DIR: &str = "assets";
+
+/// Store a pasted image in the `assets/` folder beside `doc_path`, named after a
+/// hash of its content so pasting the same image twice reuses one file. Returns the
+/// path relative to the document (always with `/`), ready for `![](assets/…)`.
+pub fn save_pasted_image(doc_path: &Path, bytes: &[u8], mime: &str) -> Result<String, String> {
+    let extension =
+        image_extension(mime).ok_or_else(|| format!("Unsupported image type: {}", mime))?;
+    let dir = doc_path
+        .parent()
+        .ok_or_else(|| format!("Invalid document path: {}", doc_path.display()))?
+        .join(ASSETS_DIR);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create assets folder: {}", e))?;
+
+    // Suffix the name in the unlikely case of a hash collision with different bytes
+    let hash = format!("{:016x}", fnv1a(bytes));
+    let mut name = format!("{}.{}", hash, extension);
+    let mut n = 1;
+    loop {
+        let path = dir.join(&name);
+        match fs::read(&path) {
+            Ok(existing) if existing == bytes => break,
+            Ok(_) => {
+                name = format!("{}-{}.{}", hash, n, extension);
+                n += 1;
+            }
+            Err(_) => {
+                write_atomic(&path, bytes)?;
+                break;
+            }
+        }
+    }
+    Ok(format!("{}/{}", ASSETS_DIR, name))
+}
+
+fn image_extension(mime: &str) -> Option<&'static str> {
+    match mime.trim().to_lowercase().as_str() {
+        "image/png" => Some("png"),
+        "image/jpeg" | "image/jpg" => Some("jpg"),
+        "image/gif" => Some("gif"),
+        "image/webp" => Some("webp"),
+        "image/svg+xml" => Some("svg"),
+        "image/bmp" => Some("bmp"),
+        "image/avif" => Some("avif"),
+        _ => None,
+    }
+}
+
+// 64-bit FNV-1a: stable across runs and Rust versions, unlike `DefaultHasher`
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Number of backups kept per file in the `.backups` folder
+pub const MAX_BACKUPS_PER_FILE: usize = 5;
+
+/// Copy the current on-disk version of `path` into a `.backups` folder next to it,
+/// named `<filename>.<timestamp>.md`, then prune all but the newest `keep` backups.
+/// Does nothing when the file doesn't exist yet.
+pub fn create_backup(path: &Path, keep: usize) -> Result<Option<PathBuf>, String> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid file path: {}", path.display()))?;
+    let backup_dir = path.with_file_name(".backups");
+    fs::create_dir_all(&backup_dir)
+        .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let backup_path = backup_dir.join(format!("{}.{}.md", file_name, timestamp));
+    fs::copy(path, &backup_path).map_err(|e| format!("Failed to create backup: {}", e))?;
+
+    prune_backups(&backup_dir, &file_name, keep)?;
+    Ok(Some(backup_path))
+}
+
+fn prune_backups(backup_dir: &Path, file_name: &str, keep: usize) -> Result<(), String> {
+    let prefix = format!("{}.", file_name);
+    // Backups of this file, keyed by their millisecond timestamp
+    let mut backups: Vec<(u128, PathBuf)> = fs::read_dir(backup_dir)
+        .map_err(|e| format!("Failed to read backup directory: {}", e))?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let timestamp = name
+                .strip_prefix(&prefix)?
+                .strip_suffix(".md")?
+                .parse()
+                .ok()?;
+            Some((timestamp, entry.path()))
+        })
+        .collect();
+
+    backups.sort_by_key(|(timestamp, _)| std::cmp::Reverse(*timestamp));
+    for (_, old) in backups.into_iter().skip(keep) {
+        let _ = fs::remove_file(old);
+    }
+    Ok(())
+}
+
+fn write_and_sync(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(content)?;
+    file.sync_all()
+}
+
+/// Line terminator style of a text file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "lf",
+            LineEnding::Crlf => "crlf",
+        }
+    }
+}
+
+/// Dominant line ending of `content`, or `None` when it has no line breaks.
+pub fn detect_line_ending(content: &str) -> Option<LineEnding> {
+    let total = content.matches('\n').count();
+    if total == 0 {
+        return None;
+    }
+    let crlf = content.matches("\r\n").count();
+    if crlf > total - crlf {
+        Some(LineEnding::Crlf)
+    } else {
+        Some(LineEnding::Lf)
+    }
+}
+
+/// Rewrite every line break in `content` as `style`: `"lf"`, `"crlf"`, or `"preserve"`
+/// (returned unchanged).
+pub fn normalize_line_endings(content: &str, style: &str) -> Result<String, String> {
+    match style {
+        "preserve" => Ok(content.to_string()),
+        "lf" => Ok(content.replace("\r\n", "\n")),
+        "crlf" => Ok(content.replace("\r\n", "\n").replace('\n', "\r\n")),
+        other => Err(format!("Invalid line ending style: {}", other)),
+    }
+}
+
+/// Decode file bytes to text. Valid UTF-8 takes the fast path; anything else is
+/// decoded best-effort using the encoding guessed by `chardetng`.
+pub fn decode_text(bytes: Vec<u8>) -> (String, &'static Encoding) {
+    let bytes = match String::from_utf8(bytes) {
+        Ok(text) => return (text, UTF_8),
+        Err(e) => e.into_bytes(),
+    };
+
+    let mut detector = EncodingDetector::new(Iso2022JpDetection::Deny);
+    detector.feed(&bytes, true);
+    let encoding = detector.guess(None, Utf8Detection::Deny);
+    // decode() also honors a BOM if present and reports the encoding actually used
+    let (text, encoding, _) = encoding.decode(&bytes);
+    (text.into_owned(), encoding)
+}
+
+/// Encode text for writing in the encoding named by `label` (e.g. `"utf-8"`,
+/// `"windows-1252"`). Fails rather than silently substituting characters the
+/// target encoding can't represent. UTF-16 is written with a byte order mark,
+/// which is how `decode_text` recognizes it.
+pub fn encode_text(text: &str, label: &str) -> Result<Vec<u8>, String> {
+    let encoding = Encoding::for_label(label.as_bytes())
+        .ok_or_else(|| format!("Unknown encoding: {}", label))?;
+    if encoding == UTF_8 {
+        return Ok(text.as_bytes().to_vec());
+    }
+    // encoding_rs only encodes to UTF-8 for these, so write the code units directly
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let units = std::iter::once(0xFEFF).chain(text.encode_utf16());
+        return Ok(if encoding == UTF_16LE {
+            units.flat_map(u16::to_le_bytes).collect()
+        } else {
+            units.flat_map(u16::to_be_bytes).collect()
+        });
+    }
+
+    let (bytes, used, had_errors) = encoding.encode(text);
+    if had_errors {
+        return Err(format!(
+            "Content contains characters that can't be saved as {}; save as UTF-8 instead",
+            used.name()
+        ));
+    }
+    Ok(bytes.into_owned())
+}
+
+/// Stop collecting search hits after this many
+const MAX_SEARCH_HITS: usize = 500;
+/// Lines longer than this are cut down to a window around the match
+const MAX_SNIPPET_CHARS: usize = 160;
+const SNIPPET_CHARS_BEFORE: usize = 60;
+
+/// A line in a Markdown file that contains a search query.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SearchHit {
+    pub path: String,
+    /// 1-based
+    pub line_number: usize,
+    /// The line, shortened around the first match when it is long
+    pub snippet: String,
+}
+
+/// One match of a search pattern in a Markdown file
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchMatch {
+    pub path: String,
+    /// 1-based
+    pub line_number: usize,
+    pub line: String,
+    /// Byte range of the match within `line`
+    pub range: std::ops::Range<usize>,
+}
+
+/// Every match of `re` in the Markdown files under `dir`, line by line.
+/// Hidden files and folders are skipped unless `show_hidden` is set, as in
+/// `list_directory`; files that look binary are ignored. Results are capped
+/// at 500 matches.
+pub fn search_in_files(
+    dir: &Path,
+    re: &regex::Regex,
+    show_hidden: bool,
+) -> Result<Vec<SearchMatch>, String> {
+    let options = ListOptions {
+        recursive: true,
+        show_hidden,
+        ..Default::default()
+    };
+    let mut files = Vec::new();
+    collect_markdown_files(list_directory(dir, &options)?, &mut files);
+
+    let mut matches = Vec::new();
+    for path in files {
+        let Ok(bytes) = fs::read(&path) else { continue };
+        if bytes.contains(&0) {
+            continue;
+        }
+        let (content, _) = decode_text(bytes);
+        for (idx, line) in content.lines().enumerate() {
+            for m in re.find_iter(line) {
+                matches.push(SearchMatch {
+                    path: path.clone(),
+                    line_number: idx + 1,
+                    line: line.to_string(),
+                    range: m.range(),
+                });
+                if matches.len() >= MAX_SEARCH_HITS {
+                    return Ok(matches);
+                }
+            }
+        }
+    }
+    Ok(matches)
+}
+
+/// Find lines containing `query` (plain text, not a pattern) in the Markdown
+/// files under `dir`, one hit per line, through [`search_in_files`].
+pub fn search_in_directory(
+    dir: &Path,
+    query: &str,
+    case_sensitive: bool,
+    show_hidden: bool,
+) -> Result<Vec<SearchHit>, String> {
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let re = regex::RegexBuilder::new(&regex::escape(query))
+        .case_insensitive(!case_sensitive)
+        .build()
+        .map_err(|e| format!("Invalid query: {}", e))?;
+
+    let mut hits: Vec<SearchHit> = Vec::new();
+    for m in search_in_files(dir, &re, show_hidden)? {
+        // Later matches on a line already listed add nothing
+        if hits
+            .last()
+            .is_some_and(|hit| hit.path == m.path && hit.line_number == m.line_number)
+        {
+            continue;
+        }
+        hits.push(SearchHit {
+            snippet: snippet(&m.line, m.range.start),
+            path: m.path,
+            line_number: m.line_number,
+        });
+    }
+    Ok(hits)
+}
+
+/// Files under `root` whose path relative to it matches a glob such as
+/// `**/*.md`. Wildcards don't match a leading `.`, so hidden files and folders
+/// are only found when the pattern spells out the dot (`.drafts/*.md`).
+/// Absolute patterns and `..` segments are rejected, and matches that resolve
+/// outside `root` through a symlink are dropped.
+pub fn find_files(root: &Path, pattern: &str) -> Result<Vec<String>, String> {
+    let relative = Path::new(pattern);
+    if relative.has_root()
+        || relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(format!("Pattern must stay inside the folder: {}", pattern));
+    }
+    let canonical_root = root
+        .canonicalize()
+        .map_err(|e| format!("Failed to read folder: {}", e))?;
+
+    let full_pattern = format!(
+        "{}{}{}",
+        glob::Pattern::escape(&root.to_string_lossy()),
+        std::path::MAIN_SEPARATOR,
+        pattern
+    );
+    let options = glob::MatchOptions {
+        require_literal_leading_dot: true,
+        ..Default::default()
+    };
+    let paths =
+        glob::glob_with(&full_pattern, options).map_err(|e| format!("Invalid pattern: {}", e))?;
+
+    Ok(paths
+        .filter_map(Result::ok)
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.canonicalize()
+                .is_ok_and(|resolved| resolved.starts_with(&canonical_root))
+        })
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Every Markdown file under `dir`, skipping hidden files and folders
+pub fn markdown_files(dir: &Path) -> Result<Vec<String>, String> {
+    let options = ListOptions {
+        recursive: true,
+        ..Default::default()
+    };
+    let mut files = Vec::new();
+    collect_markdown_files(list_directory(dir, &options)?, &mut files);
+    Ok(files)
+}
+
+fn collect_markdown_files(entries: Vec<FileEntry>, out: &mut Vec<String>) {
+    for entry in entries {
+        if entry.is_directory {
+            collect_markdown_files(entry.children.unwrap_or_default(), out);
+        } else if is_markdown_file(&entry.name) {
+            out.push(entry.path);
+        }
+    }
+}
+
+pub(crate) fn is_markdown_file(name: &str) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("md") || e.eq_ignore_ascii_case("markdown"))
+}
+
+/// `line` trimmed, or for long lines a window around the byte offset `at`.
+fn snippet(line: &str, at: usize) -> String {
+    let line_chars = line.chars().count();
+    if line_chars <= MAX_SNIPPET_CHARS {
+        return line.trim().to_string();
+    }
+    let match_char = line[..at].chars().count();
+    let start = match_char.saturating_sub(SNIPPET_CHARS_BEFORE);
+    let end = (start + MAX_SNIPPET_CHARS).min(line_chars);
+    let mut text: String = line.chars().skip(start).take(end - start).collect();
+    if start > 0 {
+        text.insert(0, '…');
+    }
+    if end < line_chars {
+        text.push('…');
+    }
+    text
+}
+
+/// Dot-prefixed names are hidden everywhere; on Windows the hidden attribute counts too.
+fn is_hidden(name: &str, metadata: Option<&fs::Metadata>) -> bool {
+    if name.starts_with('.') {
+        return true;
+    }
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if let Some(metadata) = metadata {
+            return metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0;
+        }
+    }
+    #[cfg(not(windows))]
+    let _ = metadata;
+
+    false
+}
+
+fn modified_millis(metadata: &fs::Metadata) -> Option<u64> {
+    unix_millis(metadata.modified().ok()?)
+}
+
+fn unix_millis(time: SystemTime) -> Option<u64> {
+    let since_epoch = time.duration_since(UNIX_EPOCH).ok()?;
+    Some(since_epoch.as_millis() as u64)
+}
+
+// Sort: directories first, then files, both by the requested key (name breaks ties)
+fn sort_entries(entries: &mut [FileEntry], mode: SortMode) {
+    entries.sort_by(|a, b| match (a.is_directory, b.is_directory) {
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        _ => {
+            let by_name = || a.name.to_lowercase().cmp(&b.name.to_lowercase());
+            let ordering = match mode.key {
+                SortKey::Name => by_name(),
+                SortKey::Modified => a.modified.cmp(&b.modified).then_with(by_name),
+                SortKey::Size => a.size.cmp(&b.size).then_with(by_name),
+            };
+            if mode.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_directory_recursive_nests_children() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("notes/deep")).unwrap();
+        fs::write(dir.join("notes/deep/c.md"), "").unwrap();
+        fs::write(dir.join("notes/b.md"), "").unwrap();
+        fs::write(dir.join("a.md"), "").unwrap();
+
+        let options = ListOptions {
+            recursive: true,
+            ..Default::default()
+        };
+        let entries = list_directory(dir, &options).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "notes");
+        assert!(entries[1].children.is_none());
+        let notes = entries[0].children.as_ref().unwrap();
+        assert_eq!(notes[0].name, "deep");
+        assert_eq!(notes[1].name, "b.md");
+        assert_eq!(notes[0].children.as_ref().unwrap()[0].name, "c.md");
+    }
+
+    #[test]
+    fn test_list_directory_reports_size_and_modified() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::write(dir.join("note.md"), "hello").unwrap();
+
+        let entries = list_directory(dir, &ListOptions::default()).unwrap();
+
+        assert_eq!(entries[0].size, 5);
+        assert!(entries[0].modified.is_some());
+    }
+
+    #[test]
+    fn test_file_info() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let file = dir.join("Notes.MD");
+        fs::write(&file, "hello").unwrap();
+
+        let info = file_info(&file).unwrap();
+        let dir_info = file_info(dir).unwrap();
+        let missing = file_info(&dir.join("missing.md")).unwrap_err();
+
+        assert_eq!(info.size, 5);
+        assert!(info.modified.is_some());
+        assert!(!info.is_directory);
+        assert!(!info.is_readonly);
+        assert_eq!(info.extension.as_deref(), Some("md"));
+        assert!(dir_info.is_directory);
+        assert_eq!(missing.kind(), std::io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_list_directory_filters_by_extension() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("drafts")).unwrap();
+        fs::write(dir.join("a.md"), "").unwrap();
+        fs::write(dir.join("B.MD"), "").unwrap();
+        fs::write(dir.join("c.txt"), "").unwrap();
+        fs::write(dir.join("README"), "").unwrap();
+
+        let md_only = ListOptions {
+            extensions: Some(vec!["md".to_string()]),
+            ..Default::default()
+        };
+        let dirs_only = ListOptions {
+            extensions: Some(Vec::new()),
+            ..Default::default()
+        };
+        let md_names: Vec<String> = list_directory(dir, &md_only)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        let dir_names: Vec<String> = list_directory(dir, &dirs_only)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+
+        assert_eq!(md_names, vec!["drafts", "a.md", "B.MD"]);
+        assert_eq!(dir_names, vec!["drafts"]);
+    }
+
+    #[test]
+    fn test_list_directory_respects_max_depth() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("one/two")).unwrap();
+
+        let options = ListOptions {
+            recursive: true,
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let entries = list_directory(dir, &options).unwrap();
+
+        let one = entries[0].children.as_ref().unwrap();
+        assert_eq!(one[0].name, "two");
+        assert!(one[0].children.is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_list_directory_stops_at_symlink_cycle() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        std::os::unix::fs::symlink(dir, dir.join("sub/loop")).unwrap();
+
+        let options = ListOptions {
+            recursive: true,
+            ..Default::default()
+        };
+        let entries = list_directory(dir, &options).unwrap();
+
+        let sub = entries[0].children.as_ref().unwrap();
+        assert_eq!(sub[0].name, "loop");
+        assert!(sub[0].children.is_none());
+    }
+
+    #[test]
+    fn test_sort_mode_parse() {
+        assert_eq!(SortMode::parse("name").unwrap(), SortMode::default());
+        assert_eq!(
+            SortMode::parse("size_desc").unwrap(),
+            SortMode {
+                key: SortKey::Size,
+                descending: true
+            }
+        );
+        assert!(SortMode::parse("nmae").is_err());
+    }
+
+    #[test]
+    fn test_list_directory_sorts_by_size_with_directories_first() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("folder")).unwrap();
+        fs::write(dir.join("big.md"), "0123456789").unwrap();
+        fs::write(dir.join("small.md"), "0").unwrap();
+
+        let options = ListOptions {
+            sort: SortMode::parse("size_desc").unwrap(),
+            ..Default::default()
+        };
+        let entries = list_directory(dir, &options).unwrap();
+
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["folder", "big.md", "small.md"]);
+    }
+
+    #[test]
+    fn test_list_directory_hidden_entries() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join(".github")).unwrap();
+        fs::write(dir.join(".env.md"), "").unwrap();
+        fs::write(dir.join("visible.md"), "").unwrap();
+
+        let hidden_skipped = list_directory(dir, &ListOptions::default()).unwrap();
+        let options = ListOptions {
+            show_hidden: true,
+            ..Default::default()
+        };
+        let hidden_shown = list_directory(dir, &options).unwrap();
+
+        let names: Vec<&str> = hidden_skipped.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["visible.md"]);
+        let names: Vec<&str> = hidden_shown.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, [".github", ".env.md", "visible.md"]);
+    }
+
+    #[test]
+    fn test_read_range_pages_through_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let file = dir.join("log.md");
+        fs::write(&file, "abcdefghij").unwrap();
+
+        let first = read_range(&file, 0, 4).unwrap();
+        let second = read_range(&file, first.end, 4).unwrap();
+        let last = read_range(&file, 8, 100).unwrap();
+        let past_end = read_range(&file, 50, 4).unwrap();
+
+        assert_eq!(
+            first,
+            FileChunk {
+                content: "abcd".to_string(),
+                offset: 0,
+                end: 4,
+                total_size: 10
+            }
+        );
+        assert_eq!(second.content, "efgh");
+        assert_eq!(last.content, "ij");
+        assert_eq!(last.end, 10);
+        assert_eq!(past_end.content, "");
+        assert_eq!(past_end.offset, 10);
+    }
+
+    #[test]
+    fn test_read_range_aligns_to_char_boundaries() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let file = dir.join("notes.md");
+        // "a" then three 3-byte characters: bytes 1..4, 4..7, 7..10
+        fs::write(&file, "a日本語").unwrap();
+
+        // Window 0..5 cuts 本 in half: it is left for the next read
+        let head = read_range(&file, 0, 5).unwrap();
+        // Window starting at 2 is inside 日: its tail is skipped
+        let middle = read_range(&file, 2, 5).unwrap();
+
+        assert_eq!(head.content, "a日");
+        assert_eq!(head.end, 4);
+        assert_eq!(middle.content, "本");
+        assert_eq!((middle.offset, middle.end), (4, 7));
+    }
+
+    #[test]
+    fn test_expand_tilde() {
+        let home = Path::new("/home/ana");
+        assert_eq!(
+            expand_tilde("~/notes/a.md", Some(home)),
+            PathBuf::from("/home/ana/notes/a.md")
+        );
+        assert_eq!(expand_tilde("~", Some(home)), PathBuf::from("/home/ana"));
+        assert_eq!(
+            expand_tilde("~bob/a.md", Some(home)),
+            PathBuf::from("~bob/a.md")
+        );
+        assert_eq!(
+            expand_tilde("notes/~/a.md", Some(home)),
+            PathBuf::from("notes/~/a.md")
+        );
+        assert_eq!(expand_tilde("~/a.md", None), PathBuf::from("~/a.md"));
+    }
+
+    #[test]
+    fn test_canonicalize_path_normalizes_dots() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("notes")).unwrap();
+        fs::write(dir.join("notes").join("a.md"), "").unwrap();
+
+        let direct = canonicalize_path(dir.join("notes").join("a.md").to_str().unwrap()).unwrap();
+        let dotted =
+            canonicalize_path(dir.join("notes/./../notes/a.md").to_str().unwrap()).unwrap();
+        let missing = canonicalize_path(dir.join("notes/../new.md").to_str().unwrap()).unwrap();
+        let canonical_dir = dir.canonicalize().unwrap();
+
+        assert_eq!(dotted, direct);
+        assert_eq!(
+            direct,
+            canonical_dir.join("notes").join("a.md").to_string_lossy()
+        );
+        assert_eq!(missing, canonical_dir.join("new.md").to_string_lossy());
+        assert!(canonicalize_path("  ").is_err());
+    }
+
+    #[test]
+    fn test_write_atomic_replaces_content_without_temp_litter() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let target = dir.join("note.md");
+        fs::write(&target, "old content that is longer").unwrap();
+
+        write_atomic(&target, b"new").unwrap();
+        let content = fs::read_to_string(&target).unwrap();
+        let leftovers = fs::read_dir(dir).unwrap().count();
+
+        assert_eq!(content, "new");
+        assert_eq!(leftovers, 1);
+    }
+
+    #[test]
+    fn test_write_atomic_falls_back_to_direct_write() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let target = dir.join("note.md");
+        fs::write(&target, "old").unwrap();
+        // Something in the way of the temp file makes the rename route fail
+        let blocker = dir.join(format!("note.md.tmp-{}", std::process::id()));
+        fs::create_dir_all(blocker.join("inside")).unwrap();
+
+        write_atomic(&target, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        assert!(blocker.join("inside").is_dir());
+    }
+
+    #[test]
+    fn test_write_atomic_failure_keeps_original() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        // A non-empty directory can't be replaced by a file
+        let target = dir.join("note.md");
+        fs::create_dir_all(target.join("inside")).unwrap();
+
+        let result = write_atomic(&target, b"new");
+        let leftovers = fs::read_dir(dir).unwrap().count();
+        let kept = target.join("inside").is_dir();
+
+        assert!(result.unwrap_err().starts_with("Failed to write file"));
+        assert_eq!(leftovers, 1);
+        assert!(kept);
+    }
+
+    #[test]
+    fn test_create_backup_skips_missing_and_prunes_old() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let target = dir.join("note.md");
+        assert!(create_backup(&target, 2).unwrap().is_none());
+
+        fs::write(&target, "v1").unwrap();
+        let first = create_backup(&target, 2).unwrap().unwrap();
+        for _ in 0..3 {
+            std::thread::sleep(std::time::Duration::from_millis(2));
+            create_backup(&target, 2).unwrap();
+        }
+        let remaining = fs::read_dir(dir.join(".backups")).unwrap().count();
+        let first_exists = first.exists();
+
+        assert_eq!(remaining, 2);
+        assert!(!first_exists);
+    }
+
+    #[test]
+    fn test_detect_line_ending_picks_dominant_style() {
+        assert_eq!(detect_line_ending("a\r\nb\r\nc\n"), Some(LineEnding::Crlf));
+        assert_eq!(detect_line_ending("a\nb\nc\r\n"), Some(LineEnding::Lf));
+        assert_eq!(detect_line_ending("single line"), None);
+    }
+
+    #[test]
+    fn test_normalize_line_endings_mixed_input() {
+        let mixed = "a\r\nb\nc\r\n";
+        assert_eq!(normalize_line_endings(mixed, "lf").unwrap(), "a\nb\nc\n");
+        assert_eq!(
+            normalize_line_endings(mixed, "crlf").unwrap(),
+            "a\r\nb\r\nc\r\n"
+        );
+        assert_eq!(normalize_line_endings(mixed, "preserve").unwrap(), mixed);
+        assert!(normalize_line_endings(mixed, "cr").is_err());
+    }
+
+    #[test]
+    fn test_decode_text_utf8_fast_path() {
+        let (text, encoding) = decode_text("héllo".as_bytes().to_vec());
+        assert_eq!(text, "héllo");
+        assert_eq!(encoding, UTF_8);
+    }
+
+    #[test]
+    fn test_decode_text_latin1_round_trip() {
+        // "café crème" in Latin-1 / windows-1252
+        let bytes = b"caf\xe9 cr\xe8me".to_vec();
+        let (text, encoding) = decode_text(bytes.clone());
+        assert_eq!(text, "café crème");
+        assert_eq!(encode_text(&text, encoding.name()).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_decode_text_utf16_round_trip() {
+        for bytes in [
+            b"\xff\xfec\x00a\x00f\x00\xe9\x00".to_vec(),
+            b"\xfe\xff\x00c\x00a\x00f\x00\xe9".to_vec(),
+        ] {
+            let (text, encoding) = decode_text(bytes.clone());
+            assert_eq!(text, "café");
+            assert_eq!(encode_text(&text, encoding.name()).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn test_encode_text_rejects_unrepresentable_characters() {
+        assert!(encode_text("中文", "windows-1252").is_err());
+        assert!(encode_text("text", "not-an-encoding").is_err());
+    }
+
+    #[test]
+    fn test_search_in_directory() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("notes")).unwrap();
+        fs::create_dir_all(dir.join(".trash")).unwrap();
+        fs::write(dir.join("a.md"), "# Plans\n\nShip the Release on Friday\n").unwrap();
+        fs::write(
+            dir.join("notes/b.markdown"),
+            "release notes\nnothing here\n",
+        )
+        .unwrap();
+        fs::write(dir.join("notes/c.txt"), "release in a text file\n").unwrap();
+        fs::write(dir.join("notes/d.md"), b"release\0binary").unwrap();
+        fs::write(dir.join(".trash/e.md"), "old release\n").unwrap();
+
+        let hits = search_in_directory(dir, "release", false, false).unwrap();
+        let mut found: Vec<_> = hits
+            .iter()
+            .map(|h| {
+                (
+                    h.path
+                        .strip_prefix(dir.to_str().unwrap())
+                        .unwrap()
+                        .to_string(),
+                    h.line_number,
+                    h.snippet.as_str(),
+                )
+            })
+            .collect();
+        found.sort();
+        let sep = std::path::MAIN_SEPARATOR;
+        assert_eq!(
+            found,
+            vec![
+                (format!("{}a.md", sep), 3, "Ship the Release on Friday"),
+                (format!("{}notes{}b.markdown", sep, sep), 1, "release notes"),
+            ]
+        );
+
+        assert_eq!(
+            search_in_directory(dir, "release", true, false)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            search_in_directory(dir, "release", false, true)
+                .unwrap()
+                .len(),
+            3
+        );
+        assert!(search_in_directory(dir, "", false, false)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_find_files_matches_nested_markdown() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("notes/deep")).unwrap();
+        fs::create_dir_all(dir.join(".drafts")).unwrap();
+        fs::write(dir.join("a.md"), "").unwrap();
+        fs::write(dir.join("notes/b.md"), "").unwrap();
+        fs::write(dir.join("notes/deep/c.md"), "").unwrap();
+        fs::write(dir.join("notes/d.txt"), "").unwrap();
+        fs::write(dir.join(".drafts/e.md"), "").unwrap();
+
+        let relative = |paths: Vec<String>| -> Vec<String> {
+            paths
+                .iter()
+                .map(|p| {
+                    Path::new(p)
+                        .strip_prefix(dir)
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                })
+                .collect()
+        };
+        assert_eq!(
+            relative(find_files(dir, "**/*.md").unwrap()),
+            ["a.md", "notes/b.md", "notes/deep/c.md"]
+        );
+        assert_eq!(
+            relative(find_files(dir, ".drafts/*.md").unwrap()),
+            [".drafts/e.md"]
+        );
+        assert!(find_files(dir, "**/*.pdf").unwrap().is_empty());
+        assert!(find_files(dir, "../**/*.md").is_err());
+        assert!(find_files(dir, "/etc/*").is_err());
+    }
+
+    #[test]
+    fn test_search_snippet_window() {
+        let line = format!("{}needle{}", "a".repeat(200), "b".repeat(200));
+        let text = snippet(&line, 200);
+        assert!(text.starts_with('…') && text.ends_with('…'));
+        assert!(text.contains("needle"));
+        assert_eq!(text.chars().count(), MAX_SNIPPET_CHARS + 2);
+    }
+
+    #[test]
+    fn test_check_allowed_path_rejects_traversal() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("vault/notes")).unwrap();
+        fs::write(dir.join("vault/notes/a.md"), "a").unwrap();
+        fs::write(dir.join("secret.md"), "secret").unwrap();
+        let roots = vec![resolve_path(&dir.join("vault")).unwrap()];
+
+        let escape = dir.join("vault/notes/../../../../../etc/passwd");
+        let err = check_allowed_path(&escape, &roots).unwrap_err();
+        assert!(err.starts_with("Access denied"), "{}", err);
+        assert!(check_allowed_path(&dir.join("vault/../secret.md"), &roots).is_err());
+        assert!(check_allowed_path(&dir.join("vault/missing/../../secret.md"), &roots).is_err());
+
+        // Existing and not-yet-created files inside the root are fine
+        let inside = check_allowed_path(&dir.join("vault/notes/./a.md"), &roots).unwrap();
+        assert_eq!(inside, roots[0].join("notes").join("a.md"));
+        let new_file = check_allowed_path(&dir.join("vault/new/../notes/b.md"), &roots).unwrap();
+        assert_eq!(new_file, roots[0].join("notes").join("b.md"));
+
+        // Without configured roots every path is allowed
+        assert!(check_allowed_path(&escape, &[]).is_ok());
+    }
+
+    #[test]
+    fn test_create_empty_file_never_clobbers() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("nested/new.md");
+        create_empty_file(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+
+        fs::write(&path, "# Keep me\n").unwrap();
+        let err = create_empty_file(&path).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# Keep me\n");
+    }
+
+    #[test]
+    fn test_rename_path_refuses_to_overwrite() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let a = dir.join("a.md");
+        let b = dir.join("b.md");
+        fs::write(&a, "a").unwrap();
+        fs::write(&b, "b").unwrap();
+
+        let err = rename_path(&a, &b, false).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(&a).unwrap(), "a");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "b");
+
+        rename_path(&a, &b, true).unwrap();
+        assert!(!a.exists());
+        assert_eq!(fs::read_to_string(&b).unwrap(), "a");
+    }
+
+    #[test]
+    fn test_rename_path_case_only() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::write(dir.join("Note.md"), "note").unwrap();
+
+        rename_path(&dir.join("Note.md"), &dir.join("note.md"), false).unwrap();
+        let names: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["note.md"]);
+        assert_eq!(fs::read_to_string(dir.join("note.md")).unwrap(), "note");
+    }
+
+    #[test]
+    fn test_save_pasted_image_dedupes_by_content() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let doc = dir.join("note.md");
+        let png = [0x89, b'P', b'N', b'G', 1, 2, 3];
+
+        let first = save_pasted_image(&doc, &png, "image/png").unwrap();
+        let second = save_pasted_image(&doc, &png, "image/png").unwrap();
+        assert_eq!(first, second);
+        assert!(first.starts_with("assets/") && first.ends_with(".png"));
+        assert_eq!(fs::read(dir.join(&first)).unwrap(), png);
+        assert_eq!(fs::read_dir(dir.join(ASSETS_DIR)).unwrap().count(), 1);
+
+        let other = save_pasted_image(&doc, b"GIF89a", "image/gif").unwrap();
+        assert_ne!(other, first);
+        assert!(other.ends_with(".gif"));
+        assert!(save_pasted_image(&doc, &png, "application/pdf").is_err());
+    }
+}
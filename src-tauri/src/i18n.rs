@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Language used when a locale or a key is missing
+const FALLBACK_LANGUAGE: &str = "en";
+
+/// Menu translations, one flat `key -> label` JSON file per locale.
+/// Adding a language means adding a file here and a menu item for it.
+/// Chinese is keyed by script; a `zh-Hans` table is picked up by
+/// `locale_chain` as soon as it is added.
+const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.json")),
+    ("zh-Hant", include_str!("../locales/zh-Hant.json")),
+    ("ja", include_str!("../locales/ja.json")),
+];
+
+/// Regions that write Chinese in simplified characters
+const SIMPLIFIED_CHINESE_REGIONS: &[&str] = &["cn", "sg", "my"];
+
+type Translations = HashMap<String, HashMap<String, String>>;
+
+fn translations() -> &'static Translations {
+    static TRANSLATIONS: OnceLock<Translations> = OnceLock::new();
+    TRANSLATIONS.get_or_init(|| {
+        LOCALES
+            .iter()
+            .map(|(lang, json)| {
+                let table = serde_json::from_str(json)
+                    .unwrap_or_else(|e| panic!("Invalid translations for {}: {}", lang, e));
+                (lang.to_string(), table)
+            })
+            .collect()
+    })
+}
+
+/// Translation tables to try for a locale such as `zh-TW`, `zh_CN.UTF-8` or
+/// `ja`, most specific first and always ending in English. Simplified
+/// Chinese locales try `zh-Hans` and then `zh-Hant`; other Chinese locales
+/// use `zh-Hant`. Other languages reduce to their primary code.
+pub fn locale_chain(locale: &str) -> Vec<String> {
+    // Drop POSIX encoding/modifier suffixes ("zh_TW.UTF-8", "de_DE@euro")
+    let tag = locale.split(['.', '@']).next().unwrap_or("");
+    let mut subtags = tag
+        .split(['-', '_'])
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_lowercase());
+    let language = subtags.next().unwrap_or_default();
+    let subtags: Vec<String> = subtags.collect();
+
+    let mut chain = Vec::new();
+    if language == "zh" {
+        let traditional = subtags.iter().any(|s| s == "hant");
+        let simplified = subtags
+            .iter()
+            .any(|s| s == "hans" || SIMPLIFIED_CHINESE_REGIONS.contains(&s.as_str()));
+        if simplified && !traditional {
+            chain.push("zh-Hans".to_string());
+        }
+        chain.push("zh-Hant".to_string());
+    } else if !language.is_empty() && language != FALLBACK_LANGUAGE {
+        chain.push(language);
+    }
+    chain.push(FALLBACK_LANGUAGE.to_string());
+    chain
+}
+
+/// Menu label for `key` in `lang`, following `locale_chain` and falling
+/// back to the key itself.
+pub fn get_label(lang: &str, key: &str) -> String {
+    let tables = translations();
+    locale_chain(lang)
+        .iter()
+        .find_map(|l| tables.get(l).and_then(|table| table.get(key)))
+        .cloned()
+        .unwrap_or_else(|| key.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_english_key_is_translated() {
+        let tables = translations();
+        let en = &tables["en"];
+        for (lang, _) in LOCALES {
+            let missing: Vec<_> = en
+                .keys()
+                .filter(|k| !tables[*lang].contains_key(*k))
+                .collect();
+            assert!(missing.is_empty(), "{} is missing {:?}", lang, missing);
+        }
+    }
+
+    #[test]
+    fn test_get_label_fallbacks() {
+        assert_eq!(get_label("zh", "file"), "檔案");
+        assert_eq!(get_label("ja", "edit"), "編集");
+        assert_eq!(get_label("fr", "file"), "File");
+        assert_eq!(get_label("en", "no_such_key"), "no_such_key");
+        // Simplified Chinese falls back to the traditional table until it has its own
+        assert_eq!(get_label("zh-CN", "file"), "檔案");
+    }
+
+    #[test]
+    fn test_locale_chain() {
+        assert_eq!(locale_chain("en"), ["en"]);
+        assert_eq!(locale_chain("en-US"), ["en"]);
+        assert_eq!(locale_chain("zh"), ["zh-Hant", "en"]);
+        assert_eq!(locale_chain("zh-TW"), ["zh-Hant", "en"]);
+        assert_eq!(locale_chain("zh-HK"), ["zh-Hant", "en"]);
+        assert_eq!(locale_chain("zh-CN"), ["zh-Hans", "zh-Hant", "en"]);
+        assert_eq!(locale_chain("zh_CN.UTF-8"), ["zh-Hans", "zh-Hant", "en"]);
+        assert_eq!(locale_chain("zh-Hans-SG"), ["zh-Hans", "zh-Hant", "en"]);
+        assert_eq!(locale_chain("zh-Hant-CN"), ["zh-Hant", "en"]);
+        assert_eq!(locale_chain("ja-JP"), ["ja", "en"]);
+        assert_eq!(locale_chain("fr-CA"), ["fr", "en"]);
+        assert_eq!(locale_chain(""), ["en"]);
+    }
+}
@@ -0,0 +1,461 @@
+use std::collections::HashSet;
+use std::fs;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+use pulldown_cmark::{Event, LinkType, Parser, Tag};
+use serde::Serialize;
+
+use crate::files;
+use crate::markdown::{generate_toc, parser_options};
+
+/// A link or image in a document and whether its target could be found
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct LinkStatus {
+    /// The destination as written in the Markdown
+    pub destination: String,
+    /// 1-based line the link starts on
+    pub line: usize,
+    /// The file the destination resolves to; `None` for same-document anchors
+    pub target: Option<String>,
+    pub exists: bool,
+}
+
+/// Check every relative link and image in `markdown` against the file system,
+/// resolving them against the directory of `doc_path`. Links with a URL scheme
+/// (`https:`, `mailto:`, ...) are skipped; `#anchor` links must match the slug
+/// of a heading in the same document.
+pub fn check_links(doc_path: &str, markdown: &str) -> Vec<LinkStatus> {
+    let base = Path::new(doc_path)
+        .parent()
+        .unwrap_or_else(|| Path::new(""));
+    let slugs: HashSet<String> = generate_toc(markdown)
+        .into_iter()
+        .map(|entry| entry.slug)
+        .collect();
+    let mut statuses = Vec::new();
+
+    for (event, range) in Parser::new_ext(markdown, parser_options()).into_offset_iter() {
+        let destination = match event {
+            Event::Start(Tag::Link { dest_url, .. })
+            | Event::Start(Tag::Image { dest_url, .. }) => dest_url,
+            _ => continue,
+        };
+        if destination.is_empty() || has_url_scheme(&destination) {
+            continue;
+        }
+        let line = markdown[..range.start].matches('\n').count() + 1;
+
+        let status = match link_file(&destination) {
+            None => {
+                let anchor = percent_decode(destination.trim_start_matches('#')).to_lowercase();
+                LinkStatus {
+                    destination: destination.to_string(),
+                    line,
+                    target: None,
+                    exists: slugs.contains(&anchor),
+                }
+            }
+            Some(file) => {
+                let target = resolve_link(base, &file);
+                LinkStatus {
+                    destination: destination.to_string(),
+                    line,
+                    exists: target.exists(),
+                    target: Some(target.to_string_lossy().into_owned()),
+                }
+            }
+        };
+        statuses.push(status);
+    }
+    statuses
+}
+
+/// The percent-decoded file part of a destination, without any `#fragment`
+/// or `?query`; `None` when the link only points at an anchor
+pub fn link_file(destination: &str) -> Option<String> {
+    let end = destination.find(['#', '?']).unwrap_or(destination.len());
+    let file = &destination[..end];
+    (!file.is_empty()).then(|| percent_decode(file))
+}
+
+/// Resolve a link's file part against the linking document's directory
+pub fn resolve_link(base: &Path, file: &str) -> PathBuf {
+    normalize(&base.join(file))
+}
+
+/// Remove `.` and `..` segments without touching the file system
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Rename `old_path` to `new_path`, then point the relative links to it in
+/// the Markdown files under `vault_root` at the new name. When the note moves
+/// to another folder its own relative links are rewritten to still reach their
+/// targets. Returns the files that were rewritten; files that can't be read as
+/// text are skipped.
+pub fn rename_with_link_update(
+    old_path: &Path,
+    new_path: &Path,
+    vault_root: &Path,
+) -> Result<Vec<String>, String> {
+    // Links are compared against resolved paths, so `..` or a symlinked vault
+    // in the arguments doesn't hide them
+    let old_path = files::resolve_path(old_path)?;
+    let new_path = files::resolve_path(new_path)?;
+    let vault_root = files::resolve_path(vault_root)?;
+    files::rename_path(&old_path, &new_path, false)
+        .map_err(|e| format!("Failed to rename file: {}", e))?;
+
+    let mut updated = Vec::new();
+    let new_path_str = new_path.to_string_lossy().into_owned();
+    if files::is_markdown_file(&new_path_str) {
+        if let Ok(markdown) = fs::read_to_string(&new_path) {
+            if let Some(rewritten) = rebase_links(&markdown, &old_path, &new_path) {
+                files::write_atomic(&new_path, rewritten.as_bytes())?;
+                updated.push(new_path_str);
+            }
+        }
+    }
+
+    for doc in files::markdown_files(&vault_root)? {
+        let doc_path = Path::new(&doc);
+        // The moved note's own links were fixed above
+        if doc_path == new_path {
+            continue;
+        }
+        let Ok(markdown) = fs::read_to_string(doc_path) else {
+            continue;
+        };
+        if let Some(rewritten) = retarget_links(doc_path, &markdown, &old_path, &new_path) {
+            files::write_atomic(doc_path, rewritten.as_bytes())?;
+            updated.push(doc);
+        }
+    }
+    Ok(updated)
+}
+
+/// `markdown` with every relative link, image and reference definition that
+/// resolves to `old_path` pointed at `new_path`, keeping any `#fragment`.
+/// `None` when nothing links there.
+pub fn retarget_links(
+    doc_path: &Path,
+    markdown: &str,
+    old_path: &Path,
+    new_path: &Path,
+) -> Option<String> {
+    let base = doc_path.parent().unwrap_or_else(|| Path::new(""));
+    let old_path = normalize(old_path);
+    let new_path = normalize(new_path);
+    rewrite_destinations(markdown, |destination| {
+        let file = link_file(destination)?;
+        (resolve_link(base, &file) == old_path)
+            .then(|| new_destination(destination, base, &new_path))
+    })
+}
+
+/// The relative links of a note moving from `old_doc` to `new_doc`, rewritten
+/// to reach the same files from its new folder; links to the note itself
+/// follow it to its new name. `None` when no link changes.
+pub fn rebase_links(markdown: &str, old_doc: &Path, new_doc: &Path) -> Option<String> {
+    let old_base = old_doc.parent().unwrap_or_else(|| Path::new(""));
+    let new_base = new_doc.parent().unwrap_or_else(|| Path::new(""));
+    let old_doc = normalize(old_doc);
+    let new_doc = normalize(new_doc);
+    rewrite_destinations(markdown, |destination| {
+        let file = link_file(destination)?;
+        let target = resolve_link(old_base, &file);
+        if target == old_doc {
+            Some(new_destination(destination, new_base, &new_doc))
+        } else if old_base != new_base {
+            Some(new_destination(destination, new_base, &target))
+        } else {
+            None
+        }
+    })
+}
+
+/// `markdown` with the destination of each relative inline link, image and
+/// reference definition replaced by what `rewrite` returns for it. `None`
+/// when nothing changes.
+fn rewrite_destinations(
+    markdown: &str,
+    mut rewrite: impl FnMut(&str) -> Option<String>,
+) -> Option<String> {
+    let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+    let mut retarget = |span: Range<usize>, marker: &str, destination: &str| {
+        if has_url_scheme(destination) {
+            return;
+        }
+        let Some(new) = rewrite(destination).filter(|new| new != destination) else {
+            return;
+        };
+        if let Some(range) = destination_range(markdown, span, marker, destination) {
+            edits.push((range, new));
+        }
+    };
+
+    let parser = Parser::new_ext(markdown, parser_options()).into_offset_iter();
+    for (_, definition) in parser.reference_definitions().iter() {
+        retarget(definition.span.clone(), "]:", &definition.dest);
+    }
+    for (event, span) in parser {
+        // Reference-style links were handled through their definitions
+        if let Event::Start(
+            Tag::Link {
+                link_type: LinkType::Inline,
+                dest_url,
+                ..
+            }
+            | Tag::Image {
+                link_type: LinkType::Inline,
+                dest_url,
+                ..
+            },
+        ) = event
+        {
+            retarget(span, "](", &dest_url);
+        }
+    }
+    if edits.is_empty() {
+        return None;
+    }
+
+    edits.sort_by_key(|(range, _)| range.start);
+    edits.dedup_by_key(|(range, _)| range.start);
+    let mut rewritten = markdown.to_string();
+    for (range, destination) in edits.into_iter().rev() {
+        rewritten.replace_range(range, &destination);
+    }
+    Some(rewritten)
+}
+
+/// Where `destination` is written in the source after the last `marker`
+/// within `span`. `None` when it's written differently, e.g. with escapes.
+fn destination_range(
+    markdown: &str,
+    span: Range<usize>,
+    marker: &str,
+    destination: &str,
+) -> Option<Range<usize>> {
+    let source = markdown.get(span.clone())?;
+    let after_marker = source.rfind(marker)? + marker.len();
+    let start = span.start + after_marker + source[after_marker..].find(destination)?;
+    Some(start..start + destination.len())
+}
+
+/// `destination` rewritten to reach `new_path` from `base`, keeping its
+/// `#fragment` or `?query` and a leading `./`
+fn new_destination(destination: &str, base: &Path, new_path: &Path) -> String {
+    let suffix = destination
+        .find(['#', '?'])
+        .map_or("", |i| &destination[i..]);
+    let mut path = relative_path(base, new_path).replace(' ', "%20");
+    if destination.starts_with("./") && !path.starts_with("../") {
+        path.insert_str(0, "./");
+    }
+    path + suffix
+}
+
+/// `target` relative to the directory `from`, with `/` separators
+fn relative_path(from: &Path, target: &Path) -> String {
+    let from = normalize(from);
+    let from: Vec<Component> = from.components().collect();
+    let target: Vec<Component> = target.components().collect();
+    let common = from.iter().zip(&target).take_while(|(a, b)| a == b).count();
+
+    let mut parts: Vec<String> = vec!["..".to_string(); from.len() - common];
+    parts.extend(
+        target[common..]
+            .iter()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+    );
+    parts.join("/")
+}
+
+/// Whether the destination starts with a URL scheme such as `https:` or
+/// `mailto:`. A single letter is a Windows drive, not a scheme.
+fn has_url_scheme(destination: &str) -> bool {
+    match destination.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.len() > 1
+                && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// Decode `%XX` escapes such as the `%20` editors write for spaces in file names
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escape = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_check_links_reports_missing_relative_file() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/other note.md"), "# Other\n").unwrap();
+        let doc = dir.join("index.md");
+        let md = "[ok](./sub/other%20note.md#other)\n\n[gone](renamed.md)\n\n[web](https://example.com) [mail](mailto:a@b.c)\n";
+
+        let statuses = check_links(doc.to_str().unwrap(), md);
+
+        assert_eq!(statuses.len(), 2);
+        assert!(statuses[0].exists);
+        assert_eq!(
+            statuses[0].target.as_deref(),
+            Some(dir.join("sub/other note.md").to_str().unwrap())
+        );
+        assert_eq!(statuses[1].destination, "renamed.md");
+        assert_eq!(statuses[1].line, 3);
+        assert!(!statuses[1].exists);
+    }
+
+    #[test]
+    fn test_rename_with_link_update_rewrites_links_to_renamed_note() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("notes")).unwrap();
+        fs::write(dir.join("notes/target.md"), "# Target\n").unwrap();
+        fs::write(
+            dir.join("index.md"),
+            "See [target](./notes/target.md#target), ![chart](notes/target.md) and [ref][t].\n\n\
+             [other](notes/unrelated.md) [web](https://example.com/notes/target.md)\n\n\
+             [t]: notes/target.md\n",
+        )
+        .unwrap();
+
+        fs::create_dir_all(dir.join("archive")).unwrap();
+        let updated = rename_with_link_update(
+            &dir.join("notes/target.md"),
+            &dir.join("archive/new note.md"),
+            dir,
+        )
+        .unwrap();
+        let index = fs::read_to_string(dir.join("index.md")).unwrap();
+
+        // Paths come back resolved, e.g. with /var -> /private/var on macOS
+        assert_eq!(
+            updated,
+            [files::resolve_path(&dir.join("index.md"))
+                .unwrap()
+                .to_string_lossy()]
+        );
+        assert_eq!(
+            index,
+            "See [target](./archive/new%20note.md#target), ![chart](archive/new%20note.md) and [ref][t].\n\n\
+             [other](notes/unrelated.md) [web](https://example.com/notes/target.md)\n\n\
+             [t]: archive/new%20note.md\n"
+        );
+    }
+
+    #[test]
+    fn test_rename_with_link_update_rebases_links_in_moved_note() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        fs::create_dir_all(dir.join("notes")).unwrap();
+        fs::create_dir_all(dir.join("archive/2024")).unwrap();
+        fs::write(dir.join("notes/sibling.md"), "# Sibling\n").unwrap();
+        fs::write(
+            dir.join("notes/moving.md"),
+            "[sib](sibling.md#sibling) [self](moving.md#top) [top](#top) [web](https://example.com/x.md)\n",
+        )
+        .unwrap();
+
+        // A `..` in the arguments still matches the links
+        let old_path = dir.join("notes/../notes/moving.md");
+        let updated =
+            rename_with_link_update(&old_path, &dir.join("archive/2024/moved.md"), dir).unwrap();
+        let moved = fs::read_to_string(dir.join("archive/2024/moved.md")).unwrap();
+
+        assert_eq!(updated.len(), 1);
+        assert_eq!(
+            moved,
+            "[sib](../../notes/sibling.md#sibling) [self](moved.md#top) [top](#top) [web](https://example.com/x.md)\n"
+        );
+    }
+
+    #[test]
+    fn test_rebase_links_within_same_folder_only_renames_self_links() {
+        let md = "[a](./a.md) [me](old.md#x)\n";
+        let rebased = rebase_links(md, Path::new("/v/old.md"), Path::new("/v/new.md"));
+        assert_eq!(rebased.as_deref(), Some("[a](./a.md) [me](new.md#x)\n"));
+        assert_eq!(
+            rebase_links(
+                "[a](a.md)\n",
+                Path::new("/v/old.md"),
+                Path::new("/v/new.md")
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_retarget_links_climbs_out_of_subfolders() {
+        let md = "[up](../b.md)\n";
+        let rewritten = retarget_links(
+            Path::new("/v/sub/a.md"),
+            md,
+            Path::new("/v/b.md"),
+            Path::new("/v/other/c.md"),
+        );
+        assert_eq!(rewritten.as_deref(), Some("[up](../other/c.md)\n"));
+        assert_eq!(
+            retarget_links(
+                Path::new("/v/sub/a.md"),
+                md,
+                Path::new("/v/x.md"),
+                Path::new("/v/y.md")
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_check_links_matches_anchors_to_heading_slugs() {
+        let md = "# Getting Started\n\nSee [setup](#getting-started) and [missing](#nowhere).\n";
+        let statuses = check_links("/notes/doc.md", md);
+        assert_eq!(statuses.len(), 2);
+        assert!(statuses[0].exists);
+        assert_eq!(statuses[0].target, None);
+        assert!(!statuses[1].exists);
+    }
+}
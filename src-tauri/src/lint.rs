@@ -0,0 +1,265 @@
+use serde::Serialize;
+
+/// A style problem that `format_markdown` would fix. `line` and `column` are
+/// 1-based positions in the original document.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct LintIssue {
+    pub line: usize,
+    pub column: usize,
+    pub rule: &'static str,
+    pub message: String,
+}
+
+/// Normalize the document's style: trailing whitespace removed (two-space hard
+/// breaks are kept), a single space after ATX heading markers, `-` for bullets,
+/// one blank line around headings and between blocks, and a final newline.
+/// Fenced code blocks are left untouched.
+pub fn format_markdown(markdown: &str) -> String {
+    clean_up(markdown).0
+}
+
+/// Report what `format_markdown` would change, in document order.
+pub fn lint_markdown(markdown: &str) -> Vec<LintIssue> {
+    clean_up(markdown).1
+}
+
+fn clean_up(markdown: &str) -> (String, Vec<LintIssue>) {
+    let mut issues = Vec::new();
+    // Output lines with the source line they came from
+    let mut out: Vec<(String, usize)> = Vec::new();
+    let mut fence: Option<(char, usize)> = None;
+    let mut after_heading = false;
+
+    for (index, raw) in markdown.lines().enumerate() {
+        let line_no = index + 1;
+
+        if let Some((marker, len)) = fence {
+            if is_closing_fence(raw, marker, len) {
+                fence = None;
+            }
+            out.push((raw.to_string(), line_no));
+            continue;
+        }
+
+        let mut line = raw.to_string();
+        let trimmed = raw.trim_end();
+        let trailing = &raw[trimmed.len()..];
+        // Two trailing spaces after text are a hard line break
+        if !trailing.is_empty() && (trailing != "  " || trimmed.is_empty()) {
+            issues.push(issue(
+                line_no,
+                trimmed.chars().count() + 1,
+                "trailing-whitespace",
+                "Trailing whitespace",
+            ));
+            line = trimmed.to_string();
+        }
+
+        if line.is_empty() {
+            if out.last().is_none_or(|(last, _)| last.is_empty()) {
+                issues.push(issue(
+                    line_no,
+                    1,
+                    "blank-lines",
+                    "Multiple consecutive blank lines",
+                ));
+            } else {
+                out.push((line, line_no));
+            }
+            after_heading = false;
+            continue;
+        }
+
+        if after_heading {
+            issues.push(issue(
+                line_no,
+                1,
+                "blank-around-heading",
+                "Heading should be followed by a blank line",
+            ));
+            out.push((String::new(), line_no));
+        }
+        after_heading = false;
+
+        if let Some(opening) = opening_fence(&line) {
+            fence = Some(opening);
+        } else if let Some(fixed) = fix_heading(&line, line_no, &mut issues) {
+            if out.last().is_some_and(|(last, _)| !last.is_empty()) {
+                issues.push(issue(
+                    line_no,
+                    1,
+                    "blank-around-heading",
+                    "Heading should be preceded by a blank line",
+                ));
+                out.push((String::new(), line_no));
+            }
+            line = fixed;
+            after_heading = true;
+        } else if let Some(fixed) = fix_bullet(&line, line_no, &mut issues) {
+            line = fixed;
+        }
+        out.push((line, line_no));
+    }
+
+    while let Some((last, line_no)) = out.last() {
+        if !last.is_empty() {
+            break;
+        }
+        issues.push(issue(
+            *line_no,
+            1,
+            "blank-lines",
+            "Blank line at end of document",
+        ));
+        out.pop();
+    }
+    if !markdown.is_empty() && !markdown.ends_with('\n') && !out.is_empty() {
+        let last = markdown.lines().last().unwrap_or("");
+        let line_no = markdown.lines().count();
+        issues.push(issue(
+            line_no,
+            last.chars().count() + 1,
+            "final-newline",
+            "Document should end with a newline",
+        ));
+    }
+
+    issues.sort_by_key(|i| (i.line, i.column));
+    let mut formatted: String = out.into_iter().map(|(line, _)| line + "\n").collect();
+    if formatted.trim().is_empty() {
+        formatted.clear();
+    }
+    (formatted, issues)
+}
+
+fn issue(line: usize, column: usize, rule: &'static str, message: &str) -> LintIssue {
+    LintIssue {
+        line,
+        column,
+        rule,
+        message: message.to_string(),
+    }
+}
+
+/// An ATX heading with more than one space or a tab after the `#`s, rewritten.
+/// Returns the line (fixed or not) when it is a heading, `None` otherwise.
+fn fix_heading(line: &str, line_no: usize, issues: &mut Vec<LintIssue>) -> Option<String> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let text = &line[level..];
+    if !(1..=6).contains(&level) || text.trim().is_empty() {
+        return None;
+    }
+    // Without a space `#tag` is a tag or plain text, not a heading
+    if !text.starts_with([' ', '\t']) {
+        return None;
+    }
+    let content = text.trim_start();
+    if text.len() - content.len() != 1 || !text.starts_with(' ') {
+        issues.push(issue(
+            line_no,
+            level + 1,
+            "heading-spacing",
+            "Use a single space after the heading marker",
+        ));
+    }
+    Some(format!("{} {}", &line[..level], content))
+}
+
+/// A `*` or `+` bullet rewritten to `-`
+fn fix_bullet(line: &str, line_no: usize, issues: &mut Vec<LintIssue>) -> Option<String> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let rest = &line[indent..];
+    let mut chars = rest.chars();
+    let marker = chars.next()?;
+    if !matches!(marker, '*' | '+') || !matches!(chars.next(), Some(' ' | '\t')) {
+        return None;
+    }
+    // `* * *` is a horizontal rule, not a list
+    if marker == '*' && rest.chars().all(|c| c == '*' || c == ' ') && rest.matches('*').count() >= 3
+    {
+        return None;
+    }
+    issues.push(issue(
+        line_no,
+        indent + 1,
+        "bullet-marker",
+        "Use '-' for bullet lists",
+    ));
+    Some(format!("{}-{}", &line[..indent], &rest[1..]))
+}
+
+/// Marker character and length of a ``` or ~~~ fence opening on this line
+fn opening_fence(line: &str) -> Option<(char, usize)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let marker = trimmed.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let len = trimmed.chars().take_while(|&c| c == marker).count();
+    (len >= 3).then_some((marker, len))
+}
+
+fn is_closing_fence(line: &str, marker: char, len: usize) -> bool {
+    let trimmed = line.trim();
+    trimmed.len() >= len && trimmed.chars().all(|c| c == marker)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heading_spacing() {
+        let md = "#\tTitle\nText\n##  Section\n\n\n\nMore\n";
+        assert_eq!(
+            format_markdown(md),
+            "# Title\n\nText\n\n## Section\n\nMore\n"
+        );
+
+        let rules: Vec<_> = lint_markdown(md).iter().map(|i| (i.line, i.rule)).collect();
+        assert_eq!(
+            rules,
+            vec![
+                (1, "heading-spacing"),
+                (2, "blank-around-heading"),
+                (3, "blank-around-heading"),
+                (3, "heading-spacing"),
+                (5, "blank-lines"),
+                (6, "blank-lines"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hash_without_space_is_not_a_heading() {
+        let md = "Notes\n#todo #work\n##\n";
+        assert_eq!(format_markdown(md), md);
+        assert!(lint_markdown(md).is_empty());
+    }
+
+    #[test]
+    fn test_bullet_normalization() {
+        let md = "* one\n+ two\n  * nested\n\n* * *\n\n**bold** text\n";
+        assert_eq!(
+            format_markdown(md),
+            "- one\n- two\n  - nested\n\n* * *\n\n**bold** text\n"
+        );
+        let issues = lint_markdown(md);
+        assert_eq!(issues.len(), 3);
+        assert_eq!(
+            (issues[2].line, issues[2].column, issues[2].rule),
+            (3, 3, "bullet-marker")
+        );
+    }
+
+    #[test]
+    fn test_code_blocks_are_kept_verbatim() {
+        let md = "Text   \nhard break  \n```\n#not a heading  \n* item\n\n\n```\nend";
+        assert_eq!(
+            format_markdown(md),
+            "Text\nhard break  \n```\n#not a heading  \n* item\n\n\n```\nend\n"
+        );
+        let rules: Vec<_> = lint_markdown(md).iter().map(|i| i.rule).collect();
+        assert_eq!(rules, vec!["trailing-whitespace", "final-newline"]);
+    }
+}
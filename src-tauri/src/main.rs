@@ -2,30 +2,117 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod convert;
+mod diff;
+mod error;
+mod files;
+mod i18n;
+mod links;
+mod lint;
+mod markdown;
+mod open_files;
+mod shortcuts;
+mod themes;
+mod watch;
 
-use std::collections::VecDeque;
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
-use std::path::PathBuf;
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
-use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, MenuItemKind, PredefinedMenuItem, Submenu};
 use tauri::{AppHandle, Emitter, Manager, State};
-use walkdir::WalkDir;
+use themes::{CustomTheme, BUILTIN_THEMES, DEFAULT_THEME};
 use regex::RegexBuilder;
 
+const DEFAULT_MAX_RECENT_FILES: usize = 10;
+const MAX_RECENT_FOLDERS: usize = 10;
+const DEFAULT_AUTOSAVE_DELAY_MS: u64 = 2000;
+
+// Format menu items shown as checkmarks reflecting the formatting at the cursor
+const FORMAT_CHECK_ITEMS: &[&str] = &[
+    "editor_bold",
+    "editor_italic",
+    "editor_strike",
+    "editor_inline_code",
+    "editor_bullet_list",
+    "editor_ordered_list",
+    "editor_blockquote",
+];
+
 // State management
 struct AppState {
     recent_files: Mutex<VecDeque<String>>,
-    pending_open_files: Mutex<VecDeque<String>>,
+    max_recent: Mutex<usize>,
+    // Folders opened in the sidebar, most recent first; persisted in the settings file
+    recent_folders: Mutex<VecDeque<String>>,
+    // Files the OS asked to open, held back until the frontend is listening
+    open_queue: Mutex<OpenQueue>,
     language: Mutex<String>,
+    // Line ending detected when each file was read, re-applied on save
+    line_endings: Mutex<HashMap<String, LineEnding>>,
+    // Encoding of files that were not valid UTF-8 when read, keyed by path
+    encodings: Mutex<HashMap<String, &'static encoding_rs::Encoding>>,
+    // Watchers for open documents, keyed by path
+    watchers: Mutex<HashMap<String, watch::FileWatcher>>,
+    // Menu keyboard shortcuts, defaults plus the user's overrides
+    shortcuts: Mutex<Shortcuts>,
+    // Checked state of the Format menu toggles, kept so menu rebuilds preserve it
+    format_states: Mutex<HashMap<String, bool>>,
+    // Folders file commands are confined to; empty means no restriction
+    allowed_roots: Mutex<Vec<PathBuf>>,
+    // Autosaves waiting out the debounce delay, keyed by path
+    autosave_pending: Mutex<HashMap<String, PendingAutosave>>,
+    autosave_delay: Mutex<Duration>,
+    // Labels of windows with unsaved changes; closing them asks the frontend first
+    dirty_windows: Mutex<HashSet<String>>,
+    // Active theme id, checked in the View > Theme menu
+    theme: Mutex<String>,
+    // Themes registered by the user, listed after the built-in ones
+    custom_themes: Mutex<Vec<CustomTheme>>,
+    // View > Read Only: saving is refused while set; not persisted across launches
+    read_only: Mutex<bool>,
+    // Cancel flags of the running PDF and spreadsheet imports, keyed by the
+    // id the frontend passed in; set by `cancel_import`
+    import_cancels: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+// Latest autosaved content of a document and when it was edited
+struct PendingAutosave {
+    content: String,
+    edited_at: Instant,
 }
 
 impl AppState {
-    fn new(language: String) -> Self {
+    fn new(
+        language: String,
+        shortcuts: Shortcuts,
+        theme: String,
+        custom_themes: Vec<CustomTheme>,
+        recent_folders: VecDeque<String>,
+    ) -> Self {
         AppState {
             recent_files: Mutex::new(VecDeque::new()),
-            pending_open_files: Mutex::new(VecDeque::new()),
+            max_recent: Mutex::new(DEFAULT_MAX_RECENT_FILES),
+            recent_folders: Mutex::new(recent_folders),
+            open_queue: Mutex::new(OpenQueue::default()),
             language: Mutex::new(language),
+            line_endings: Mutex::new(HashMap::new()),
+            encodings: Mutex::new(HashMap::new()),
+            watchers: Mutex::new(HashMap::new()),
+            shortcuts: Mutex::new(shortcuts),
+            format_states: Mutex::new(HashMap::new()),
+            allowed_roots: Mutex::new(Vec::new()),
+            autosave_pending: Mutex::new(HashMap::new()),
+            autosave_delay: Mutex::new(Duration::from_millis(DEFAULT_AUTOSAVE_DELAY_MS)),
+            dirty_windows: Mutex::new(HashSet::new()),
+            theme: Mutex::new(theme),
+            custom_themes: Mutex::new(custom_themes),
+            read_only: Mutex::new(false),
+            import_cancels: Mutex::new(HashMap::new()),
         }
     }
 }
@@ -34,6 +121,24 @@ impl AppState {
 #[derive(Serialize, Deserialize, Clone, Debug)]
 struct UserSettings {
     language: String,
+    // None until the user picks a theme from the menu
+    #[serde(default)]
+    theme: Option<String>,
+    #[serde(default)]
+    custom_themes: Vec<CustomTheme>,
+    #[serde(default)]
+    recent_folders: Vec<String>,
+}
+
+impl Default for UserSettings {
+    fn default() -> Self {
+        UserSettings {
+            language: "en".to_string(),
+            theme: None,
+            custom_themes: Vec::new(),
+            recent_folders: Vec::new(),
+        }
+    }
 }
 
 impl UserSettings {
@@ -111,220 +216,423 @@ impl UserSettings {
     }
 }
 
-fn get_label(lang: &str, key: &str) -> String {
-    match lang {
-        "zh" => match key {
-            "file" => "檔案".to_string(),
-            "file_new" => "新檔案".to_string(),
-            "file_open" => "開啟...".to_string(),
-            "file_save" => "儲存".to_string(),
-            "file_save_as" => "另存新檔...".to_string(),
-            "file_close_document" => "關閉文件".to_string(),
-            "format" => "格式".to_string(),
-            "format_text" => "文字".to_string(),
-            "format_bold" => "粗體".to_string(),
-            "format_italic" => "斜體".to_string(),
-            "format_strike" => "刪除線".to_string(),
-            "format_inline_code" => "行內程式碼".to_string(),
-            "format_headings" => "標題".to_string(),
-            "format_paragraph" => "本文".to_string(),
-            "format_heading_1" => "標題 1".to_string(),
-            "format_heading_2" => "標題 2".to_string(),
-            "format_heading_3" => "標題 3".to_string(),
-            "format_heading_4" => "標題 4".to_string(),
-            "format_heading_5" => "標題 5".to_string(),
-            "format_heading_6" => "標題 6".to_string(),
-            "format_lists" => "清單".to_string(),
-            "format_bullet_list" => "項目符號清單".to_string(),
-            "format_ordered_list" => "編號清單".to_string(),
-            "format_blocks" => "區塊".to_string(),
-            "format_blockquote" => "引用".to_string(),
-            "format_code_block" => "程式碼區塊".to_string(),
-            "format_horizontal_rule" => "水平分割線".to_string(),
-            "view" => "檢視".to_string(),
-            "view_source_code" => "原始碼".to_string(),
-            "view_theme" => "佈景主題".to_string(),
-            "view_language" => "語言".to_string(),
-            "edit" => "編輯".to_string(),
-            "edit_undo" => "復原".to_string(),
-            "edit_redo" => "重做".to_string(),
-            "edit_cut" => "剪下".to_string(),
-            "edit_copy" => "複製".to_string(),
-            "edit_paste" => "貼上".to_string(),
-            "edit_select_all" => "全選".to_string(),
-            "edit_find" => "尋找".to_string(),
-            "edit_find_in_files" => "在檔案中尋找".to_string(),
-            "window" => "視窗".to_string(),
-            "help" => "說明".to_string(),
-            "lang_en" => "English".to_string(),
-            "lang_zh" => "繁體中文".to_string(),
-            "file_import" => "匯入".to_string(),
-            "file_import_docx" => "從 Word (.docx)".to_string(),
-            "file_import_xlsx" => "從試算表 (.xlsx)".to_string(),
-            "file_import_pdf"  => "從 PDF".to_string(),
-            "file_import_pptx" => "從 PowerPoint (.pptx)".to_string(),
-            "file_export" => "匯出".to_string(),
-            "file_export_docx" => "匯出為 Word (.docx)...".to_string(),
-            "file_export_xlsx" => "匯出為試算表 (.xlsx)...".to_string(),
-            "file_export_pdf"  => "匯出為 PDF...".to_string(),
-            "file_export_pptx" => "匯出為 PowerPoint (.pptx)...".to_string(),
-            "app_about" => "關於 MarkBear".to_string(),
-            "app_services" => "服務".to_string(),
-            "app_hide" => "隱藏 MarkBear".to_string(),
-            "app_hide_others" => "隱藏其他".to_string(),
-            "app_show_all" => "全部顯示".to_string(),
-            "app_quit" => "結束 MarkBear".to_string(),
-            "window_minimize" => "縮到最小".to_string(),
-            "window_zoom" => "縮放".to_string(),
-            "window_fullscreen" => "切換全螢幕".to_string(),
-            "window_close" => "關閉視窗".to_string(),
-            _ => key.to_string(),
-        },
-        _ => match key {
-            "file" => "File".to_string(),
-            "file_new" => "New File".to_string(),
-            "file_open" => "Open...".to_string(),
-            "file_save" => "Save".to_string(),
-            "file_save_as" => "Save As...".to_string(),
-            "file_close_document" => "Close Document".to_string(),
-            "format" => "Format".to_string(),
-            "format_text" => "Text".to_string(),
-            "format_bold" => "Bold".to_string(),
-            "format_italic" => "Italic".to_string(),
-            "format_strike" => "Strikethrough".to_string(),
-            "format_inline_code" => "Inline Code".to_string(),
-            "format_headings" => "Headings".to_string(),
-            "format_paragraph" => "Paragraph".to_string(),
-            "format_heading_1" => "Heading 1".to_string(),
-            "format_heading_2" => "Heading 2".to_string(),
-            "format_heading_3" => "Heading 3".to_string(),
-            "format_heading_4" => "Heading 4".to_string(),
-            "format_heading_5" => "Heading 5".to_string(),
-            "format_heading_6" => "Heading 6".to_string(),
-            "format_lists" => "Lists".to_string(),
-            "format_bullet_list" => "Bullet List".to_string(),
-            "format_ordered_list" => "Ordered List".to_string(),
-            "format_blocks" => "Blocks".to_string(),
-            "format_blockquote" => "Blockquote".to_string(),
-            "format_code_block" => "Code Block".to_string(),
-            "format_horizontal_rule" => "Horizontal Rule".to_string(),
-            "view" => "View".to_string(),
-            "view_source_code" => "Source Code".to_string(),
-            "view_theme" => "Theme".to_string(),
-            "view_language" => "Language".to_string(),
-            "edit" => "Edit".to_string(),
-            "edit_undo" => "Undo".to_string(),
-            "edit_redo" => "Redo".to_string(),
-            "edit_cut" => "Cut".to_string(),
-            "edit_copy" => "Copy".to_string(),
-            "edit_paste" => "Paste".to_string(),
-            "edit_select_all" => "Select All".to_string(),
-            "edit_find" => "Find...".to_string(),
-            "edit_find_in_files" => "Find in Files".to_string(),
-            "window" => "Window".to_string(),
-            "help" => "Help".to_string(),
-            "lang_en" => "English".to_string(),
-            "lang_zh" => "繁體中文".to_string(),
-            "file_import" => "Import".to_string(),
-            "file_import_docx" => "From Word (.docx)".to_string(),
-            "file_import_xlsx" => "From Spreadsheet (.xlsx)".to_string(),
-            "file_import_pdf"  => "From PDF".to_string(),
-            "file_import_pptx" => "From PowerPoint (.pptx)".to_string(),
-            "file_export" => "Export".to_string(),
-            "file_export_docx" => "Export as Word (.docx)...".to_string(),
-            "file_export_xlsx" => "Export as Spreadsheet (.xlsx)...".to_string(),
-            "file_export_pdf"  => "Export as PDF...".to_string(),
-            "file_export_pptx" => "Export as PowerPoint (.pptx)...".to_string(),
-            "app_about" => "About MarkBear".to_string(),
-            "app_services" => "Services".to_string(),
-            "app_hide" => "Hide MarkBear".to_string(),
-            "app_hide_others" => "Hide Others".to_string(),
-            "app_show_all" => "Show All".to_string(),
-            "app_quit" => "Quit MarkBear".to_string(),
-            "window_minimize" => "Minimize".to_string(),
-            "window_zoom" => "Zoom".to_string(),
-            "window_fullscreen" => "Toggle Full Screen".to_string(),
-            "window_close" => "Close Window".to_string(),
-            _ => key.to_string(),
-        },
+/**
+ * Reject paths outside the allowed roots, if any have been set with set_allowed_roots
+ * Paths are canonicalized first, so `..` and symlinks can't be used to escape
+ */
+fn check_path(state: &AppState, path: &str) -> Result<(), AppError> {
+    let roots = state
+        .allowed_roots
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())?;
+    files::check_allowed_path(Path::new(path), &roots)
+        .map(|_| ())
+        .map_err(AppError::PermissionDenied)
+}
+
+// Refuse to write while the document is locked with View > Read Only
+fn check_writable(state: &AppState) -> Result<(), AppError> {
+    let read_only = *state
+        .read_only
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())?;
+    if read_only {
+        return Err(AppError::ReadOnly(
+            "Read-only mode is on; turn off View > Read Only to save".to_string(),
+        ));
     }
+    Ok(())
+}
+
+// Whether View > Read Only is on
+#[tauri::command]
+fn get_read_only(state: State<AppState>) -> Result<bool, AppError> {
+    let read_only = state
+        .read_only
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())?;
+    Ok(*read_only)
+}
+
+// Turn read-only mode on or off and update the menu checkmark
+#[tauri::command]
+fn set_read_only(app: AppHandle, read_only: bool, state: State<AppState>) -> Result<(), AppError> {
+    *state
+        .read_only
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())? = read_only;
+    update_menu_item_state(app, "view_read_only".to_string(), read_only)
+}
+
+// Restrict file commands to the given folders (e.g. the vaults the user opened); an empty list lifts the restriction
+#[tauri::command]
+fn set_allowed_roots(roots: Vec<String>, state: State<AppState>) -> Result<(), AppError> {
+    let resolved = roots
+        .iter()
+        .map(|root| {
+            Path::new(root)
+                .canonicalize()
+                .map_err(|e| AppError::io(&format!("Invalid folder '{}'", root), e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    *state
+        .allowed_roots
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())? = resolved;
+    Ok(())
+}
+
+// Forget the line ending and encoding remembered for a path, so a different
+// file later saved there doesn't inherit them
+fn forget_file_format(state: &AppState, path: &str) {
+    if let Ok(mut endings) = state.line_endings.lock() {
+        endings.remove(path);
+    }
+    if let Ok(mut encodings) = state.encodings.lock() {
+        encodings.remove(path);
+    }
+}
+
+// Contents of a file along with the encoding it was decoded from
+#[derive(Serialize, Clone)]
+struct DecodedFile {
+    content: String,
+    encoding: String,
+}
+
+// Read a markdown file; pass the returned encoding back when saving to keep it
+#[tauri::command]
+async fn read_markdown_file(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<DecodedFile, AppError> {
+    check_path(&state, &path)?;
+    let bytes = fs::read(&path).map_err(|e| AppError::io("Failed to read file", e))?;
+
+    // Fall back to a best-effort decode for non-UTF-8 files and remember the encoding
+    let (content, encoding) = files::decode_text(bytes);
+    if let Ok(mut encodings) = state.encodings.lock() {
+        if encoding == encoding_rs::UTF_8 {
+            encodings.remove(&path);
+        } else {
+            println!("⚠️ {} is not UTF-8; decoded as {}", path, encoding.name());
+            encodings.insert(path.clone(), encoding);
+        }
+    }
+
+    // Remember the file's line endings so an editor normalizing to \n doesn't rewrite them
+    if let Ok(mut endings) = state.line_endings.lock() {
+        match files::detect_line_ending(&content) {
+            Some(ending) => endings.insert(path, ending),
+            None => endings.remove(&path),
+        };
+    }
+
+    Ok(DecodedFile {
+        content,
+        encoding: encoding.name().to_string(),
+    })
 }
 
-// File entry for directory listing
-#[derive(Serialize, Deserialize, Clone)]
-struct FileEntry {
-    name: String,
+// Size, timestamps and type of a path without reading it, so the UI can warn before opening huge files
+#[tauri::command]
+fn get_file_info(path: String, state: State<AppState>) -> Result<files::FileInfo, AppError> {
+    check_path(&state, &path)?;
+    files::file_info(Path::new(&path)).map_err(|e| AppError::io("Failed to read file info", e))
+}
+
+// Read a bounded window of a file too large to load whole, for paging through it lazily.
+// The window is aligned to character boundaries; encodings and line endings aren't tracked.
+#[tauri::command]
+fn read_markdown_file_range(
     path: String,
-    is_directory: bool,
+    byte_offset: u64,
+    max_bytes: usize,
+    state: State<AppState>,
+) -> Result<files::FileChunk, AppError> {
+    check_path(&state, &path)?;
+    files::read_range(Path::new(&path), byte_offset, max_bytes)
+        .map_err(|e| AppError::io("Failed to read file", e))
+}
+
+// Get the encoding a file was decoded with when read ("UTF-8" unless detection kicked in)
+#[tauri::command]
+fn get_file_encoding(path: String, state: State<AppState>) -> Result<String, AppError> {
+    let encodings = state
+        .encodings
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())?;
+    Ok(encodings
+        .get(&path)
+        .copied()
+        .unwrap_or(encoding_rs::UTF_8)
+        .name()
+        .to_string())
 }
 
-// Read a markdown file
+// Get the line ending detected when a file was read ("lf", "crlf", or null)
 #[tauri::command]
-async fn read_markdown_file(path: String) -> Result<String, String> {
-    fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read file: {}", e))
+fn get_line_ending(path: String, state: State<AppState>) -> Result<Option<String>, AppError> {
+    let endings = state
+        .line_endings
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())?;
+    Ok(endings.get(&path).map(|e| e.as_str().to_string()))
 }
 
-// Save a markdown file
+// Save a markdown file (atomically via temp file + rename)
 #[tauri::command]
-async fn save_markdown_file(path: String, content: String) -> Result<(), String> {
+async fn save_markdown_file(
+    path: String,
+    content: String,
+    line_ending: Option<String>,
+    encoding: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    check_path(&state, &path)?;
+    check_writable(&state)?;
+
     // Create parent directory if it doesn't exist
     if let Some(parent) = PathBuf::from(&path).parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create directory: {}", e))?;
+        fs::create_dir_all(parent).map_err(|e| AppError::io("Failed to create directory", e))?;
     }
-    
-    fs::write(&path, content)
-        .map_err(|e| format!("Failed to write file: {}", e))
+
+    // An explicit style wins; otherwise re-apply the style detected on read
+    let style = match line_ending {
+        Some(style) => style,
+        None => state
+            .line_endings
+            .lock()
+            .map_err(|_| "Failed to lock state".to_string())?
+            .get(&path)
+            .map(|e| e.as_str().to_string())
+            .unwrap_or_else(|| "preserve".to_string()),
+    };
+    let content = files::normalize_line_endings(&content, &style)?;
+
+    // An explicit encoding (e.g. "utf-8" to convert) wins; otherwise keep the original
+    let encoding = match encoding {
+        Some(label) => label,
+        None => state
+            .encodings
+            .lock()
+            .map_err(|_| "Failed to lock state".to_string())?
+            .get(&path)
+            .map(|e| e.name().to_string())
+            .unwrap_or_else(|| "utf-8".to_string()),
+    };
+    let bytes = files::encode_text(&content, &encoding)?;
+
+    // Tell the watcher about our own write so it isn't reported as an external change
+    if let Some(watcher) = state
+        .watchers
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())?
+        .get(&path)
+    {
+        watcher.mark_synced(&bytes);
+    }
+
+    files::write_atomic(Path::new(&path), &bytes)?;
+
+    // Once saved as UTF-8 the file no longer needs special handling
+    if encoding_rs::Encoding::for_label(encoding.as_bytes()) == Some(encoding_rs::UTF_8) {
+        if let Ok(mut encodings) = state.encodings.lock() {
+            encodings.remove(&path);
+        }
+    }
+    Ok(())
 }
 
-// List directory contents
+// Save a markdown file, first backing up the version currently on disk
 #[tauri::command]
-async fn list_directory(path: String) -> Result<Vec<FileEntry>, String> {
-    let entries = fs::read_dir(&path)
-        .map_err(|e| format!("Failed to read directory: {}", e))?;
-    
-    let mut file_entries = Vec::new();
-    
-    for entry in entries {
-        match entry {
-            Ok(entry) => {
-                let path = entry.path();
-                let name = entry.file_name().to_string_lossy().to_string();
-                
-                // Skip hidden files
-                if name.starts_with('.') {
-                    continue;
-                }
-                
-                let is_directory = path.is_dir();
-                let path_str = path.to_string_lossy().to_string();
-                
-                file_entries.push(FileEntry {
-                    name,
-                    path: path_str,
-                    is_directory,
-                });
+async fn save_markdown_file_with_backup(
+    path: String,
+    content: String,
+    line_ending: Option<String>,
+    encoding: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    check_path(&state, &path)?;
+    check_writable(&state)?;
+    files::create_backup(Path::new(&path), files::MAX_BACKUPS_PER_FILE)?;
+    save_markdown_file(path, content, line_ending, encoding, state).await
+}
+
+/**
+ * Autosave a document once edits pause for the autosave delay
+ * Rapid calls for the same path coalesce into one write of the latest content;
+ * `autosaved` is emitted with the path after each flush
+ */
+#[tauri::command]
+fn autosave(
+    app: AppHandle,
+    path: String,
+    content: String,
+    state: State<AppState>,
+) -> Result<(), AppError> {
+    check_path(&state, &path)?;
+    let delay = *state
+        .autosave_delay
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())?;
+    let edited_at = Instant::now();
+    state
+        .autosave_pending
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())?
+        .insert(path.clone(), PendingAutosave { content, edited_at });
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(delay).await;
+        // A later edit restarted the timer; its own task will flush
+        let is_latest = app
+            .state::<AppState>()
+            .autosave_pending
+            .lock()
+            .map(|pending| pending.get(&path).is_some_and(|p| p.edited_at == edited_at))
+            .unwrap_or(false);
+        if is_latest {
+            if let Err(e) = flush_autosave(&app, &path).await {
+                println!("❌ Autosave failed for {}: {}", path, e);
             }
-            Err(_) => continue,
+        }
+    });
+    Ok(())
+}
+
+// Write pending autosaves right away: one document, or all of them when `path` is omitted
+#[tauri::command]
+async fn flush_autosaves(app: AppHandle, path: Option<String>) -> Result<(), AppError> {
+    match path {
+        Some(path) => flush_autosave(&app, &path).await,
+        None => {
+            flush_all_autosaves(&app).await;
+            Ok(())
         }
     }
-    
-    // Sort: directories first, then files, both alphabetically
-    file_entries.sort_by(|a, b| {
-        match (a.is_directory, b.is_directory) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+}
+
+// Set how long edits must pause before an autosave is written
+#[tauri::command]
+fn set_autosave_delay(delay_ms: u64, state: State<AppState>) -> Result<(), AppError> {
+    *state
+        .autosave_delay
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())? = Duration::from_millis(delay_ms);
+    Ok(())
+}
+
+/**
+ * Write the pending autosave for `path`, if there is one, and emit `autosaved`
+ * The content stays pending until the write succeeds, so a failed write is
+ * retried by the next flush instead of being dropped
+ */
+async fn flush_autosave(app: &AppHandle, path: &str) -> Result<(), AppError> {
+    let pending = app
+        .state::<AppState>()
+        .autosave_pending
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())?
+        .get(path)
+        .map(|p| (p.content.clone(), p.edited_at));
+    let Some((content, edited_at)) = pending else {
+        return Ok(());
+    };
+
+    save_markdown_file(path.to_string(), content, None, None, app.state()).await?;
+    // Keep edits that arrived while writing; their own timer flushes them
+    if let Ok(mut pending) = app.state::<AppState>().autosave_pending.lock() {
+        if pending.get(path).is_some_and(|p| p.edited_at == edited_at) {
+            pending.remove(path);
         }
-    });
-    
-    Ok(file_entries)
+    }
+    let _ = app.emit("autosaved", path);
+    Ok(())
+}
+
+/**
+ * Write every pending autosave so closing a document or the app doesn't drop the last edits
+ */
+async fn flush_all_autosaves(app: &AppHandle) {
+    let paths: Vec<String> = match app.state::<AppState>().autosave_pending.lock() {
+        Ok(pending) => pending.keys().cloned().collect(),
+        Err(_) => return,
+    };
+    for path in paths {
+        if let Err(e) = flush_autosave(app, &path).await {
+            println!("❌ Autosave failed for {}: {}", path, e);
+        }
+    }
+}
+
+// Record whether the calling window has unsaved changes, so closing it prompts first
+#[tauri::command]
+fn set_document_dirty(
+    window: tauri::Window,
+    dirty: bool,
+    state: State<AppState>,
+) -> Result<(), AppError> {
+    let mut dirty_windows = state
+        .dirty_windows
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())?;
+    if dirty {
+        dirty_windows.insert(window.label().to_string());
+    } else {
+        dirty_windows.remove(window.label());
+    }
+    Ok(())
+}
+
+// Quit once the frontend has saved or discarded its changes. Another window
+// that still has unsaved changes is asked in turn.
+#[tauri::command]
+fn quit_app(app: AppHandle) {
+    app.exit(0);
+}
+
+// List directory contents, optionally as a nested tree
+#[tauri::command]
+async fn list_directory(
+    path: String,
+    recursive: Option<bool>,
+    max_depth: Option<usize>,
+    sort: Option<String>,
+    show_hidden: Option<bool>,
+    extensions: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<Vec<FileEntry>, AppError> {
+    check_path(&state, &path)?;
+    let options = ListOptions {
+        recursive: recursive.unwrap_or(false),
+        max_depth,
+        sort: sort
+            .as_deref()
+            .map(SortMode::parse)
+            .transpose()?
+            .unwrap_or_default(),
+        show_hidden: show_hidden.unwrap_or(false),
+        // Accept ".MD" as well as "md"
+        extensions: extensions.map(|list| {
+            list.iter()
+                .map(|e| e.trim_start_matches('.').to_lowercase())
+                .collect()
+        }),
+    };
+    Ok(files::list_directory(Path::new(&path), &options)?)
+}
+
+// Absolute form of a path with `~` expanded and `.`/`..` resolved, as stored in recent files
+#[tauri::command]
+fn canonicalize_path(path: String) -> Result<String, AppError> {
+    Ok(files::canonicalize_path(&path)?)
 }
 
 // Get recent files
 #[tauri::command]
-fn get_recent_files(state: State<AppState>) -> Result<Vec<String>, String> {
+fn get_recent_files(state: State<AppState>) -> Result<Vec<String>, AppError> {
     let recent = state.recent_files.lock()
         .map_err(|_| "Failed to lock state".to_string())?;
     Ok(recent.iter().cloned().collect())
@@ -332,48 +640,309 @@ fn get_recent_files(state: State<AppState>) -> Result<Vec<String>, String> {
 
 // Add a file to recent files
 #[tauri::command]
-fn add_recent_file(path: String, state: State<AppState>) -> Result<(), String> {
-    let mut recent = state.recent_files.lock()
+fn add_recent_file(app: AppHandle, path: String, state: State<AppState>) -> Result<(), AppError> {
+    // Store one spelling per file so `./a.md` and `~/a.md` don't show up twice
+    let path = files::canonicalize_path(&path).unwrap_or(path);
+    let max_recent = *state
+        .max_recent
+        .lock()
         .map_err(|_| "Failed to lock state".to_string())?;
-    
-    // Remove if already exists
+    {
+        let mut recent = state
+            .recent_files
+            .lock()
+            .map_err(|_| "Failed to lock state".to_string())?;
+        push_recent(&mut recent, path, max_recent);
+    }
+
+    refresh_app_menu(&app);
+    Ok(())
+}
+
+/**
+ * Move `path` to the front of a recent list, dropping any older entry for it
+ * and keeping at most `max` entries
+ */
+fn push_recent(recent: &mut VecDeque<String>, path: String, max: usize) {
     recent.retain(|p| p != &path);
-    
-    // Add to front
     recent.push_front(path);
-    
-    // Keep only 10 most recent
-    recent.truncate(10);
-    
+    recent.truncate(max);
+}
+
+// Get recently opened folders
+#[tauri::command]
+fn get_recent_folders(state: State<AppState>) -> Result<Vec<String>, AppError> {
+    let recent = state
+        .recent_folders
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())?;
+    Ok(recent.iter().cloned().collect())
+}
+
+// Add a folder to recent folders and save the list
+#[tauri::command]
+fn add_recent_folder(app: AppHandle, path: String, state: State<AppState>) -> Result<(), AppError> {
+    let path = files::canonicalize_path(&path).unwrap_or(path);
+    if !Path::new(&path).is_dir() {
+        return Err(AppError::NotFound(format!(
+            "Cannot add '{}' to recent folders: it is not a folder",
+            path
+        )));
+    }
+    let folders: Vec<String> = {
+        let mut recent = state
+            .recent_folders
+            .lock()
+            .map_err(|_| "Failed to lock state".to_string())?;
+        push_recent(&mut recent, path, MAX_RECENT_FOLDERS);
+        recent.iter().cloned().collect()
+    };
+    save_recent_folders_to_storage(folders)?;
+
+    refresh_app_menu(&app);
     Ok(())
 }
 
-// Create a new file
+// Saved recent folders that still exist, for the list restored at launch
+fn existing_folders(folders: Vec<String>) -> VecDeque<String> {
+    folders
+        .into_iter()
+        .filter(|folder| Path::new(folder).is_dir())
+        .take(MAX_RECENT_FOLDERS)
+        .collect()
+}
+
+// Clear the recent files list
 #[tauri::command]
-async fn create_file(path: String) -> Result<(), String> {
-    // Create parent directory if it doesn't exist
-    if let Some(parent) = PathBuf::from(&path).parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create directory: {}", e))?;
+fn clear_recent_files(app: AppHandle) -> Result<(), AppError> {
+    clear_recent(&app)
+}
+
+/**
+ * Empty the recent files list, notify listeners and refresh the Open Recent menu
+ * Shared by the clear_recent_files command and the File > Open Recent > Clear Recent item
+ */
+fn clear_recent(app: &AppHandle) -> Result<(), AppError> {
+    app.state::<AppState>()
+        .recent_files
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())?
+        .clear();
+
+    let _ = app.emit("recent-files-changed", Vec::<String>::new());
+    refresh_app_menu(app);
+    Ok(())
+}
+
+// Set how many recent files are remembered
+#[tauri::command]
+fn set_max_recent_files(
+    app: AppHandle,
+    count: usize,
+    state: State<AppState>,
+) -> Result<(), AppError> {
+    if count == 0 {
+        return Err("Recent files limit must be at least 1".into());
     }
-    
-    // Create empty file
-    fs::write(&path, "")
-        .map_err(|e| format!("Failed to create file: {}", e))
+
+    *state
+        .max_recent
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())? = count;
+
+    // Apply a lowered cap right away
+    state
+        .recent_files
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())?
+        .truncate(count);
+
+    refresh_app_menu(&app);
+    Ok(())
+}
+
+// Create a new empty file (and any missing parent folders)
+#[tauri::command]
+async fn create_file(path: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    check_path(&state, &path)?;
+
+    // Never truncate an existing file; already_exists lets the frontend ask for another name
+    files::create_empty_file(Path::new(&path)).map_err(|e| AppError::io("Failed to create file", e))
+}
+
+// Save a pasted image beside the document and return its relative path for the Markdown link
+#[tauri::command]
+async fn save_pasted_image(
+    doc_path: String,
+    bytes: Vec<u8>,
+    mime: String,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    check_path(&state, &doc_path)?;
+    Ok(files::save_pasted_image(
+        Path::new(&doc_path),
+        &bytes,
+        &mime,
+    )?)
 }
 
 // Delete a file
 #[tauri::command]
-async fn delete_file(path: String) -> Result<(), String> {
-    fs::remove_file(&path)
-        .map_err(|e| format!("Failed to delete file: {}", e))
+async fn delete_file(path: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    check_path(&state, &path)?;
+    fs::remove_file(&path).map_err(|e| AppError::io("Failed to delete file", e))
+}
+
+// Move a file or folder to the OS trash / recycle bin
+#[tauri::command]
+async fn trash_file(path: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    check_path(&state, &path)?;
+    if !PathBuf::from(&path).exists() {
+        return Err(AppError::NotFound(format!(
+            "Failed to move to trash: '{}' does not exist",
+            path
+        )));
+    }
+
+    // trash::delete handles directories recursively
+    trash::delete(&path).map_err(|e| {
+        AppError::Io(format!(
+            "Failed to move to trash (the system trash may be unavailable): {}",
+            e
+        ))
+    })
+}
+
+// Rename a note and fix the relative links to it in the other notes under
+// `vault_root`; returns the notes that were rewritten
+#[tauri::command]
+async fn rename_with_link_update(
+    old_path: String,
+    new_path: String,
+    vault_root: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, AppError> {
+    check_writable(&state)?;
+    check_path(&state, &old_path)?;
+    check_path(&state, &new_path)?;
+    check_path(&state, &vault_root)?;
+    let renamed = old_path.clone();
+    let rewritten = tokio::task::spawn_blocking(move || {
+        links::rename_with_link_update(
+            Path::new(&old_path),
+            Path::new(&new_path),
+            Path::new(&vault_root),
+        )
+    })
+    .await
+    .map_err(|e| format!("Task error: {}", e))??;
+    forget_file_format(&state, &renamed);
+    Ok(rewritten)
+}
+
+// Show a file in Finder / Explorer with it selected. Not every Linux file
+// manager can select a file, so there its folder is opened instead.
+#[tauri::command]
+fn reveal_in_file_manager(path: String, state: State<AppState>) -> Result<(), AppError> {
+    check_path(&state, &path)?;
+    let target = PathBuf::from(&path);
+    if !target.exists() {
+        return Err(AppError::NotFound(format!(
+            "Cannot reveal '{}': it does not exist",
+            path
+        )));
+    }
+
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = std::process::Command::new("open");
+        command.arg("-R").arg(&target);
+        command
+    };
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        use std::os::windows::process::CommandExt;
+        // Explorer wants the path quoted after the comma, not the whole argument
+        let mut command = std::process::Command::new("explorer");
+        command.raw_arg(format!("/select,\"{}\"", target.display()));
+        command
+    };
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut command = {
+        let folder = if target.is_dir() {
+            target.as_path()
+        } else {
+            target.parent().unwrap_or(Path::new("/"))
+        };
+        let mut command = std::process::Command::new("xdg-open");
+        command.arg(folder);
+        command
+    };
+
+    let mut child = command
+        .spawn()
+        .map_err(|e| AppError::Io(format!("Failed to open the file manager: {}", e)))?;
+    // Reap the launcher once it exits so it doesn't linger as a zombie. Explorer
+    // exits with 1 even on success, so the status itself is ignored.
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+// Rename a file; an existing destination is only replaced when `overwrite` is set
+#[tauri::command]
+async fn rename_file(
+    old_path: String,
+    new_path: String,
+    overwrite: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    check_path(&state, &old_path)?;
+    check_path(&state, &new_path)?;
+    files::rename_path(
+        Path::new(&old_path),
+        Path::new(&new_path),
+        overwrite.unwrap_or(false),
+    )
+    .map_err(|e| AppError::io("Failed to rename file", e))?;
+    forget_file_format(&state, &old_path);
+    Ok(())
+}
+
+// Watch an open document and emit `file-changed-externally` when another program modifies it
+#[tauri::command]
+fn watch_file(app: AppHandle, path: String, state: State<AppState>) -> Result<(), AppError> {
+    check_path(&state, &path)?;
+    let event_path = path.clone();
+    let watcher = watch::FileWatcher::new(Path::new(&path), move || {
+        let _ = app.emit("file-changed-externally", event_path.clone());
+    })?;
+
+    if let Ok(mut queue) = state.open_queue.lock() {
+        queue.mark_opened(canonical_key(Path::new(&path)));
+    }
+    state
+        .watchers
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())?
+        .insert(path, watcher);
+    Ok(())
 }
 
-// Rename a file
+// Stop watching a closed document (dropping the watcher shuts it down)
 #[tauri::command]
-async fn rename_file(old_path: String, new_path: String) -> Result<(), String> {
-    fs::rename(&old_path, &new_path)
-        .map_err(|e| format!("Failed to rename file: {}", e))
+fn unwatch_file(path: String, state: State<AppState>) -> Result<(), AppError> {
+    forget_file_format(&state, &path);
+    if let Ok(mut queue) = state.open_queue.lock() {
+        queue.forget(&canonical_key(Path::new(&path)));
+    }
+    state
+        .watchers
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())?
+        .remove(&path);
+    Ok(())
 }
 
 // Check if file exists
@@ -382,6 +951,109 @@ fn file_exists(path: String) -> bool {
     PathBuf::from(path).exists()
 }
 
+// Word, character and reading-time counts for the status bar
+#[tauri::command]
+fn document_stats(markdown: String) -> markdown::DocumentStats {
+    markdown::document_stats(&markdown)
+}
+
+// Document text with the Markdown syntax stripped, for previews and plain-text copies
+#[tauri::command]
+fn markdown_to_plaintext(markdown: String) -> String {
+    markdown::markdown_to_plaintext(&markdown)
+}
+
+// LaTeX source for the document; `fragment` leaves out the preamble for pasting into a paper
+#[tauri::command]
+fn markdown_to_latex(markdown: String, fragment: Option<bool>) -> String {
+    convert::latex::markdown_to_latex(&markdown, fragment.unwrap_or(false))
+}
+
+// Whether the document has tables; File > Export As > XLSX is disabled without them
+#[tauri::command]
+fn document_has_tables(markdown: String) -> bool {
+    markdown::has_tables(&markdown)
+}
+
+// Tidy up Markdown style (heading spacing, bullet markers, blank lines, trailing whitespace)
+#[tauri::command]
+fn format_markdown(markdown: String) -> String {
+    lint::format_markdown(&markdown)
+}
+
+// Style issues format_markdown would fix, with line/column and rule id
+#[tauri::command]
+fn lint_markdown(markdown: String) -> Vec<lint::LintIssue> {
+    lint::lint_markdown(&markdown)
+}
+
+// Table of contents for the outline panel
+#[tauri::command]
+fn generate_toc(markdown: String) -> Vec<markdown::TocEntry> {
+    markdown::generate_toc(&markdown)
+}
+
+// Nested outline with source offsets, so clicking a heading can scroll the source view
+#[tauri::command]
+fn document_outline(markdown: String) -> Vec<markdown::OutlineNode> {
+    markdown::document_outline(&markdown)
+}
+
+// Relative links and anchors in a document, with whether each target exists
+#[tauri::command]
+fn check_links(doc_path: String, markdown: String) -> Vec<links::LinkStatus> {
+    links::check_links(&doc_path, &markdown)
+}
+
+// Line diff between two versions of a document, for reviewing changes before applying them
+#[tauri::command]
+fn diff_markdown(old: String, new: String) -> Vec<diff::DiffHunk> {
+    diff::diff_markdown(&old, &new)
+}
+
+// Split YAML front matter (as JSON) from the document body
+#[tauri::command]
+fn parse_front_matter(markdown: String) -> Result<(Option<serde_json::Value>, String), AppError> {
+    Ok(markdown::parse_front_matter(&markdown)?)
+}
+
+// Find lines containing plain text in the Markdown files under a directory
+#[tauri::command]
+async fn search_in_directory(
+    dir: String,
+    query: String,
+    case_sensitive: bool,
+    show_hidden: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Vec<files::SearchHit>, AppError> {
+    check_path(&state, &dir)?;
+    tokio::task::spawn_blocking(move || {
+        files::search_in_directory(
+            Path::new(&dir),
+            &query,
+            case_sensitive,
+            show_hidden.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|e| format!("Task error: {}", e))?
+    .map_err(AppError::from)
+}
+
+// Files under a folder matching a glob like `**/*.md`, for the quick-open palette
+#[tauri::command]
+async fn find_files(
+    root: String,
+    pattern: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, AppError> {
+    check_path(&state, &root)?;
+    tokio::task::spawn_blocking(move || files::find_files(Path::new(&root), &pattern))
+        .await
+        .map_err(|e| format!("Task error: {}", e))?
+        .map_err(AppError::from)
+}
+
 // Search result for cross-file search
 #[derive(Serialize, Clone)]
 struct SearchResult {
@@ -399,7 +1071,10 @@ async fn search_in_files(
     query: String,
     case_sensitive: bool,
     use_regex: bool,
-) -> Result<Vec<SearchResult>, String> {
+    state: State<'_, AppState>,
+) -> Result<Vec<SearchResult>, AppError> {
+    check_path(&state, &root)?;
+
     if query.is_empty() {
         return Ok(vec![]);
     }
@@ -415,96 +1090,70 @@ async fn search_in_files(
         .build()
         .map_err(|e| format!("Invalid regex: {}", e))?;
 
-    let mut results: Vec<SearchResult> = Vec::new();
-    const MAX_RESULTS: usize = 500;
-
-    for entry in WalkDir::new(&root)
-        .follow_links(false)
+    let root = PathBuf::from(root);
+    let matches = tokio::task::spawn_blocking(move || files::search_in_files(&root, &re, false))
+        .await
+        .map_err(|e| format!("Task error: {}", e))??;
+    Ok(matches
         .into_iter()
-        .filter_map(|e| e.ok())
-    {
-        if results.len() >= MAX_RESULTS {
-            break;
-        }
-
-        let path = entry.path();
-        if !path.is_file() {
-            continue;
-        }
-
-        let ext = path.extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("")
-            .to_lowercase();
-        if ext != "md" && ext != "markdown" {
-            continue;
-        }
-
-        // Skip hidden files/dirs
-        let is_hidden = path.components().any(|c| {
-            c.as_os_str().to_str().map(|s| s.starts_with('.')).unwrap_or(false)
-        });
-        if is_hidden {
-            continue;
-        }
-
-        let content = match fs::read_to_string(path) {
-            Ok(c) => c,
-            Err(_) => continue,
-        };
-
-        let file_path_str = path.to_string_lossy().to_string();
-
-        for (line_idx, line) in content.lines().enumerate() {
-            if results.len() >= MAX_RESULTS {
-                break;
-            }
-
-            for m in re.find_iter(line) {
-                results.push(SearchResult {
-                    file_path: file_path_str.clone(),
-                    line_number: line_idx + 1,
-                    line_content: line.to_string(),
-                    match_start: m.start(),
-                    match_end: m.end(),
-                });
-
-                if results.len() >= MAX_RESULTS {
-                    break;
-                }
-            }
-        }
-    }
-
-    Ok(results)
+        .map(|m| SearchResult {
+            file_path: m.path,
+            line_number: m.line_number,
+            line_content: m.line,
+            match_start: m.range.start,
+            match_end: m.range.end,
+        })
+        .collect())
 }
 
 /**
  * Normalize language code to supported format ('en' or 'zh')
  */
 fn normalize_language(lang: &str) -> String {
-    match lang.to_lowercase().split('-').next().unwrap_or("en") {
+    match lang.to_lowercase().split(['-', '_']).next().unwrap_or("en") {
         "zh" => "zh".to_string(),
+        "ja" => "ja".to_string(),
         _ => "en".to_string(),
     }
 }
 
+/**
+ * System locale as the UI language ("en", "zh", "ja") plus the chain of
+ * translation tables to try for it, e.g. ["zh-Hans", "zh-Hant", "en"] for zh-CN
+ */
+#[derive(Serialize, Clone)]
+struct SystemLocale {
+    language: String,
+    chain: Vec<String>,
+}
+
 /**
  * Get system locale using backend detection (Tauri v2 best practice)
  * Detects locale at Rust level for better performance and reliability
  */
 #[tauri::command]
-fn get_system_locale() -> Result<String, String> {
+fn get_system_locale() -> Result<SystemLocale, AppError> {
     match tauri_plugin_os::locale() {
         Some(locale_str) => {
             let normalized = normalize_language(&locale_str);
-            println!("🌍 System locale detected: {} → normalized to: {}", 
-                     locale_str, normalized);
-            Ok(normalized)
+            let chain = i18n::locale_chain(&locale_str);
+            println!(
+                "🌍 System locale detected: {} → normalized to: {} (chain: {})",
+                locale_str,
+                normalized,
+                chain.join(" → ")
+            );
+            Ok(SystemLocale {
+                language: normalized,
+                chain,
+            })
         }
         None => {
             println!("⚠️ System locale not available, using default: English");
-            Ok("en".to_string())
+            Ok(SystemLocale {
+                language: "en".to_string(),
+                chain: i18n::locale_chain("en"),
+            })
         }
     }
 }
@@ -513,7 +1162,7 @@ fn get_system_locale() -> Result<String, String> {
  * Get the current language setting
  */
 #[tauri::command]
-fn get_language(state: State<AppState>) -> Result<String, String> {
+fn get_language(state: State<AppState>) -> Result<String, AppError> {
     let lang = state.language.lock()
         .map_err(|_| "Failed to lock language state".to_string())?;
     Ok(lang.clone())
@@ -524,7 +1173,7 @@ fn get_language(state: State<AppState>) -> Result<String, String> {
  * This updates the state but NOT the menu (menu is handled in event handler)
  */
 #[tauri::command]
-fn set_language(state: State<AppState>, lang: String) -> Result<(), String> {
+fn set_language(state: State<AppState>, lang: String) -> Result<(), AppError> {
     let normalized_lang = normalize_language(&lang);
     
     let mut l = state.language.lock()
@@ -541,8 +1190,8 @@ fn set_language(state: State<AppState>, lang: String) -> Result<(), String> {
  * Tauri v2 best practice: store settings in app config directory
  */
 #[tauri::command]
-fn get_user_settings() -> Result<UserSettings, String> {
-    let settings = UserSettings::load()?.unwrap_or_else(|| UserSettings { language: "en".to_string() });
+fn get_user_settings() -> Result<UserSettings, AppError> {
+    let settings = UserSettings::load()?.unwrap_or_default();
     println!("📂 User settings retrieved: language={}", settings.language);
     Ok(settings)
 }
@@ -552,11 +1201,11 @@ fn get_user_settings() -> Result<UserSettings, String> {
  * This ensures language preference survives app restarts
  */
 #[tauri::command]
-fn save_language_preference(lang: String, state: State<AppState>) -> Result<(), String> {
+fn save_language_preference(lang: String, state: State<AppState>) -> Result<(), AppError> {
     let normalized_lang = normalize_language(&lang);
     
     // Load existing settings (to preserve other settings if any)
-    let mut settings = UserSettings::load()?.unwrap_or_else(|| UserSettings { language: "en".to_string() });
+    let mut settings = UserSettings::load()?.unwrap_or_default();
     
     // Update language
     settings.language = normalized_lang.clone();
@@ -573,71 +1222,483 @@ fn save_language_preference(lang: String, state: State<AppState>) -> Result<(),
     Ok(())
 }
 
-// Update check menu item state
+// Get the active theme id
 #[tauri::command]
-fn update_menu_item_state(app: AppHandle, id: String, checked: bool) -> Result<(), String> {
-    if let Some(menu) = app.menu() {
-        if let Some(item) = menu.get(&id) {
-            if let Some(check_item) = item.as_check_menuitem() {
-                let _ = check_item.set_checked(checked);
+fn get_theme(state: State<AppState>) -> Result<String, AppError> {
+    let theme = state
+        .theme
+        .lock()
+        .map_err(|_| "Failed to lock theme state".to_string())?;
+    Ok(theme.clone())
+}
+
+// Set the active theme (e.g. when the frontend toggles it) and persist it
+#[tauri::command]
+fn set_theme(app: AppHandle, theme: String) -> Result<(), AppError> {
+    if !themes::is_known(&theme, &custom_themes(&app)) {
+        return Err(format!("Unknown theme: {}", theme).into());
+    }
+    Ok(apply_theme(&app, &theme)?)
+}
+
+/**
+ * Add a user theme to View > Theme. Selecting it emits `menu-set-theme` with
+ * its id; the frontend provides the styles. Saved so it survives restarts.
+ */
+#[tauri::command]
+fn register_theme(
+    app: AppHandle,
+    id: String,
+    label: String,
+    state: State<AppState>,
+) -> Result<(), AppError> {
+    {
+        let mut custom = state
+            .custom_themes
+            .lock()
+            .map_err(|_| "Failed to lock theme state".to_string())?;
+        let theme = themes::validate_custom(&id, &label, &custom)?;
+        custom.push(theme);
+
+        let mut settings = UserSettings::load()?.unwrap_or_default();
+        settings.custom_themes = custom.clone();
+        settings.save()?;
+    }
+    refresh_app_menu(&app);
+    Ok(())
+}
+
+/**
+ * Find a menu item by id at any depth
+ * `Menu::get` only looks at the top level, where there are just the submenus
+ */
+fn find_menu_item<R: tauri::Runtime>(
+    items: Vec<MenuItemKind<R>>,
+    id: &str,
+) -> Option<MenuItemKind<R>> {
+    for item in items {
+        if item.id().0 == id {
+            return Some(item);
+        }
+        if let MenuItemKind::Submenu(submenu) = &item {
+            if let Some(found) = submenu
+                .items()
+                .ok()
+                .and_then(|items| find_menu_item(items, id))
+            {
+                return Some(found);
             }
         }
     }
+    None
+}
+
+// Update check menu item state
+#[tauri::command]
+fn update_menu_item_state(app: AppHandle, id: String, checked: bool) -> Result<(), AppError> {
+    let item = app
+        .menu()
+        .and_then(|menu| find_menu_item(menu.items().ok()?, &id));
+    if let Some(check_item) = item.as_ref().and_then(|item| item.as_check_menuitem()) {
+        let _ = check_item.set_checked(checked);
+    }
+    Ok(())
+}
+
+/**
+ * Update the Format menu checkmarks from the formatting at the cursor.
+ * `states` maps menu item ids (e.g. "editor_bold") to whether they are active;
+ * ids that aren't format toggles are ignored.
+ */
+#[tauri::command]
+fn update_format_state(
+    app: AppHandle,
+    states: HashMap<String, bool>,
+    state: State<AppState>,
+) -> Result<(), AppError> {
+    let mut format_states = state
+        .format_states
+        .lock()
+        .map_err(|_| "Failed to lock format state".to_string())?;
+    for (id, checked) in states {
+        if !FORMAT_CHECK_ITEMS.contains(&id.as_str()) {
+            continue;
+        }
+        format_states.insert(id.clone(), checked);
+        update_menu_item_state(app.clone(), id, checked)?;
+    }
     Ok(())
 }
 
 // Enable or disable a menu item by id
 #[tauri::command]
-fn enable_menu_item(app: AppHandle, id: String, enabled: bool) -> Result<(), String> {
-    if let Some(menu) = app.menu() {
-        if let Some(item) = menu.get(&id) {
-            match item {
-                tauri::menu::MenuItemKind::MenuItem(mi) => mi.set_enabled(enabled).map_err(|e| e.to_string())?,
-                tauri::menu::MenuItemKind::Submenu(sm) => sm.set_enabled(enabled).map_err(|e| e.to_string())?,
-                tauri::menu::MenuItemKind::Check(cm) => cm.set_enabled(enabled).map_err(|e| e.to_string())?,
-                tauri::menu::MenuItemKind::Icon(im) => im.set_enabled(enabled).map_err(|e| e.to_string())?,
-                tauri::menu::MenuItemKind::Predefined(_) => {}
-            }
+fn enable_menu_item(app: AppHandle, id: String, enabled: bool) -> Result<(), AppError> {
+    if let Some(item) = app
+        .menu()
+        .and_then(|menu| find_menu_item(menu.items().ok()?, &id))
+    {
+        match item {
+            MenuItemKind::MenuItem(mi) => mi.set_enabled(enabled).map_err(|e| e.to_string())?,
+            MenuItemKind::Submenu(sm) => sm.set_enabled(enabled).map_err(|e| e.to_string())?,
+            MenuItemKind::Check(cm) => cm.set_enabled(enabled).map_err(|e| e.to_string())?,
+            MenuItemKind::Icon(im) => im.set_enabled(enabled).map_err(|e| e.to_string())?,
+            MenuItemKind::Predefined(_) => {}
         }
     }
     Ok(())
 }
 
-// Import a document from a non-markdown format and return Markdown content
+/**
+ * Path of the keyboard shortcut overrides, next to settings.json
+ */
+fn shortcuts_path() -> Result<PathBuf, String> {
+    Ok(UserSettings::config_path()?.with_file_name("shortcuts.json"))
+}
+
+/**
+ * Load the user's keyboard shortcuts, falling back to the defaults
+ */
+fn load_shortcuts() -> Shortcuts {
+    match shortcuts_path() {
+        Ok(path) => Shortcuts::load(&path),
+        Err(e) => {
+            println!("⚠️ Failed to locate shortcuts file: {}", e);
+            Shortcuts::default()
+        }
+    }
+}
+
+// Get the current accelerator of every menu item that can have a shortcut
+#[tauri::command]
+fn get_shortcuts(state: State<AppState>) -> Result<HashMap<String, String>, AppError> {
+    let shortcuts = state
+        .shortcuts
+        .lock()
+        .map_err(|_| "Failed to lock shortcuts state".to_string())?;
+    Ok(shortcuts.all().clone())
+}
+
+/**
+ * Assign a keyboard shortcut to a menu command, rebuild the menu and persist it.
+ * An empty accelerator removes the shortcut; duplicates are rejected.
+ * The shortcut is saved only once the menu accepted it, so an accelerator
+ * Tauri can't parse never reaches the file read at startup.
+ */
 #[tauri::command]
-async fn import_document(path: String, format: String) -> Result<String, String> {
-    tokio::task::spawn_blocking(move || match format.as_str() {
-        "docx" => convert::docx::docx_to_markdown(&path).map_err(String::from),
-        "xlsx" => convert::xlsx::xlsx_to_markdown(&path).map_err(String::from),
-        "pdf"  => convert::pdf::pdf_to_markdown(&path).map_err(String::from),
-        "pptx" => convert::pptx::pptx_to_markdown(&path).map_err(String::from),
-        other  => Err(format!("Unsupported import format: {}", other)),
+fn set_shortcut(
+    app: AppHandle,
+    command: String,
+    accelerator: String,
+    state: State<AppState>,
+) -> Result<(), AppError> {
+    let (previous, updated) = {
+        let mut shortcuts = state
+            .shortcuts
+            .lock()
+            .map_err(|_| "Failed to lock shortcuts state".to_string())?;
+        let mut updated = shortcuts.clone();
+        updated.set(&command, &accelerator)?;
+        (std::mem::replace(&mut *shortcuts, updated.clone()), updated)
+    };
+
+    let lang = state
+        .language
+        .lock()
+        .map(|l| l.clone())
+        .unwrap_or_else(|_| "en".to_string());
+    if let Err(e) = create_app_menu(&app, &lang).and_then(|menu| app.set_menu(menu)) {
+        if let Ok(mut shortcuts) = state.shortcuts.lock() {
+            *shortcuts = previous;
+        }
+        return Err(format!("Invalid shortcut '{}': {}", accelerator, e).into());
+    }
+
+    updated.save(&shortcuts_path()?)?;
+    println!("⌨️ Shortcut for {} set to: {}", command, accelerator);
+    Ok(())
+}
+
+/**
+ * Resolve the converter format for a document command.
+ * An explicit `format` wins; otherwise the file extension of `path` is used.
+ */
+fn resolve_format(path: &str, format: Option<String>) -> Result<String, String> {
+    match format.filter(|f| !f.trim().is_empty()) {
+        Some(f) => Ok(f.trim().trim_start_matches('.').to_lowercase()),
+        None => convert::extension_of(path).ok_or_else(|| {
+            format!(
+                "Cannot determine format for '{}': file has no extension",
+                path
+            )
+        }),
+    }
+}
+
+// Import a document from a non-markdown format and return Markdown content.
+// PDF and spreadsheet imports emit `import-progress`; given an `import_id`
+// they can be stopped with `cancel_import`.
+#[tauri::command]
+async fn import_document(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    format: Option<String>,
+    drop_underline: Option<bool>,
+    import_id: Option<String>,
+) -> Result<String, AppError> {
+    check_path(&state, &path)?;
+    let format = resolve_format(&path, format)?;
+    run_import(
+        app,
+        &state,
+        path,
+        format,
+        drop_underline.unwrap_or(false),
+        import_id,
+    )
+    .await
+}
+
+// Markdown from an import together with the caveats to show the user
+#[derive(Serialize, Clone)]
+struct ImportedMarkdown {
+    markdown: String,
+    warnings: Vec<String>,
+}
+
+// Import any supported document, detecting the format from its extension
+#[tauri::command]
+async fn import_to_markdown(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    import_id: Option<String>,
+) -> Result<ImportedMarkdown, AppError> {
+    check_path(&state, &path)?;
+    let format = resolve_format(&path, None)?;
+    let markdown = run_import(app, &state, path, format.clone(), false, import_id).await?;
+    let warnings = convert::import_warnings(&format, &markdown);
+    Ok(ImportedMarkdown { markdown, warnings })
+}
+
+// Progress of one import, tagged with the id it was started with
+#[derive(Serialize, Clone)]
+struct ImportProgressEvent {
+    import_id: Option<String>,
+    #[serde(flatten)]
+    progress: convert::ImportProgress,
+}
+
+async fn run_import(
+    app: AppHandle,
+    state: &AppState,
+    path: String,
+    format: String,
+    drop_underline: bool,
+    import_id: Option<String>,
+) -> Result<String, AppError> {
+    // Each import gets its own flag, so cancelling one leaves the others running
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Some(id) = &import_id {
+        state
+            .import_cancels
+            .lock()
+            .map_err(|_| "Failed to lock state".to_string())?
+            .insert(id.clone(), cancel.clone());
+    }
+    let event_id = import_id.clone();
+    let control = convert::ImportControl {
+        cancel,
+        progress: Some(Arc::new(move |progress: convert::ImportProgress| {
+            let _ = app.emit(
+                "import-progress",
+                ImportProgressEvent {
+                    import_id: event_id.clone(),
+                    progress,
+                },
+            );
+        })),
+    };
+    let options = convert::ImportOptions {
+        drop_underline,
+        control,
+    };
+    let result = tokio::task::spawn_blocking(move || {
+        convert::import_with_options(&path, &format, &options).map_err(String::from)
+    })
+    .await;
+
+    if let Some(id) = &import_id {
+        if let Ok(mut cancels) = state.import_cancels.lock() {
+            cancels.remove(id);
+        }
+    }
+    result
+        .map_err(|e| format!("Task error: {}", e))?
+        .map_err(AppError::from)
+}
+
+// Stop the import started with `import_id` at its next page or block of rows
+#[tauri::command]
+fn cancel_import(import_id: String, state: State<AppState>) -> Result<(), AppError> {
+    let cancels = state
+        .import_cancels
+        .lock()
+        .map_err(|_| "Failed to lock state".to_string())?;
+    if let Some(cancel) = cancels.get(&import_id) {
+        cancel.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+// Export Markdown content to a non-markdown format, optionally just the
+// lines covering `byte_range`. CSV takes the table at `table_index`.
+#[tauri::command]
+async fn export_document(
+    state: State<'_, AppState>,
+    content: String,
+    path: String,
+    format: Option<String>,
+    pdf_options: Option<convert::pdf::PdfOptions>,
+    xlsx_options: Option<convert::xlsx::XlsxOptions>,
+    epub_meta: Option<convert::epub::EpubMeta>,
+    font: Option<String>,
+    theme: Option<String>,
+    byte_range: Option<(usize, usize)>,
+    table_index: Option<usize>,
+) -> Result<(), AppError> {
+    check_path(&state, &path)?;
+    let format = resolve_format(&path, format)?;
+    let options = convert::ExportOptions {
+        docx: None,
+        pdf: pdf_options,
+        xlsx: xlsx_options,
+        epub: epub_meta,
+        font,
+        theme,
+        byte_range,
+        table_index,
+    };
+    tokio::task::spawn_blocking(move || {
+        convert::export_with_format(&content, &path, &format, &options).map_err(String::from)
     })
     .await
     .map_err(|e| format!("Task error: {}", e))?
+    .map_err(AppError::from)
 }
 
-// Export Markdown content to a non-markdown format
+// Combine the notes in a folder into one document, emitting `export-progress` after each note
 #[tauri::command]
-async fn export_document(content: String, path: String, format: String) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || match format.as_str() {
-        "docx" => convert::docx::markdown_to_docx(&content, &path).map_err(String::from),
-        "xlsx" => convert::xlsx::markdown_to_xlsx(&content, &path).map_err(String::from),
-        "pdf"  => convert::pdf::markdown_to_pdf(&content, &path).map_err(String::from),
-        "pptx" => convert::pptx::markdown_to_pptx(&content, &path).map_err(String::from),
-        other  => Err(format!("Unsupported export format: {}", other)),
+async fn export_folder(
+    app: AppHandle,
+    root: String,
+    target: String,
+    format: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    check_path(&state, &root)?;
+    check_path(&state, &target)?;
+    let format = resolve_format(&target, Some(format))?;
+    tokio::task::spawn_blocking(move || {
+        convert::export_folder(Path::new(&root), &target, &format, |progress| {
+            let _ = app.emit("export-progress", progress);
+        })
+        .map_err(String::from)
     })
     .await
     .map_err(|e| format!("Task error: {}", e))?
+    .map_err(AppError::from)
+}
+
+// Outcome of converting one file in a batch
+#[derive(Serialize, Clone)]
+struct ConversionResult {
+    source: String,
+    output: Option<String>,
+    error: Option<String>,
+}
+
+// Emitted after each file of a batch conversion
+#[derive(Serialize, Clone)]
+struct ConversionProgress {
+    index: usize,
+    total: usize,
+    source: String,
+    success: bool,
+}
+
+// Convert several documents to one format, one after another. A failing file
+// is reported in its result and doesn't stop the rest of the batch.
+#[tauri::command]
+async fn convert_batch(
+    app: AppHandle,
+    paths: Vec<String>,
+    target_format: String,
+    output_dir: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<ConversionResult>, AppError> {
+    check_path(&state, &output_dir)?;
+    for source in &paths {
+        check_path(&state, source)?;
+    }
+    tokio::task::spawn_blocking(move || {
+        let total = paths.len();
+        let output_dir = PathBuf::from(output_dir);
+        paths
+            .into_iter()
+            .enumerate()
+            .map(|(index, source)| {
+                let result = match convert::convert_file(&source, &target_format, &output_dir) {
+                    Ok(output) => ConversionResult {
+                        source,
+                        output: Some(output.to_string_lossy().into_owned()),
+                        error: None,
+                    },
+                    Err(e) => ConversionResult {
+                        source,
+                        output: None,
+                        error: Some(e.0),
+                    },
+                };
+                let _ = app.emit(
+                    "conversion-progress",
+                    ConversionProgress {
+                        index: index + 1,
+                        total,
+                        source: result.source.clone(),
+                        success: result.error.is_none(),
+                    },
+                );
+                result
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| AppError::Io(format!("Task error: {}", e)))
 }
 
 // Drain any pending open-file requests (used on app startup).
 #[tauri::command]
-fn take_pending_open_files(state: State<AppState>) -> Result<Vec<String>, String> {
-    let mut pending = state.pending_open_files
+fn take_pending_open_files(state: State<AppState>) -> Result<Vec<String>, AppError> {
+    let mut queue = state
+        .open_queue
+        .lock()
+        .map_err(|_| "Failed to lock pending open files".to_string())?;
+    Ok(queue.take_pending())
+}
+
+// Called once the frontend's open-file listener is registered; emits the files queued so far
+#[tauri::command]
+fn frontend_ready(app: AppHandle, state: State<AppState>) -> Result<(), AppError> {
+    let paths = state
+        .open_queue
         .lock()
-        .map_err(|_| "Failed to lock pending open files".to_string())?;
-    Ok(pending.drain(..).collect())
+        .map_err(|_| "Failed to lock pending open files".to_string())?
+        .mark_ready();
+    for path in paths {
+        let _ = app.emit("open-file", path);
+    }
+    Ok(())
 }
 
 // Get OS platform (compile-time detection for early initialization)
@@ -661,6 +1722,31 @@ fn get_os_platform() -> String {
     }
 }
 
+// Version details for the About dialog and bug reports
+#[derive(Serialize, Clone)]
+struct BuildInfo {
+    version: String,
+    // Short commit hash captured by build.rs, "unknown" outside a git checkout
+    commit: String,
+    profile: String,
+    platform: String,
+}
+
+#[tauri::command]
+fn get_build_info() -> BuildInfo {
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        commit: env!("MARKBEAR_GIT_COMMIT").to_string(),
+        profile: if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        }
+        .to_string(),
+        platform: get_os_platform(),
+    }
+}
+
 #[derive(Serialize, Clone)]
 struct MenuCommandPayload {
     command: String,
@@ -673,6 +1759,41 @@ fn emit_editor_command(app: &tauri::AppHandle, command: &str, level: Option<u8>)
         level,
     };
     let _ = app.emit("menu-editor-command", payload);
+    // Headings carry their level after a colon, e.g. "heading:2"
+    let arg = match level {
+        Some(level) => format!("{}:{}", command, level),
+        None => command.to_string(),
+    };
+    emit_menu_action(app, "editor-command", Some(arg));
+}
+
+// Payload of `menu-action`, the one event every menu action is sent on
+#[derive(Clone, Serialize)]
+struct MenuAction {
+    action: String,
+    arg: Option<String>,
+}
+
+fn emit_menu_action(app: &tauri::AppHandle, action: &str, arg: Option<String>) {
+    let _ = app.emit(
+        "menu-action",
+        MenuAction {
+            action: action.to_string(),
+            arg,
+        },
+    );
+}
+
+// Emit a menu action on `menu-action` and on its older per-action event
+// (`menu-<action>`, with `arg` as the payload). The per-action events are
+// deprecated and stay only until the frontend listens to `menu-action` alone.
+fn emit_menu_event(app: &tauri::AppHandle, action: &str, arg: Option<String>) {
+    let legacy = format!("menu-{}", action);
+    let _ = match &arg {
+        Some(arg) => app.emit(&legacy, arg.clone()),
+        None => app.emit(&legacy, ()),
+    };
+    emit_menu_action(app, action, arg);
 }
 
 /**
@@ -680,73 +1801,210 @@ fn emit_editor_command(app: &tauri::AppHandle, command: &str, level: Option<u8>)
  * Used by menu event handlers
  */
 fn save_language_to_storage(lang: &str) -> Result<(), String> {
-    let mut settings = UserSettings::load()?.unwrap_or_else(|| UserSettings { language: "en".to_string() });
+    let mut settings = UserSettings::load()?.unwrap_or_default();
     settings.language = lang.to_string();
     settings.save()
 }
 
-fn normalize_open_path(arg: &str) -> Option<String> {
-    let trimmed = arg.trim_matches('"');
-    if trimmed.is_empty() || trimmed.starts_with("-psn_") {
-        return None;
+fn save_theme_to_storage(theme: &str) -> Result<(), String> {
+    let mut settings = UserSettings::load()?.unwrap_or_default();
+    settings.theme = Some(theme.to_string());
+    settings.save()
+}
+
+fn save_recent_folders_to_storage(folders: Vec<String>) -> Result<(), String> {
+    let mut settings = UserSettings::load()?.unwrap_or_default();
+    settings.recent_folders = folders;
+    settings.save()
+}
+
+fn custom_themes(app: &AppHandle) -> Vec<CustomTheme> {
+    app.state::<AppState>()
+        .custom_themes
+        .lock()
+        .map(|themes| themes.clone())
+        .unwrap_or_default()
+}
+
+/**
+ * Make `theme` the active theme: remember it, persist it to the settings file
+ * and move the checkmark in View > Theme
+ */
+fn apply_theme(app: &AppHandle, theme: &str) -> Result<(), String> {
+    *app.state::<AppState>()
+        .theme
+        .lock()
+        .map_err(|_| "Failed to lock theme state".to_string())? = theme.to_string();
+    let ids = BUILTIN_THEMES
+        .iter()
+        .map(|(id, _)| id.to_string())
+        .chain(custom_themes(app).into_iter().map(|t| t.id));
+    for id in ids {
+        let _ = update_menu_item_state(app.clone(), themes::menu_id(&id), id == theme);
     }
+    save_theme_to_storage(theme)
+}
 
-    let path_str = if let Ok(url) = tauri::Url::parse(trimmed) {
-        if url.scheme() == "file" {
-            url.to_file_path().ok()
-        } else {
-            None
-        }
-    } else {
-        Some(PathBuf::from(trimmed))
-    };
+fn queue_open_files(app: &AppHandle, paths: Vec<String>) {
+    if paths.is_empty() {
+        return;
+    }
 
-    let path = match path_str {
-        Some(p) => p,
-        None => return None,
+    let actions = match app.state::<AppState>().open_queue.lock() {
+        Ok(mut queue) => queue.push(paths),
+        Err(_) => return,
     };
 
-    if !path.is_file() {
-        return None;
+    for action in actions {
+        match action {
+            OpenAction::Open(path) => {
+                let _ = app.emit("open-file", path);
+            }
+            OpenAction::Focus(path) => {
+                // Bring the window holding the document forward, then let the frontend switch to it
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.unminimize();
+                    let _ = window.set_focus();
+                }
+                let _ = app.emit("focus-file", path);
+            }
+        }
     }
+}
+
+const RECENT_FILE_MENU_PREFIX: &str = "recent_file:";
+
+/**
+ * Build the File > Open Recent submenu from AppState.recent_files
+ * Each entry's id carries the file path so the menu handler can emit it directly
+ */
+fn create_open_recent_menu<R: tauri::Runtime>(
+    handle: &AppHandle<R>,
+    lang: &str,
+) -> tauri::Result<Submenu<R>> {
+    let recent: Vec<String> = handle
+        .try_state::<AppState>()
+        .and_then(|state| {
+            state
+                .recent_files
+                .lock()
+                .ok()
+                .map(|r| r.iter().cloned().collect())
+        })
+        .unwrap_or_default();
 
-    let ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or("").to_lowercase();
-    if ext != "md" && ext != "markdown" {
-        return None;
+    let submenu = Submenu::new(handle, get_label(lang, "file_open_recent"), true)?;
+
+    if recent.is_empty() {
+        let empty_item = MenuItem::with_id(
+            handle,
+            "file_no_recent",
+            get_label(lang, "file_no_recent"),
+            false,
+            None::<&str>,
+        )?;
+        submenu.append(&empty_item)?;
+    } else {
+        for path in &recent {
+            let item = MenuItem::with_id(
+                handle,
+                format!("{}{}", RECENT_FILE_MENU_PREFIX, path),
+                path,
+                true,
+                None::<&str>,
+            )?;
+            submenu.append(&item)?;
+        }
     }
 
-    Some(path.to_string_lossy().to_string())
-}
+    let clear_item = MenuItem::with_id(
+        handle,
+        "file_clear_recent",
+        get_label(lang, "file_clear_recent"),
+        !recent.is_empty(),
+        None::<&str>,
+    )?;
+    submenu.append(&PredefinedMenuItem::separator(handle)?)?;
+    submenu.append(&clear_item)?;
 
-fn collect_open_paths<I>(args: I) -> Vec<String>
-where
-    I: IntoIterator<Item = String>,
-{
-    args.into_iter()
-        .filter_map(|arg| normalize_open_path(&arg))
-        .collect()
+    Ok(submenu)
 }
 
-fn queue_open_files(app: &AppHandle, paths: Vec<String>) {
-    if paths.is_empty() {
-        return;
-    }
+const RECENT_FOLDER_MENU_PREFIX: &str = "recent_folder:";
 
-    if let Ok(mut pending) = app.state::<AppState>().pending_open_files.lock() {
-        for path in &paths {
-            if !pending.contains(path) {
-                pending.push_back(path.clone());
-            }
+/**
+ * Build the File > Open Recent Folder submenu from AppState.recent_folders
+ * Like Open Recent, each entry's id carries the folder path
+ */
+fn create_open_recent_folder_menu<R: tauri::Runtime>(
+    handle: &AppHandle<R>,
+    lang: &str,
+) -> tauri::Result<Submenu<R>> {
+    let recent: Vec<String> = handle
+        .try_state::<AppState>()
+        .and_then(|state| {
+            state
+                .recent_folders
+                .lock()
+                .ok()
+                .map(|r| r.iter().cloned().collect())
+        })
+        .unwrap_or_default();
+
+    let submenu = Submenu::new(handle, get_label(lang, "file_open_recent_folder"), true)?;
+
+    if recent.is_empty() {
+        let empty_item = MenuItem::with_id(
+            handle,
+            "file_no_recent_folders",
+            get_label(lang, "file_no_recent_folders"),
+            false,
+            None::<&str>,
+        )?;
+        submenu.append(&empty_item)?;
+    } else {
+        for path in &recent {
+            let item = MenuItem::with_id(
+                handle,
+                format!("{}{}", RECENT_FOLDER_MENU_PREFIX, path),
+                path,
+                true,
+                None::<&str>,
+            )?;
+            submenu.append(&item)?;
         }
     }
 
-    for path in paths {
-        let _ = app.emit("open-file", path);
+    Ok(submenu)
+}
+
+/**
+ * Rebuild the app menu from the current language and recent files
+ * Tauri menus are built up front, so dynamic entries require a full rebuild
+ */
+fn refresh_app_menu(app: &AppHandle) {
+    let lang = app
+        .state::<AppState>()
+        .language
+        .lock()
+        .map(|l| l.clone())
+        .unwrap_or_else(|_| "en".to_string());
+    if let Ok(menu) = create_app_menu(app, &lang) {
+        let _ = app.set_menu(menu);
     }
 }
 
 fn create_app_menu<R: tauri::Runtime>(handle: &AppHandle<R>, lang: &str) -> tauri::Result<Menu<R>> {
     let menu = Menu::new(handle)?;
+    let shortcuts = handle
+        .try_state::<AppState>()
+        .and_then(|state| state.shortcuts.lock().ok().map(|s| s.clone()))
+        .unwrap_or_default();
+    let format_states = handle
+        .try_state::<AppState>()
+        .and_then(|state| state.format_states.lock().ok().map(|s| s.clone()))
+        .unwrap_or_default();
+    let format_checked = |id: &str| format_states.get(id).copied().unwrap_or(false);
 
     // macOS App Name Menu — the leftmost slot (app name is filled automatically by macOS)
     #[cfg(target_os = "macos")]
@@ -771,32 +2029,150 @@ fn create_app_menu<R: tauri::Runtime>(handle: &AppHandle<R>, lang: &str) -> taur
     }
 
     // File Menu
-    let new_item = MenuItem::with_id(handle, "file_new", get_label(lang, "file_new"), true, Some("CmdOrCtrl+N"))?;
-    let open_item = MenuItem::with_id(handle, "file_open", get_label(lang, "file_open"), true, Some("CmdOrCtrl+O"))?;
-    let save_item = MenuItem::with_id(handle, "file_save", get_label(lang, "file_save"), true, Some("CmdOrCtrl+S"))?;
-    let save_as_item = MenuItem::with_id(handle, "file_save_as", get_label(lang, "file_save_as"), true, Some("CmdOrCtrl+Shift+S"))?;
-    let close_document_item = MenuItem::with_id(handle, "file_close_document", get_label(lang, "file_close_document"), true, Some("CmdOrCtrl+W"))?;
-
-    let import_docx_item = MenuItem::with_id(handle, "file_import_docx", get_label(lang, "file_import_docx"), true, None::<&str>)?;
-    let import_xlsx_item = MenuItem::with_id(handle, "file_import_xlsx", get_label(lang, "file_import_xlsx"), true, None::<&str>)?;
-    let import_pdf_item  = MenuItem::with_id(handle, "file_import_pdf",  get_label(lang, "file_import_pdf"),  true, None::<&str>)?;
-    let import_pptx_item = MenuItem::with_id(handle, "file_import_pptx", get_label(lang, "file_import_pptx"), true, None::<&str>)?;
+    let new_item = MenuItem::with_id(
+        handle,
+        "file_new",
+        get_label(lang, "file_new"),
+        true,
+        shortcuts.get("file_new"),
+    )?;
+    let open_item = MenuItem::with_id(
+        handle,
+        "file_open",
+        get_label(lang, "file_open"),
+        true,
+        shortcuts.get("file_open"),
+    )?;
+    let save_item = MenuItem::with_id(
+        handle,
+        "file_save",
+        get_label(lang, "file_save"),
+        true,
+        shortcuts.get("file_save"),
+    )?;
+    let save_as_item = MenuItem::with_id(
+        handle,
+        "file_save_as",
+        get_label(lang, "file_save_as"),
+        true,
+        shortcuts.get("file_save_as"),
+    )?;
+    let close_document_item = MenuItem::with_id(
+        handle,
+        "file_close_document",
+        get_label(lang, "file_close_document"),
+        true,
+        shortcuts.get("file_close_document"),
+    )?;
+
+    let recent_submenu = create_open_recent_menu(handle, lang)?;
+    let recent_folder_submenu = create_open_recent_folder_menu(handle, lang)?;
+
+    let import_any_item = MenuItem::with_id(
+        handle,
+        "file_import_any",
+        get_label(lang, "file_import_any"),
+        true,
+        shortcuts.get("file_import_any"),
+    )?;
+    let import_docx_item = MenuItem::with_id(
+        handle,
+        "file_import_docx",
+        get_label(lang, "file_import_docx"),
+        true,
+        None::<&str>,
+    )?;
+    let import_xlsx_item = MenuItem::with_id(
+        handle,
+        "file_import_xlsx",
+        get_label(lang, "file_import_xlsx"),
+        true,
+        None::<&str>,
+    )?;
+    let import_pdf_item = MenuItem::with_id(
+        handle,
+        "file_import_pdf",
+        get_label(lang, "file_import_pdf"),
+        true,
+        None::<&str>,
+    )?;
+    let import_pptx_item = MenuItem::with_id(
+        handle,
+        "file_import_pptx",
+        get_label(lang, "file_import_pptx"),
+        true,
+        None::<&str>,
+    )?;
+    let import_html_item = MenuItem::with_id(
+        handle,
+        "file_import_html",
+        get_label(lang, "file_import_html"),
+        true,
+        None::<&str>,
+    )?;
+    let import_rtf_item = MenuItem::with_id(
+        handle,
+        "file_import_rtf",
+        get_label(lang, "file_import_rtf"),
+        true,
+        None::<&str>,
+    )?;
+    let import_odt_item = MenuItem::with_id(
+        handle,
+        "file_import_odt",
+        get_label(lang, "file_import_odt"),
+        true,
+        None::<&str>,
+    )?;
     let import_submenu = Submenu::with_items(
         handle,
         get_label(lang, "file_import"),
         true,
-        &[&import_docx_item, &import_xlsx_item, &import_pdf_item, &import_pptx_item],
+        &[
+            &import_docx_item,
+            &import_xlsx_item,
+            &import_pdf_item,
+            &import_pptx_item,
+            &import_html_item,
+            &import_rtf_item,
+            &import_odt_item,
+        ],
     )?;
 
-    let export_docx_item = MenuItem::with_id(handle, "file_export_docx", get_label(lang, "file_export_docx"), true, None::<&str>)?;
-    let export_xlsx_item = MenuItem::with_id(handle, "file_export_xlsx", get_label(lang, "file_export_xlsx"), true, None::<&str>)?;
-    let export_pdf_item  = MenuItem::with_id(handle, "file_export_pdf",  get_label(lang, "file_export_pdf"),  true, None::<&str>)?;
-    let export_pptx_item = MenuItem::with_id(handle, "file_export_pptx", get_label(lang, "file_export_pptx"), true, None::<&str>)?;
-    let export_submenu = Submenu::with_items(
+    // Ids stay `file_export_<format>`; the menu handler emits the format after the prefix
+    let export_item = |format: &str| {
+        MenuItem::with_id(
+            handle,
+            format!("file_export_{}", format),
+            get_label(lang, &format!("file_export_as_{}", format)),
+            true,
+            None::<&str>,
+        )
+    };
+    let export_pdf_item = export_item("pdf")?;
+    let export_docx_item = export_item("docx")?;
+    let export_xlsx_item = export_item("xlsx")?;
+    let export_pptx_item = export_item("pptx")?;
+    let export_html_item = export_item("html")?;
+    let export_epub_item = export_item("epub")?;
+    let export_odt_item = export_item("odt")?;
+    let export_tex_item = export_item("tex")?;
+    let export_submenu = Submenu::with_id_and_items(
         handle,
-        get_label(lang, "file_export"),
+        "file_export_as",
+        get_label(lang, "file_export_as"),
         true,
-        &[&export_docx_item, &export_xlsx_item, &export_pdf_item, &export_pptx_item],
+        &[
+            &export_pdf_item,
+            &export_docx_item,
+            &export_xlsx_item,
+            &export_pptx_item,
+            &export_html_item,
+            &PredefinedMenuItem::separator(handle)?,
+            &export_epub_item,
+            &export_odt_item,
+            &export_tex_item,
+        ],
     )?;
 
     let file_menu = Submenu::with_items(
@@ -806,11 +2182,14 @@ fn create_app_menu<R: tauri::Runtime>(handle: &AppHandle<R>, lang: &str) -> taur
         &[
             &new_item,
             &open_item,
+            &recent_submenu,
+            &recent_folder_submenu,
             &PredefinedMenuItem::separator(handle)?,
             &save_item,
             &save_as_item,
             &close_document_item,
             &PredefinedMenuItem::separator(handle)?,
+            &import_any_item,
             &import_submenu,
             &export_submenu,
         ],
@@ -818,15 +2197,50 @@ fn create_app_menu<R: tauri::Runtime>(handle: &AppHandle<R>, lang: &str) -> taur
     menu.append(&file_menu)?;
 
     // Edit Menu
-    let find_item = MenuItem::with_id(handle, "edit_find", get_label(lang, "edit_find"), true, Some("CmdOrCtrl+F"))?;
-    let find_in_files_item = MenuItem::with_id(handle, "edit_find_in_files", get_label(lang, "edit_find_in_files"), true, Some("CmdOrCtrl+Shift+F"))?;
+    // Undo/redo go through the editor's own history (the predefined items are
+    // macOS-only); clipboard items stay predefined for native behavior
+    let undo_item = MenuItem::with_id(
+        handle,
+        "edit_undo",
+        get_label(lang, "edit_undo"),
+        true,
+        shortcuts.get("edit_undo"),
+    )?;
+    let redo_item = MenuItem::with_id(
+        handle,
+        "edit_redo",
+        get_label(lang, "edit_redo"),
+        true,
+        shortcuts.get("edit_redo"),
+    )?;
+    let find_item = MenuItem::with_id(
+        handle,
+        "edit_find",
+        get_label(lang, "edit_find"),
+        true,
+        shortcuts.get("edit_find"),
+    )?;
+    let find_replace_item = MenuItem::with_id(
+        handle,
+        "edit_find_replace",
+        get_label(lang, "edit_find_replace"),
+        true,
+        shortcuts.get("edit_find_replace"),
+    )?;
+    let find_in_files_item = MenuItem::with_id(
+        handle,
+        "edit_find_in_files",
+        get_label(lang, "edit_find_in_files"),
+        true,
+        shortcuts.get("edit_find_in_files"),
+    )?;
     let edit_menu = Submenu::with_items(
         handle,
         get_label(lang, "edit"),
         true,
         &[
-            &PredefinedMenuItem::undo(handle, Some(&get_label(lang, "edit_undo")))?,
-            &PredefinedMenuItem::redo(handle, Some(&get_label(lang, "edit_redo")))?,
+            &undo_item,
+            &redo_item,
             &PredefinedMenuItem::separator(handle)?,
             &PredefinedMenuItem::cut(handle, Some(&get_label(lang, "edit_cut")))?,
             &PredefinedMenuItem::copy(handle, Some(&get_label(lang, "edit_copy")))?,
@@ -835,28 +2249,139 @@ fn create_app_menu<R: tauri::Runtime>(handle: &AppHandle<R>, lang: &str) -> taur
             &PredefinedMenuItem::select_all(handle, Some(&get_label(lang, "edit_select_all")))?,
             &PredefinedMenuItem::separator(handle)?,
             &find_item,
+            &find_replace_item,
             &find_in_files_item,
         ],
     )?;
     menu.append(&edit_menu)?;
 
     // Format Menu
-    let bold_item = MenuItem::with_id(handle, "editor_bold", get_label(lang, "format_bold"), true, Some("CmdOrCtrl+B"))?;
-    let italic_item = MenuItem::with_id(handle, "editor_italic", get_label(lang, "format_italic"), true, Some("CmdOrCtrl+I"))?;
-    let strike_item = MenuItem::with_id(handle, "editor_strike", get_label(lang, "format_strike"), true, Some("CmdOrCtrl+Shift+X"))?;
-    let inline_code_item = MenuItem::with_id(handle, "editor_inline_code", get_label(lang, "format_inline_code"), true, Some("CmdOrCtrl+Shift+C"))?;
-    let paragraph_item = MenuItem::with_id(handle, "editor_paragraph", get_label(lang, "format_paragraph"), true, None::<&str>)?;
-    let heading_1_item = MenuItem::with_id(handle, "editor_heading_1", get_label(lang, "format_heading_1"), true, Some("CmdOrCtrl+Option+1"))?;
-    let heading_2_item = MenuItem::with_id(handle, "editor_heading_2", get_label(lang, "format_heading_2"), true, Some("CmdOrCtrl+Option+2"))?;
-    let heading_3_item = MenuItem::with_id(handle, "editor_heading_3", get_label(lang, "format_heading_3"), true, Some("CmdOrCtrl+Option+3"))?;
-    let heading_4_item = MenuItem::with_id(handle, "editor_heading_4", get_label(lang, "format_heading_4"), true, Some("CmdOrCtrl+Option+4"))?;
-    let heading_5_item = MenuItem::with_id(handle, "editor_heading_5", get_label(lang, "format_heading_5"), true, Some("CmdOrCtrl+Option+5"))?;
-    let heading_6_item = MenuItem::with_id(handle, "editor_heading_6", get_label(lang, "format_heading_6"), true, Some("CmdOrCtrl+Option+6"))?;
-    let bullet_list_item = MenuItem::with_id(handle, "editor_bullet_list", get_label(lang, "format_bullet_list"), true, Some("CmdOrCtrl+Shift+8"))?;
-    let ordered_list_item = MenuItem::with_id(handle, "editor_ordered_list", get_label(lang, "format_ordered_list"), true, Some("CmdOrCtrl+Shift+7"))?;
-    let blockquote_item = MenuItem::with_id(handle, "editor_blockquote", get_label(lang, "format_blockquote"), true, None::<&str>)?;
-    let code_block_item = MenuItem::with_id(handle, "editor_code_block", get_label(lang, "format_code_block"), true, None::<&str>)?;
-    let horizontal_rule_item = MenuItem::with_id(handle, "editor_horizontal_rule", get_label(lang, "format_horizontal_rule"), true, None::<&str>)?;
+    let bold_item = CheckMenuItem::with_id(
+        handle,
+        "editor_bold",
+        get_label(lang, "format_bold"),
+        true,
+        format_checked("editor_bold"),
+        shortcuts.get("editor_bold"),
+    )?;
+    let italic_item = CheckMenuItem::with_id(
+        handle,
+        "editor_italic",
+        get_label(lang, "format_italic"),
+        true,
+        format_checked("editor_italic"),
+        shortcuts.get("editor_italic"),
+    )?;
+    let strike_item = CheckMenuItem::with_id(
+        handle,
+        "editor_strike",
+        get_label(lang, "format_strike"),
+        true,
+        format_checked("editor_strike"),
+        shortcuts.get("editor_strike"),
+    )?;
+    let inline_code_item = CheckMenuItem::with_id(
+        handle,
+        "editor_inline_code",
+        get_label(lang, "format_inline_code"),
+        true,
+        format_checked("editor_inline_code"),
+        shortcuts.get("editor_inline_code"),
+    )?;
+    let paragraph_item = MenuItem::with_id(
+        handle,
+        "editor_paragraph",
+        get_label(lang, "format_paragraph"),
+        true,
+        shortcuts.get("editor_paragraph"),
+    )?;
+    let heading_1_item = MenuItem::with_id(
+        handle,
+        "editor_heading_1",
+        get_label(lang, "format_heading_1"),
+        true,
+        shortcuts.get("editor_heading_1"),
+    )?;
+    let heading_2_item = MenuItem::with_id(
+        handle,
+        "editor_heading_2",
+        get_label(lang, "format_heading_2"),
+        true,
+        shortcuts.get("editor_heading_2"),
+    )?;
+    let heading_3_item = MenuItem::with_id(
+        handle,
+        "editor_heading_3",
+        get_label(lang, "format_heading_3"),
+        true,
+        shortcuts.get("editor_heading_3"),
+    )?;
+    let heading_4_item = MenuItem::with_id(
+        handle,
+        "editor_heading_4",
+        get_label(lang, "format_heading_4"),
+        true,
+        shortcuts.get("editor_heading_4"),
+    )?;
+    let heading_5_item = MenuItem::with_id(
+        handle,
+        "editor_heading_5",
+        get_label(lang, "format_heading_5"),
+        true,
+        shortcuts.get("editor_heading_5"),
+    )?;
+    let heading_6_item = MenuItem::with_id(
+        handle,
+        "editor_heading_6",
+        get_label(lang, "format_heading_6"),
+        true,
+        shortcuts.get("editor_heading_6"),
+    )?;
+    let bullet_list_item = CheckMenuItem::with_id(
+        handle,
+        "editor_bullet_list",
+        get_label(lang, "format_bullet_list"),
+        true,
+        format_checked("editor_bullet_list"),
+        shortcuts.get("editor_bullet_list"),
+    )?;
+    let ordered_list_item = CheckMenuItem::with_id(
+        handle,
+        "editor_ordered_list",
+        get_label(lang, "format_ordered_list"),
+        true,
+        format_checked("editor_ordered_list"),
+        shortcuts.get("editor_ordered_list"),
+    )?;
+    let blockquote_item = CheckMenuItem::with_id(
+        handle,
+        "editor_blockquote",
+        get_label(lang, "format_blockquote"),
+        true,
+        format_checked("editor_blockquote"),
+        shortcuts.get("editor_blockquote"),
+    )?;
+    let code_block_item = MenuItem::with_id(
+        handle,
+        "editor_code_block",
+        get_label(lang, "format_code_block"),
+        true,
+        shortcuts.get("editor_code_block"),
+    )?;
+    let horizontal_rule_item = MenuItem::with_id(
+        handle,
+        "editor_horizontal_rule",
+        get_label(lang, "format_horizontal_rule"),
+        true,
+        shortcuts.get("editor_horizontal_rule"),
+    )?;
+    let clean_up_item = MenuItem::with_id(
+        handle,
+        "format_clean_up",
+        get_label(lang, "format_clean_up"),
+        true,
+        shortcuts.get("format_clean_up"),
+    )?;
 
     let text_menu = Submenu::with_items(
         handle,
@@ -886,32 +2411,82 @@ fn create_app_menu<R: tauri::Runtime>(handle: &AppHandle<R>, lang: &str) -> taur
         handle,
         get_label(lang, "format"),
         true,
-        &[&text_menu, &heading_menu, &list_menu, &block_menu],
+        &[
+            &text_menu,
+            &heading_menu,
+            &list_menu,
+            &block_menu,
+            &PredefinedMenuItem::separator(handle)?,
+            &clean_up_item,
+        ],
     )?;
     menu.append(&format_menu)?;
 
     // View Menu
-    let theme_github_light = MenuItem::with_id(handle, "view_theme_github_light", "GitHub Light", true, None::<&str>)?;
-    let theme_github_dark = MenuItem::with_id(handle, "view_theme_github_dark", "GitHub Dark", true, None::<&str>)?;
-    let theme_dracula = MenuItem::with_id(handle, "view_theme_dracula", "Dracula", true, None::<&str>)?;
-    let theme_nord_light = MenuItem::with_id(handle, "view_theme_nord_light", "Nord Light", true, None::<&str>)?;
-    let theme_nord_dark = MenuItem::with_id(handle, "view_theme_nord_dark", "Nord Dark", true, None::<&str>)?;
-    let theme_solarized_light = MenuItem::with_id(handle, "view_theme_solarized_light", "Solarized Light", true, None::<&str>)?;
-    let theme_solarized_dark = MenuItem::with_id(handle, "view_theme_solarized_dark", "Solarized Dark", true, None::<&str>)?;
-    let theme_menu = Submenu::with_items(
+    let current_theme = handle
+        .try_state::<AppState>()
+        .and_then(|state| state.theme.lock().ok().map(|t| t.clone()))
+        .unwrap_or_else(|| DEFAULT_THEME.to_string());
+    let theme_menu = Submenu::new(handle, get_label(lang, "view_theme"), true)?;
+    for (id, label) in BUILTIN_THEMES {
+        let item = CheckMenuItem::with_id(
+            handle,
+            themes::menu_id(id),
+            *label,
+            true,
+            *id == current_theme,
+            None::<&str>,
+        )?;
+        theme_menu.append(&item)?;
+    }
+    let custom_themes = handle
+        .try_state::<AppState>()
+        .and_then(|state| state.custom_themes.lock().ok().map(|t| t.clone()))
+        .unwrap_or_default();
+    if !custom_themes.is_empty() {
+        theme_menu.append(&PredefinedMenuItem::separator(handle)?)?;
+        for theme in &custom_themes {
+            let item = CheckMenuItem::with_id(
+                handle,
+                themes::menu_id(&theme.id),
+                &theme.label,
+                true,
+                theme.id == current_theme,
+                None::<&str>,
+            )?;
+            theme_menu.append(&item)?;
+        }
+    }
+
+    let lang_en_item = CheckMenuItem::with_id(
         handle,
-        get_label(lang, "view_theme"),
+        "lang_en",
+        get_label(lang, "lang_en"),
         true,
-        &[&theme_github_light, &theme_github_dark, &theme_dracula, &theme_nord_light, &theme_nord_dark, &theme_solarized_light, &theme_solarized_dark],
+        lang == "en",
+        None::<&str>,
+    )?;
+    let lang_zh_item = CheckMenuItem::with_id(
+        handle,
+        "lang_zh",
+        get_label(lang, "lang_zh"),
+        true,
+        lang == "zh",
+        None::<&str>,
+    )?;
+    let lang_ja_item = CheckMenuItem::with_id(
+        handle,
+        "lang_ja",
+        get_label(lang, "lang_ja"),
+        true,
+        lang == "ja",
+        None::<&str>,
     )?;
-
-    let lang_en_item = CheckMenuItem::with_id(handle, "lang_en", get_label(lang, "lang_en"), true, lang == "en", None::<&str>)?;
-    let lang_zh_item = CheckMenuItem::with_id(handle, "lang_zh", get_label(lang, "lang_zh"), true, lang == "zh", None::<&str>)?;
     let language_menu = Submenu::with_items(
         handle,
         get_label(lang, "view_language"),
         true,
-        &[&lang_en_item, &lang_zh_item],
+        &[&lang_en_item, &lang_zh_item, &lang_ja_item],
     )?;
 
     let source_code_item = CheckMenuItem::with_id(
@@ -920,7 +2495,20 @@ fn create_app_menu<R: tauri::Runtime>(handle: &AppHandle<R>, lang: &str) -> taur
         get_label(lang, "view_source_code"),
         true,
         false,
-        Some("CmdOrCtrl+Alt+S"),
+        shortcuts.get("view_source_code"),
+    )?;
+
+    let read_only = handle
+        .try_state::<AppState>()
+        .and_then(|state| state.read_only.lock().ok().map(|r| *r))
+        .unwrap_or(false);
+    let read_only_item = CheckMenuItem::with_id(
+        handle,
+        "view_read_only",
+        get_label(lang, "view_read_only"),
+        true,
+        read_only,
+        None::<&str>,
     )?;
 
     let view_menu = Submenu::with_items(
@@ -929,6 +2517,7 @@ fn create_app_menu<R: tauri::Runtime>(handle: &AppHandle<R>, lang: &str) -> taur
         true,
         &[
             &source_code_item,
+            &read_only_item,
             &PredefinedMenuItem::separator(handle)?,
             &theme_menu,
             &language_menu,
@@ -1007,6 +2596,15 @@ fn main() {
         }
     };
     
+    // Theme chosen in an earlier session; without one the frontend's own choice stands
+    let saved_settings = UserSettings::load().ok().flatten().unwrap_or_default();
+    let saved_custom_themes = saved_settings.custom_themes;
+    let saved_theme = saved_settings
+        .theme
+        .filter(|theme| themes::is_known(theme, &saved_custom_themes));
+    // Folders deleted or unmounted since the last session are dropped
+    let saved_recent_folders = existing_folders(saved_settings.recent_folders);
+
     let app = tauri::Builder::default()
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_fs::init())
@@ -1015,45 +2613,93 @@ fn main() {
             let paths = collect_open_paths(argv);
             queue_open_files(app, paths);
         }))
-        .manage(AppState::new(default_language.clone()))
+        // Registers the markdown:// scheme; links arrive as launch arguments or macOS Opened events
+        .plugin(tauri_plugin_deep_link::init())
+        .manage(AppState::new(
+            default_language.clone(),
+            load_shortcuts(),
+            saved_theme
+                .clone()
+                .unwrap_or_else(|| DEFAULT_THEME.to_string()),
+            saved_custom_themes,
+            saved_recent_folders,
+        ))
         .setup(|app| {
             let args = std::env::args().skip(1).collect::<Vec<_>>();
             let paths = collect_open_paths(args);
             queue_open_files(&app.handle(), paths);
+
+            // Installed builds register the scheme at install time; do it at runtime for the rest
+            #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+            {
+                use tauri_plugin_deep_link::DeepLinkExt;
+                let _ = app.deep_link().register_all();
+            }
             Ok(())
         })
+        .on_window_event(|window, event| {
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                let dirty = window
+                    .state::<AppState>()
+                    .dirty_windows
+                    .lock()
+                    .map(|windows| windows.contains(window.label()))
+                    .unwrap_or(false);
+                if dirty {
+                    // The frontend offers to save, then closes the window again once it's clean
+                    api.prevent_close();
+                    let _ = window.emit_to(window.label(), "confirm-close", false);
+                }
+            }
+        })
         .menu(move |handle| {
             // Menu starts with detected system language
             create_app_menu(handle, &default_language)
         })
         .on_menu_event(|app, event| {
-            // ... (rest of menu event handler remains the same)
             if event.id() == "file_new" {
-                let _ = app.emit("menu-new-file", ());
+                emit_menu_event(app, "new-file", None);
             } else if event.id() == "file_open" {
-                let _ = app.emit("menu-open-file", ());
+                emit_menu_event(app, "open-file", None);
             } else if event.id() == "file_save" {
-                let _ = app.emit("menu-save-file", ());
+                emit_menu_event(app, "save-file", None);
             } else if event.id() == "file_save_as" {
-                let _ = app.emit("menu-save-as", ());
+                emit_menu_event(app, "save-as", None);
             } else if event.id() == "file_close_document" {
-                let _ = app.emit("menu-close-document", ());
+                let handle = app.clone();
+                tauri::async_runtime::spawn(async move { flush_all_autosaves(&handle).await });
+                emit_menu_event(app, "close-document", None);
+            } else if event.id() == "file_clear_recent" {
+                if let Err(e) = clear_recent(app) {
+                    println!("❌ Failed to clear recent files: {}", e);
+                }
+            } else if let Some(path) = event.id().0.strip_prefix(RECENT_FILE_MENU_PREFIX) {
+                let _ = app.emit("open-file", path.to_string());
+                emit_menu_action(app, "open-recent", Some(path.to_string()));
+            } else if let Some(path) = event.id().0.strip_prefix(RECENT_FOLDER_MENU_PREFIX) {
+                emit_menu_action(app, "open-recent-folder", Some(path.to_string()));
             } else if event.id() == "view_source_code" {
-                let _ = app.emit("menu-toggle-editor-mode", ());
-            } else if event.id() == "view_theme_github_light" {
-                let _ = app.emit("menu-set-theme", "github-light");
-            } else if event.id() == "view_theme_github_dark" {
-                let _ = app.emit("menu-set-theme", "github-dark");
-            } else if event.id() == "view_theme_dracula" {
-                let _ = app.emit("menu-set-theme", "dracula");
-            } else if event.id() == "view_theme_nord_light" {
-                let _ = app.emit("menu-set-theme", "nord-light");
-            } else if event.id() == "view_theme_nord_dark" {
-                let _ = app.emit("menu-set-theme", "nord-dark");
-            } else if event.id() == "view_theme_solarized_light" {
-                let _ = app.emit("menu-set-theme", "solarized-light");
-            } else if event.id() == "view_theme_solarized_dark" {
-                let _ = app.emit("menu-set-theme", "solarized-dark");
+                emit_menu_event(app, "toggle-editor-mode", None);
+            } else if event.id() == "view_read_only" {
+                let state = app.state::<AppState>();
+                let read_only = match state.read_only.lock() {
+                    Ok(mut read_only) => {
+                        *read_only = !*read_only;
+                        *read_only
+                    }
+                    Err(_) => return,
+                };
+                let _ =
+                    update_menu_item_state(app.clone(), "view_read_only".to_string(), read_only);
+                let _ = app.emit("menu-toggle-readonly", read_only);
+                emit_menu_action(app, "toggle-readonly", Some(read_only.to_string()));
+            } else if let Some(theme) =
+                themes::theme_for_menu_id(&event.id().0, &custom_themes(app))
+            {
+                if let Err(e) = apply_theme(app, &theme) {
+                    println!("❌ Failed to save theme: {}", e);
+                }
+                emit_menu_event(app, "set-theme", Some(theme));
             } else if event.id() == "lang_en" {
                 println!("🌐 User selected: English");
                 // Save preference to persistent storage
@@ -1070,6 +2716,7 @@ fn main() {
                 }
                 // Notify frontend about the language change
                 let _ = app.emit("language-changed", "en");
+                emit_menu_action(app, "set-language", Some("en".to_string()));
                 println!("✅ Language changed to: English");
             } else if event.id() == "lang_zh" {
                 println!("🌐 User selected: Chinese");
@@ -1087,7 +2734,30 @@ fn main() {
                 }
                 // Notify frontend about the language change
                 let _ = app.emit("language-changed", "zh");
+                emit_menu_action(app, "set-language", Some("zh".to_string()));
                 println!("✅ Language changed to: Chinese");
+            } else if event.id() == "lang_ja" {
+                println!("🌐 User selected: Japanese");
+                // Save preference to persistent storage
+                if let Err(e) = save_language_to_storage("ja") {
+                    println!("❌ Failed to save language preference: {}", e);
+                }
+                // Update menu directly
+                if let Ok(menu) = create_app_menu(&app, "ja") {
+                    let _ = app.set_menu(menu);
+                }
+                // Update backend state
+                if let Ok(mut lang) = app.state::<AppState>().language.lock() {
+                    *lang = "ja".to_string();
+                }
+                // Notify frontend about the language change
+                let _ = app.emit("language-changed", "ja");
+                emit_menu_action(app, "set-language", Some("ja".to_string()));
+                println!("✅ Language changed to: Japanese");
+            } else if event.id() == "edit_undo" {
+                emit_editor_command(app, "undo", None);
+            } else if event.id() == "edit_redo" {
+                emit_editor_command(app, "redo", None);
             } else if event.id() == "editor_bold" {
                 emit_editor_command(app, "bold", None);
             } else if event.id() == "editor_italic" {
@@ -1120,45 +2790,122 @@ fn main() {
                 emit_editor_command(app, "code_block", None);
             } else if event.id() == "editor_horizontal_rule" {
                 emit_editor_command(app, "horizontal_rule", None);
+            } else if event.id() == "format_clean_up" {
+                emit_menu_event(app, "clean-up-document", None);
             } else if event.id() == "edit_find" {
-                let _ = app.emit("menu-find", ());
+                emit_menu_event(app, "find", None);
+            } else if event.id() == "edit_find_replace" {
+                emit_menu_event(app, "find-replace", None);
             } else if event.id() == "edit_find_in_files" {
-                let _ = app.emit("menu-find-in-files", ());
+                emit_menu_event(app, "find-in-files", None);
             } else if event.id().0.starts_with("file_import_") {
                 let fmt = event.id().0.strip_prefix("file_import_").unwrap_or("").to_string();
-                let _ = app.emit("menu-import", fmt);
+                emit_menu_event(app, "import", Some(fmt));
             } else if event.id().0.starts_with("file_export_") {
                 let fmt = event.id().0.strip_prefix("file_export_").unwrap_or("").to_string();
-                let _ = app.emit("menu-export", fmt);
+                emit_menu_event(app, "export", Some(fmt));
             }
         })
         .invoke_handler(tauri::generate_handler![
             read_markdown_file,
+            read_markdown_file_range,
+            get_line_ending,
+            get_file_encoding,
             save_markdown_file,
+            save_markdown_file_with_backup,
+            autosave,
+            flush_autosaves,
+            set_autosave_delay,
+            set_document_dirty,
+            quit_app,
             list_directory,
+            canonicalize_path,
             get_recent_files,
             add_recent_file,
+            set_max_recent_files,
+            clear_recent_files,
+            get_recent_folders,
+            add_recent_folder,
             create_file,
+            save_pasted_image,
             delete_file,
+            trash_file,
             rename_file,
+            rename_with_link_update,
+            reveal_in_file_manager,
+            set_allowed_roots,
             file_exists,
+            get_file_info,
+            watch_file,
+            unwatch_file,
             update_menu_item_state,
             enable_menu_item,
+            update_format_state,
             import_document,
+            import_to_markdown,
+            cancel_import,
             export_document,
+            export_folder,
+            convert_batch,
             take_pending_open_files,
+            frontend_ready,
             get_os_platform,
+            get_build_info,
+            get_theme,
+            set_theme,
+            register_theme,
+            get_read_only,
+            set_read_only,
             get_system_locale,
             get_language,
             set_language,
             get_user_settings,
             save_language_preference,
             search_in_files,
+            search_in_directory,
+            find_files,
+            get_shortcuts,
+            set_shortcut,
+            document_stats,
+            generate_toc,
+            document_outline,
+            check_links,
+            diff_markdown,
+            markdown_to_plaintext,
+            markdown_to_latex,
+            document_has_tables,
+            format_markdown,
+            lint_markdown,
+            parse_front_matter,
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application");
 
-    app.run(|app_handle, event| {
+    app.run(move |app_handle, event| {
+        if let tauri::RunEvent::ExitRequested { .. }
+        | tauri::RunEvent::WindowEvent {
+            event: tauri::WindowEvent::CloseRequested { .. },
+            ..
+        } = &event
+        {
+            tauri::async_runtime::block_on(flush_all_autosaves(app_handle));
+        }
+
+        // Quitting (Cmd+Q, the Quit menu item) skips the windows' close events,
+        // so ask the first window with unsaved changes; it calls `quit_app` when done
+        if let tauri::RunEvent::ExitRequested { api, .. } = &event {
+            let dirty_window = app_handle
+                .state::<AppState>()
+                .dirty_windows
+                .lock()
+                .ok()
+                .and_then(|windows| windows.iter().next().cloned());
+            if let Some(label) = dirty_window {
+                api.prevent_exit();
+                let _ = app_handle.emit_to(label.as_str(), "confirm-close", true);
+            }
+        }
+
         if let tauri::RunEvent::Ready = event {
             // Emit platform info as soon as the app is ready
             let platform = if cfg!(target_os = "macos") {
@@ -1171,23 +2918,64 @@ fn main() {
                 "unknown"
             };
             let _ = app_handle.emit("init-platform", platform);
+
+            // Restore the saved theme so the UI matches the checked menu entry
+            if let Some(theme) = &saved_theme {
+                let _ = app_handle.emit("menu-set-theme", theme);
+            }
         }
 
         #[cfg(target_os = "macos")]
         if let tauri::RunEvent::Opened { urls } = event {
             let paths: Vec<String> = urls
                 .into_iter()
-                .filter_map(|url| {
-                    if url.scheme() == "file" {
-                        url.to_file_path()
-                            .ok()
-                            .map(|p| p.to_string_lossy().to_string())
-                    } else {
-                        None
-                    }
-                })
+                .filter_map(|url| normalize_open_path(url.as_str()))
                 .collect();
             queue_open_files(app_handle, paths);
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_only_blocks_saving() {
+        let state = AppState::new(
+            "en".to_string(),
+            Shortcuts::default(),
+            DEFAULT_THEME.to_string(),
+            Vec::new(),
+            VecDeque::new(),
+        );
+        assert!(check_writable(&state).is_ok());
+
+        *state.read_only.lock().unwrap() = true;
+        let err = check_writable(&state).unwrap_err();
+        assert_eq!(err.code(), "read_only");
+    }
+
+    #[test]
+    fn test_push_recent_dedupes_and_caps() {
+        let mut recent = VecDeque::new();
+        for path in ["/a", "/b", "/c", "/a"] {
+            push_recent(&mut recent, path.to_string(), 3);
+        }
+        assert_eq!(recent, ["/a", "/c", "/b"]);
+
+        push_recent(&mut recent, "/d".to_string(), 3);
+        assert_eq!(recent, ["/d", "/a", "/c"]);
+    }
+
+    #[test]
+    fn test_existing_folders_prunes_missing() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let kept = dir.to_string_lossy().into_owned();
+        let missing = dir.join("gone").to_string_lossy().into_owned();
+
+        let folders = existing_folders(vec![missing, kept.clone()]);
+        assert_eq!(folders, [kept]);
+    }
+}
@@ -0,0 +1,557 @@
+use std::collections::HashMap;
+
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use serde::Serialize;
+
+/// Average silent reading speeds used for the reading time estimate
+const WORDS_PER_MINUTE: f64 = 200.0;
+const CJK_CHARS_PER_MINUTE: f64 = 500.0;
+
+pub fn parser_options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DocumentStats {
+    pub words: usize,
+    pub characters: usize,
+    pub characters_no_spaces: usize,
+    pub reading_time_minutes: u32,
+}
+
+/// Word, character and reading-time counts for the visible text of a document.
+/// Markdown syntax is stripped first, and CJK characters count as one word each.
+pub fn document_stats(markdown: &str) -> DocumentStats {
+    let text = visible_text(markdown);
+
+    let mut words = 0;
+    let mut cjk_chars = 0;
+    let mut in_word = false;
+    let mut characters = 0;
+    let mut characters_no_spaces = 0;
+
+    for c in text.chars() {
+        if c == '\n' || c == '\r' {
+            in_word = false;
+            continue;
+        }
+        characters += 1;
+        if c.is_whitespace() {
+            in_word = false;
+            continue;
+        }
+        characters_no_spaces += 1;
+
+        if is_cjk(c) {
+            cjk_chars += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() && !in_word {
+            words += 1;
+            in_word = true;
+        }
+    }
+
+    let minutes = (words as f64 / WORDS_PER_MINUTE) + (cjk_chars as f64 / CJK_CHARS_PER_MINUTE);
+
+    DocumentStats {
+        words: words + cjk_chars,
+        characters,
+        characters_no_spaces,
+        reading_time_minutes: minutes.ceil() as u32,
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct TocEntry {
+    pub level: u8,
+    pub text: String,
+    pub slug: String,
+}
+
+/// Table of contents built from the document's headings, in order.
+/// Headings inside code blocks are not headings to the parser, so they're ignored.
+pub fn generate_toc(markdown: &str) -> Vec<TocEntry> {
+    let mut entries = Vec::new();
+    let mut slugger = Slugger::default();
+    let mut current: Option<(u8, String)> = None;
+
+    for event in Parser::new_ext(markdown, parser_options()) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                current = Some((heading_level(level), String::new()));
+            }
+            Event::Text(t) | Event::Code(t) => {
+                if let Some((_, text)) = current.as_mut() {
+                    text.push_str(&t);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, text)) = current.take() {
+                    let text = text.trim().to_string();
+                    let slug = slugger.slug(&text);
+                    entries.push(TocEntry { level, text, slug });
+                }
+            }
+            _ => {}
+        }
+    }
+    entries
+}
+
+/// A heading in the document outline, with the headings nested under it
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct OutlineNode {
+    pub level: u8,
+    pub text: String,
+    pub slug: String,
+    /// Byte offset of the heading in the source, for scrolling the source view to it
+    pub offset: usize,
+    pub children: Vec<OutlineNode>,
+}
+
+/// The document's headings as a tree: each heading holds the deeper headings
+/// that follow it until the next heading of the same or a higher level.
+pub fn document_outline(markdown: &str) -> Vec<OutlineNode> {
+    let mut slugger = Slugger::default();
+    let mut current: Option<(u8, String, usize)> = None;
+    let mut roots = Vec::new();
+    // Open headings, shallowest first
+    let mut stack: Vec<OutlineNode> = Vec::new();
+
+    for (event, range) in Parser::new_ext(markdown, parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                current = Some((heading_level(level), String::new(), range.start));
+            }
+            Event::Text(t) | Event::Code(t) => {
+                if let Some((_, text, _)) = current.as_mut() {
+                    text.push_str(&t);
+                }
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((level, text, offset)) = current.take() {
+                    let text = text.trim().to_string();
+                    let slug = slugger.slug(&text);
+                    while stack.last().is_some_and(|open| open.level >= level) {
+                        close_outline_node(&mut stack, &mut roots);
+                    }
+                    stack.push(OutlineNode {
+                        level,
+                        text,
+                        slug,
+                        offset,
+                        children: Vec::new(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    while !stack.is_empty() {
+        close_outline_node(&mut stack, &mut roots);
+    }
+    roots
+}
+
+/// Move the innermost open heading into its parent, or to the top level
+fn close_outline_node(stack: &mut Vec<OutlineNode>, roots: &mut Vec<OutlineNode>) {
+    if let Some(node) = stack.pop() {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => roots.push(node),
+        }
+    }
+}
+
+/// Whether the document contains a GFM table, i.e. has something to export as a spreadsheet
+pub fn has_tables(markdown: &str) -> bool {
+    Parser::new_ext(markdown, parser_options())
+        .any(|event| matches!(event, Event::Start(Tag::Table(_))))
+}
+
+pub fn heading_level(level: HeadingLevel) -> u8 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+/// GitHub-style heading anchors: lowercased, punctuation stripped, spaces to hyphens,
+/// with `-1`, `-2`, ... appended to repeats.
+#[derive(Default)]
+pub struct Slugger {
+    seen: HashMap<String, usize>,
+}
+
+impl Slugger {
+    pub fn slug(&mut self, text: &str) -> String {
+        let base: String = text
+            .trim()
+            .to_lowercase()
+            .chars()
+            .filter_map(|c| match c {
+                ' ' => Some('-'),
+                '-' | '_' => Some(c),
+                c if c.is_alphanumeric() => Some(c),
+                _ => None,
+            })
+            .collect();
+
+        let count = self.seen.entry(base.clone()).or_insert(0);
+        let slug = if *count == 0 {
+            base.clone()
+        } else {
+            format!("{}-{}", base, count)
+        };
+        *count += 1;
+        slug
+    }
+}
+
+/// Split a leading `---` YAML front matter block from the body.
+/// Returns the parsed YAML as JSON plus the body with the block removed, or `None`
+/// and the untouched document when there's no front matter. A `---` anywhere but the
+/// first line is a horizontal rule, and a leading block that isn't a YAML mapping is
+/// treated as ordinary content.
+pub fn parse_front_matter(markdown: &str) -> Result<(Option<serde_json::Value>, String), String> {
+    let Some((yaml, body)) = split_front_matter(markdown) else {
+        return Ok((None, markdown.to_string()));
+    };
+
+    let value: serde_yaml::Value =
+        serde_yaml::from_str(yaml).map_err(|e| format!("Invalid front matter: {}", e))?;
+    let value = match value {
+        serde_yaml::Value::Mapping(_) => value,
+        serde_yaml::Value::Null => serde_yaml::Value::Mapping(Default::default()),
+        _ => return Ok((None, markdown.to_string())),
+    };
+    let json = serde_json::to_value(value)
+        .map_err(|e| format!("Failed to convert front matter: {}", e))?;
+
+    Ok((Some(json), body.to_string()))
+}
+
+/// Locate the YAML between an opening `---` on the first line and the next
+/// `---` or `...` line.
+fn split_front_matter(markdown: &str) -> Option<(&str, &str)> {
+    let content = markdown.strip_prefix('\u{feff}').unwrap_or(markdown);
+    let first_line_end = content.find('\n')?;
+    if content[..first_line_end].trim_end() != "---" {
+        return None;
+    }
+
+    let yaml_start = first_line_end + 1;
+    let mut offset = yaml_start;
+    for line in content[yaml_start..].split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            return Some((
+                &content[yaml_start..offset],
+                &content[offset + line.len()..],
+            ));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Text a reader would see, with block boundaries as newlines.
+fn visible_text(markdown: &str) -> String {
+    let mut text = String::new();
+    for event in Parser::new_ext(markdown, parser_options()) {
+        match event {
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            Event::SoftBreak => text.push(' '),
+            Event::HardBreak => text.push('\n'),
+            Event::End(
+                TagEnd::Paragraph
+                | TagEnd::Heading(_)
+                | TagEnd::Item
+                | TagEnd::CodeBlock
+                | TagEnd::TableCell
+                | TagEnd::BlockQuote(_),
+            ) => text.push('\n'),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Markdown with the syntax stripped, for previews, plain-text copies and exports.
+/// Blocks are separated by blank lines, list items start with `•` (indented two
+/// spaces per nesting level) and code blocks keep their content without the fences.
+/// Links keep their text, followed by the URL in parentheses when it is an absolute
+/// URL that says more than the text; anchors and relative links are dropped.
+pub fn markdown_to_plaintext(markdown: &str) -> String {
+    let mut out = String::new();
+    let mut list_depth = 0usize;
+    let mut links: Vec<(String, usize)> = Vec::new();
+    let mut first_cell = true;
+
+    for event in Parser::new_ext(markdown, parser_options()) {
+        match event {
+            Event::Start(Tag::List(_)) => {
+                if list_depth == 0 {
+                    start_block(&mut out);
+                } else {
+                    start_line(&mut out);
+                }
+                list_depth += 1;
+            }
+            Event::End(TagEnd::List(_)) => list_depth -= 1,
+            Event::Start(Tag::Item) => {
+                start_line(&mut out);
+                out.push_str(&"  ".repeat(list_depth.saturating_sub(1)));
+                out.push_str("• ");
+            }
+            Event::Start(
+                Tag::Paragraph
+                | Tag::Heading { .. }
+                | Tag::CodeBlock(_)
+                | Tag::BlockQuote(_)
+                | Tag::Table(_),
+            ) => {
+                if list_depth == 0 {
+                    start_block(&mut out);
+                } else if !out.ends_with("• ") {
+                    // The first block of a list item continues the bullet's line
+                    start_line(&mut out);
+                }
+            }
+            Event::Start(Tag::TableHead | Tag::TableRow) => {
+                start_line(&mut out);
+                first_cell = true;
+            }
+            Event::Start(Tag::TableCell) => {
+                if !first_cell {
+                    out.push('\t');
+                }
+                first_cell = false;
+            }
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                links.push((dest_url.to_string(), out.len()))
+            }
+            Event::End(TagEnd::Link) => {
+                if let Some((url, start)) = links.pop() {
+                    if url_adds_information(&out[start..], &url) {
+                        out.push_str(&format!(" ({})", url));
+                    }
+                }
+            }
+            Event::Text(t) | Event::Code(t) => out.push_str(&t),
+            Event::SoftBreak => out.push(' '),
+            Event::HardBreak => out.push('\n'),
+            Event::TaskListMarker(done) => out.push_str(if done { "[x] " } else { "[ ] " }),
+            Event::FootnoteReference(label) => out.push_str(&format!("[{}]", label)),
+            Event::Rule => start_block(&mut out),
+            _ => {}
+        }
+    }
+    out.trim_end().to_string()
+}
+
+fn start_line(out: &mut String) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+fn start_block(out: &mut String) {
+    if out.is_empty() {
+        return;
+    }
+    start_line(out);
+    if !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+}
+
+/// Whether a link's URL is worth showing next to its text: it must be absolute
+/// and not just the text again (as in autolinks or `[me@x.com](mailto:me@x.com)`).
+fn url_adds_information(text: &str, url: &str) -> bool {
+    if !url.contains("://") && !url.starts_with("mailto:") {
+        return false;
+    }
+    let bare = |s: &str| {
+        let s = s.trim();
+        let s = s.split_once("://").map_or(s, |(_, rest)| rest);
+        s.strip_prefix("mailto:")
+            .unwrap_or(s)
+            .trim_end_matches('/')
+            .to_lowercase()
+    };
+    bare(text) != bare(url)
+}
+
+/// Han ideographs and Japanese kana, which aren't separated by spaces.
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF      // Hiragana, Katakana
+        | 0x3400..=0x4DBF    // CJK Extension A
+        | 0x4E00..=0x9FFF    // CJK Unified Ideographs
+        | 0xF900..=0xFAFF    // CJK Compatibility Ideographs
+        | 0x20000..=0x2FA1F  // CJK Extensions B-F, Compatibility Supplement
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_document_stats_strips_markdown_syntax() {
+        let stats =
+            document_stats("# Title\n\nSome **bold** text with a [link](https://example.com).\n");
+        assert_eq!(stats.words, 7);
+        assert_eq!(stats.reading_time_minutes, 1);
+    }
+
+    #[test]
+    fn test_document_stats_counts_cjk_characters_individually() {
+        let stats = document_stats("中文字 and English");
+        assert_eq!(stats.words, 5);
+        assert_eq!(stats.characters, 15);
+        assert_eq!(stats.characters_no_spaces, 13);
+    }
+
+    #[test]
+    fn test_document_stats_ignores_code_fences() {
+        let stats = document_stats("```rust\nlet x = 1;\n```\n");
+        assert_eq!(stats.words, 3);
+    }
+
+    #[test]
+    fn test_has_tables() {
+        assert!(has_tables(
+            "# Sales\n\n| Q | Total |\n| - | - |\n| 1 | 10 |\n"
+        ));
+        assert!(!has_tables(
+            "A | B\n\n```\n| not | a table |\n| - | - |\n```\n"
+        ));
+    }
+
+    #[test]
+    fn test_document_stats_empty() {
+        let stats = document_stats("");
+        assert_eq!(
+            stats,
+            DocumentStats {
+                words: 0,
+                characters: 0,
+                characters_no_spaces: 0,
+                reading_time_minutes: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_generate_toc_disambiguates_duplicate_slugs() {
+        let toc = generate_toc("# Intro\n\n## Setup\n\n## Setup\n\n## Setup\n");
+        let slugs: Vec<&str> = toc.iter().map(|e| e.slug.as_str()).collect();
+        assert_eq!(slugs, ["intro", "setup", "setup-1", "setup-2"]);
+        assert_eq!(toc[1].level, 2);
+    }
+
+    #[test]
+    fn test_generate_toc_heading_with_inline_code_and_emphasis() {
+        let toc = generate_toc("## Using `cargo build` *quickly*!\n");
+        assert_eq!(toc[0].text, "Using cargo build quickly!");
+        assert_eq!(toc[0].slug, "using-cargo-build-quickly");
+    }
+
+    #[test]
+    fn test_generate_toc_ignores_headings_in_code_fences() {
+        let toc = generate_toc("# Real\n\n```\n# Not a heading\n```\n");
+        assert_eq!(toc.len(), 1);
+        assert_eq!(toc[0].text, "Real");
+    }
+
+    #[test]
+    fn test_document_outline_nests_headings_with_offsets() {
+        let md = "# Intro\n\ntext\n\n## Setup\n\n### Linux\n\n## Usage\n\n# Appendix\n";
+        let outline = document_outline(md);
+        assert_eq!(outline.len(), 2);
+        assert_eq!(outline[0].text, "Intro");
+        let children: Vec<&str> = outline[0]
+            .children
+            .iter()
+            .map(|n| n.slug.as_str())
+            .collect();
+        assert_eq!(children, ["setup", "usage"]);
+        assert_eq!(outline[0].children[0].children[0].text, "Linux");
+        assert_eq!(outline[1].text, "Appendix");
+
+        let offsets = [
+            outline[0].offset,
+            outline[0].children[0].offset,
+            outline[0].children[0].children[0].offset,
+            outline[0].children[1].offset,
+            outline[1].offset,
+        ];
+        assert_eq!(offsets, [0, 15, 25, 36, 46]);
+        for offset in offsets {
+            assert_eq!(&md[offset..offset + 1], "#");
+        }
+    }
+
+    #[test]
+    fn test_parse_front_matter_splits_yaml_and_body() {
+        let md = "---\ntitle: Notes\ntags: [a, b]\n---\n# Heading\n";
+        let (front, body) = parse_front_matter(md).unwrap();
+        let front = front.unwrap();
+        assert_eq!(front["title"], "Notes");
+        assert_eq!(front["tags"][1], "b");
+        assert_eq!(body, "# Heading\n");
+    }
+
+    #[test]
+    fn test_parse_front_matter_ignores_later_horizontal_rules() {
+        let md = "# Heading\n\n---\n\ntitle: not yaml\n\n---\n";
+        let (front, body) = parse_front_matter(md).unwrap();
+        assert!(front.is_none());
+        assert_eq!(body, md);
+    }
+
+    #[test]
+    fn test_parse_front_matter_leading_rule_without_mapping() {
+        let md = "---\nJust a paragraph between rules\n---\n";
+        let (front, body) = parse_front_matter(md).unwrap();
+        assert!(front.is_none());
+        assert_eq!(body, md);
+    }
+
+    #[test]
+    fn test_markdown_to_plaintext_links() {
+        let md = "See [the docs](https://docs.rs/x) and <https://example.com>, \
+                  [me@x.com](mailto:me@x.com), [jump](#setup) or [notes](notes/a.md).";
+        assert_eq!(
+            markdown_to_plaintext(md),
+            "See the docs (https://docs.rs/x) and https://example.com, me@x.com, jump or notes."
+        );
+    }
+
+    #[test]
+    fn test_markdown_to_plaintext_nested_lists() {
+        let md = "Intro\n\n- one\n- two\n  - nested\n    1. deep\n- three\n\nAfter\n";
+        assert_eq!(
+            markdown_to_plaintext(md),
+            "Intro\n\n• one\n• two\n  • nested\n    • deep\n• three\n\nAfter"
+        );
+    }
+
+    #[test]
+    fn test_markdown_to_plaintext_code_blocks() {
+        let md = "# Title\n\n```rust\nfn main() {\n    run();\n}\n```\n\nText with `code` and **bold**.\n";
+        assert_eq!(
+            markdown_to_plaintext(md),
+            "Title\n\nfn main() {\n    run();\n}\n\nText with code and bold."
+        );
+    }
+}
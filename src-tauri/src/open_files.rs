@@ -0,0 +1,281 @@
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use crate::files::{expand_home, resolve_path};
+
+/// What to tell the frontend about a requested file
+#[derive(Debug, Clone, PartialEq)]
+pub enum OpenAction {
+    /// Load the file (`open-file`)
+    Open(String),
+    /// The file is already open; bring its document to the front (`focus-file`)
+    Focus(String),
+}
+
+/// Custom URL scheme registered for deep links: `markdown://open?path=<path>`
+pub const URL_SCHEME: &str = "markdown";
+
+/// Turn a launch argument (a path, a `file://` URL or a `markdown://open` deep
+/// link) into the canonical path of an existing Markdown file. Anything else,
+/// including macOS `-psn_` process serial arguments, is ignored.
+///
+/// Deep links can be triggered by any program or web page, so they get exactly
+/// the same check as files: only an existing `.md`/`.markdown` file is ever
+/// honored, and a link can do nothing but open it in the editor.
+pub fn normalize_open_path(arg: &str) -> Option<String> {
+    let trimmed = arg.trim_matches('"');
+    if trimmed.is_empty() || trimmed.starts_with("-psn_") {
+        return None;
+    }
+
+    let path = if let Ok(url) = tauri::Url::parse(trimmed) {
+        match url.scheme() {
+            "file" => url.to_file_path().ok()?,
+            URL_SCHEME => deep_link_path(&url)?,
+            _ => return None,
+        }
+    } else {
+        expand_home(trimmed)
+    };
+
+    if !path.is_file() {
+        return None;
+    }
+
+    let ext = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    if ext != "md" && ext != "markdown" {
+        return None;
+    }
+
+    Some(canonical_key(&path))
+}
+
+/// The `path` query parameter of a `markdown://open` URL, percent-decoded
+fn deep_link_path(url: &tauri::Url) -> Option<PathBuf> {
+    if url.host_str() != Some("open") {
+        return None;
+    }
+    url.query_pairs()
+        .find(|(key, _)| key == "path")
+        .map(|(_, value)| PathBuf::from(value.as_ref()))
+        .filter(|path| !path.as_os_str().is_empty())
+}
+
+pub fn collect_open_paths<I>(args: I) -> Vec<String>
+where
+    I: IntoIterator<Item = String>,
+{
+    args.into_iter()
+        .filter_map(|arg| normalize_open_path(&arg))
+        .collect()
+}
+
+/// The form paths are compared in, so `./note.md` and its absolute path match
+pub fn canonical_key(path: &Path) -> String {
+    resolve_path(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Files the OS asked us to open (file association, second launch, macOS
+/// `Opened` events).
+///
+/// Until the frontend reports that its `open-file` listener is registered,
+/// paths are only queued; emitting them earlier would be lost on cold start.
+/// Once ready, the queue is drained a single time and later paths pass straight
+/// through, so each file is delivered exactly once. Files that are already open
+/// are focused instead of being loaded a second time.
+///
+/// Paths are expected in `canonical_key` form.
+#[derive(Default)]
+pub struct OpenQueue {
+    ready: bool,
+    pending: VecDeque<String>,
+    opened: HashSet<String>,
+}
+
+impl OpenQueue {
+    /// Accept newly requested paths. Returns what to emit right away, which is
+    /// nothing until the frontend is ready.
+    pub fn push(&mut self, paths: Vec<String>) -> Vec<OpenAction> {
+        let mut actions = Vec::new();
+        for path in paths {
+            if self.opened.contains(&path) {
+                let focus = OpenAction::Focus(path);
+                if !actions.contains(&focus) {
+                    actions.push(focus);
+                }
+            } else if self.ready {
+                self.opened.insert(path.clone());
+                actions.push(OpenAction::Open(path));
+            } else if !self.pending.contains(&path) {
+                self.pending.push_back(path);
+            }
+        }
+        actions
+    }
+
+    /// Mark the frontend as ready and return the queued paths to emit
+    pub fn mark_ready(&mut self) -> Vec<String> {
+        self.ready = true;
+        self.take_pending()
+    }
+
+    /// Drain the queue; the drained files count as opened
+    pub fn take_pending(&mut self) -> Vec<String> {
+        let paths: Vec<String> = self.pending.drain(..).collect();
+        self.opened.extend(paths.iter().cloned());
+        paths
+    }
+
+    /// Record a document the frontend opened by other means (e.g. the Open dialog)
+    pub fn mark_opened(&mut self, path: String) {
+        self.opened.insert(path);
+    }
+
+    /// Forget a closed document so requesting it again loads it
+    pub fn forget(&mut self, path: &str) {
+        self.opened.remove(path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_queues_until_frontend_is_ready() {
+        let mut queue = OpenQueue::default();
+        assert!(queue.push(paths(&["/a.md", "/b.md"])).is_empty());
+        assert!(queue.push(paths(&["/a.md"])).is_empty());
+
+        assert_eq!(queue.mark_ready(), paths(&["/a.md", "/b.md"]));
+        // Drained once: a second ready signal doesn't replay the files
+        assert!(queue.mark_ready().is_empty());
+    }
+
+    #[test]
+    fn test_emits_directly_once_ready() {
+        let mut queue = OpenQueue::default();
+        queue.mark_ready();
+        assert_eq!(
+            queue.push(paths(&["/c.md", "/c.md", "/d.md"])),
+            vec![
+                OpenAction::Open("/c.md".to_string()),
+                OpenAction::Focus("/c.md".to_string()),
+                OpenAction::Open("/d.md".to_string()),
+            ]
+        );
+        assert!(queue.take_pending().is_empty());
+    }
+
+    #[test]
+    fn test_already_opened_files_are_focused() {
+        let mut queue = OpenQueue::default();
+        queue.push(paths(&["/a.md"]));
+        queue.mark_ready();
+        queue.mark_opened("/b.md".to_string());
+
+        assert_eq!(
+            queue.push(paths(&["/a.md", "/b.md", "/b.md"])),
+            vec![
+                OpenAction::Focus("/a.md".to_string()),
+                OpenAction::Focus("/b.md".to_string())
+            ]
+        );
+
+        queue.forget("/a.md");
+        assert_eq!(
+            queue.push(paths(&["/a.md"])),
+            vec![OpenAction::Open("/a.md".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_normalize_open_path() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let note = dir.join("note.md");
+        std::fs::write(&note, "# Note").unwrap();
+        std::fs::write(dir.join("image.png"), b"png").unwrap();
+
+        let canonical = note.canonicalize().unwrap().to_string_lossy().to_string();
+        let relative = dir.join(".").join("note.md");
+        assert_eq!(
+            normalize_open_path(note.to_str().unwrap()),
+            Some(canonical.clone())
+        );
+        assert_eq!(
+            normalize_open_path(relative.to_str().unwrap()),
+            Some(canonical.clone())
+        );
+        assert_eq!(
+            normalize_open_path(&format!("\"{}\"", note.display())),
+            Some(canonical)
+        );
+
+        assert_eq!(
+            normalize_open_path(dir.join("image.png").to_str().unwrap()),
+            None
+        );
+        assert_eq!(
+            normalize_open_path(dir.join("missing.md").to_str().unwrap()),
+            None
+        );
+        assert_eq!(normalize_open_path("-psn_0_12345"), None);
+        assert_eq!(normalize_open_path("https://example.com/note.md"), None);
+    }
+
+    #[test]
+    fn test_deep_link_path() {
+        let url = tauri::Url::parse("markdown://open?path=%2Fnotes%2Fmy%20note.md").unwrap();
+        assert_eq!(
+            deep_link_path(&url),
+            Some(PathBuf::from("/notes/my note.md"))
+        );
+        let url = tauri::Url::parse("markdown://open?mode=read&path=/a/b.md").unwrap();
+        assert_eq!(deep_link_path(&url), Some(PathBuf::from("/a/b.md")));
+
+        for link in [
+            "markdown://delete?path=/a/b.md",
+            "markdown://open",
+            "markdown://open?path=",
+        ] {
+            assert_eq!(
+                deep_link_path(&tauri::Url::parse(link).unwrap()),
+                None,
+                "{}",
+                link
+            );
+        }
+    }
+
+    #[test]
+    fn test_normalize_deep_link() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let note = dir.join("linked note.md");
+        std::fs::write(&note, "# Linked").unwrap();
+        std::fs::write(dir.join("script.sh"), "echo").unwrap();
+
+        let link = |path: &Path| {
+            let mut url = tauri::Url::parse("markdown://open").unwrap();
+            url.query_pairs_mut()
+                .append_pair("path", path.to_str().unwrap());
+            url.to_string()
+        };
+        let canonical = note.canonicalize().unwrap().to_string_lossy().to_string();
+        assert_eq!(normalize_open_path(&link(&note)), Some(canonical));
+        assert_eq!(normalize_open_path(&link(&dir.join("script.sh"))), None);
+        assert_eq!(normalize_open_path(&link(&dir.join("missing.md"))), None);
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Menu item ids that can be given a keyboard shortcut, with their built-in
+/// accelerators. An empty accelerator means no shortcut by default.
+pub const DEFAULT_SHORTCUTS: &[(&str, &str)] = &[
+    ("file_new", "CmdOrCtrl+N"),
+    ("file_open", "CmdOrCtrl+O"),
+    ("file_save", "CmdOrCtrl+S"),
+    ("file_save_as", "CmdOrCtrl+Shift+S"),
+    ("file_close_document", "CmdOrCtrl+W"),
+    ("file_import_any", "CmdOrCtrl+Shift+I"),
+    ("edit_undo", "CmdOrCtrl+Z"),
+    ("edit_redo", "CmdOrCtrl+Shift+Z"),
+    ("edit_find", "CmdOrCtrl+F"),
+    ("edit_find_replace", "CmdOrCtrl+Alt+F"),
+    ("edit_find_in_files", "CmdOrCtrl+Shift+F"),
+    ("editor_bold", "CmdOrCtrl+B"),
+    ("editor_italic", "CmdOrCtrl+I"),
+    ("editor_strike", "CmdOrCtrl+Shift+X"),
+    ("editor_inline_code", "CmdOrCtrl+Shift+C"),
+    ("editor_paragraph", ""),
+    ("editor_heading_1", "CmdOrCtrl+Option+1"),
+    ("editor_heading_2", "CmdOrCtrl+Option+2"),
+    ("editor_heading_3", "CmdOrCtrl+Option+3"),
+    ("editor_heading_4", "CmdOrCtrl+Option+4"),
+    ("editor_heading_5", "CmdOrCtrl+Option+5"),
+    ("editor_heading_6", "CmdOrCtrl+Option+6"),
+    ("editor_bullet_list", "CmdOrCtrl+Shift+8"),
+    ("editor_ordered_list", "CmdOrCtrl+Shift+7"),
+    ("editor_blockquote", ""),
+    ("editor_code_block", ""),
+    ("editor_horizontal_rule", ""),
+    ("format_clean_up", ""),
+    ("view_source_code", "CmdOrCtrl+Alt+S"),
+];
+
+/// Key names accepted after the modifiers, besides single characters and F1–F24
+const NAMED_KEYS: &[&str] = &[
+    "Space",
+    "Tab",
+    "Enter",
+    "Backspace",
+    "Delete",
+    "Escape",
+    "Insert",
+    "Home",
+    "End",
+    "PageUp",
+    "PageDown",
+    "Up",
+    "Down",
+    "Left",
+    "Right",
+    "Plus",
+    "Minus",
+    "Equal",
+    "Comma",
+    "Period",
+    "Slash",
+    "Backslash",
+    "Semicolon",
+    "Quote",
+    "Backquote",
+    "BracketLeft",
+    "BracketRight",
+];
+
+/// Keyboard shortcuts for menu items: the defaults with the user's overrides applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shortcuts {
+    accelerators: HashMap<String, String>,
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        Shortcuts {
+            accelerators: DEFAULT_SHORTCUTS
+                .iter()
+                .map(|(id, accel)| (id.to_string(), accel.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl Shortcuts {
+    /// Load overrides from a JSON object of `{ "menu_item_id": "Accelerator" }`.
+    /// A missing or unreadable file gives the defaults; entries for unknown
+    /// items, invalid accelerators or clashing assignments are skipped.
+    pub fn load(path: &Path) -> Self {
+        let mut shortcuts = Shortcuts::default();
+        let Ok(content) = fs::read_to_string(path) else {
+            return shortcuts;
+        };
+        let overrides: HashMap<String, String> = match serde_json::from_str(&content) {
+            Ok(map) => map,
+            Err(e) => {
+                println!(
+                    "⚠️ Ignoring invalid shortcuts file {}: {}",
+                    path.display(),
+                    e
+                );
+                return shortcuts;
+            }
+        };
+
+        let mut ids: Vec<_> = overrides.keys().collect();
+        ids.sort();
+        // Clear first so swapped shortcuts don't clash with the defaults being replaced
+        for id in &ids {
+            if let Some(accel) = shortcuts.accelerators.get_mut(id.as_str()) {
+                accel.clear();
+            }
+        }
+        for id in ids {
+            if let Err(e) = shortcuts.set(id, &overrides[id]) {
+                println!("⚠️ Ignoring shortcut for {}: {}", id, e);
+            }
+        }
+        shortcuts
+    }
+
+    /// Write the accelerators that differ from the defaults to `path`.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let overrides: std::collections::BTreeMap<&str, &str> = DEFAULT_SHORTCUTS
+            .iter()
+            .filter_map(|(id, default)| {
+                let current = self.accelerators.get(*id)?;
+                (current != default).then_some((*id, current.as_str()))
+            })
+            .collect();
+        let content = serde_json::to_string_pretty(&overrides)
+            .map_err(|e| format!("Failed to serialize shortcuts: {}", e))?;
+        fs::write(path, content).map_err(|e| format!("Failed to write shortcuts file: {}", e))
+    }
+
+    /// Accelerator for a menu item, or `None` when it has no shortcut.
+    pub fn get(&self, id: &str) -> Option<&str> {
+        self.accelerators
+            .get(id)
+            .map(String::as_str)
+            .filter(|a| !a.is_empty())
+    }
+
+    /// All menu items that can have shortcuts, with their accelerators ("" for none).
+    pub fn all(&self) -> &HashMap<String, String> {
+        &self.accelerators
+    }
+
+    /// Assign `accelerator` to the menu item `id`; an empty string removes its
+    /// shortcut. Fails for unknown items, malformed accelerators, or one that
+    /// another item already uses.
+    pub fn set(&mut self, id: &str, accelerator: &str) -> Result<(), String> {
+        if !self.accelerators.contains_key(id) {
+            return Err(format!("Unknown menu command: {}", id));
+        }
+        let accelerator = accelerator.trim();
+        if accelerator.is_empty() {
+            self.accelerators.insert(id.to_string(), String::new());
+            return Ok(());
+        }
+
+        let normalized = normalize_accelerator(accelerator)?;
+        let clash = self.accelerators.iter().find(|(other, accel)| {
+            other.as_str() != id
+                && !accel.is_empty()
+                && normalize_accelerator(accel).is_ok_and(|n| n == normalized)
+        });
+        if let Some((other, _)) = clash {
+            return Err(format!("{} is already assigned to {}", accelerator, other));
+        }
+
+        self.accelerators
+            .insert(id.to_string(), accelerator.to_string());
+        Ok(())
+    }
+}
+
+/// Canonical form of an accelerator such as `CmdOrCtrl+Shift+S`, used to
+/// validate it and to spot two spellings of the same shortcut. `CmdOrCtrl`
+/// resolves to Cmd on macOS and Ctrl elsewhere.
+pub fn normalize_accelerator(accelerator: &str) -> Result<String, String> {
+    let invalid = |reason: &str| format!("Invalid shortcut '{}': {}", accelerator, reason);
+
+    let parts: Vec<&str> = accelerator.split('+').map(str::trim).collect();
+    let (key, modifier_names) = parts.split_last().ok_or_else(|| invalid("empty"))?;
+
+    let mut modifiers = Vec::new();
+    for name in modifier_names {
+        let modifier = match name.to_lowercase().as_str() {
+            "cmdorctrl" | "commandorcontrol" if cfg!(target_os = "macos") => "Cmd",
+            "cmdorctrl" | "commandorcontrol" => "Ctrl",
+            "cmd" | "command" | "super" | "meta" => "Cmd",
+            "ctrl" | "control" => "Ctrl",
+            "alt" | "option" => "Alt",
+            "shift" => "Shift",
+            "" => return Err(invalid("missing key")),
+            _ => return Err(invalid(&format!("unknown modifier {}", name))),
+        };
+        if modifiers.contains(&modifier) {
+            return Err(invalid(&format!("{} repeated", modifier)));
+        }
+        modifiers.push(modifier);
+    }
+    modifiers.sort_unstable();
+
+    let key = normalize_key(key).ok_or_else(|| invalid(&format!("unknown key {}", key)))?;
+    let is_function_key = key.len() > 1 && key.starts_with('F') && key[1..].parse::<u8>().is_ok();
+    if modifiers.is_empty() && !is_function_key {
+        return Err(invalid("needs a modifier key"));
+    }
+
+    modifiers.push(&key);
+    Ok(modifiers.join("+"))
+}
+
+fn normalize_key(key: &str) -> Option<String> {
+    let mut chars = key.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return c
+            .is_ascii_graphic()
+            .then(|| c.to_ascii_uppercase().to_string());
+    }
+    if let Some(n) = key
+        .strip_prefix(['F', 'f'])
+        .and_then(|n| n.parse::<u8>().ok())
+    {
+        return (1..=24).contains(&n).then(|| format!("F{}", n));
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|name| name.eq_ignore_ascii_case(key))
+        .map(|name| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_accelerator() {
+        assert_eq!(
+            normalize_accelerator("shift+ctrl+s").unwrap(),
+            "Ctrl+Shift+S"
+        );
+        assert_eq!(normalize_accelerator("Option+Cmd+1").unwrap(), "Alt+Cmd+1");
+        assert_eq!(normalize_accelerator("F5").unwrap(), "F5");
+        assert_eq!(normalize_accelerator("Ctrl+pageup").unwrap(), "Ctrl+PageUp");
+        assert!(normalize_accelerator("S").is_err());
+        assert!(normalize_accelerator("Ctrl+").is_err());
+        assert!(normalize_accelerator("Hyper+S").is_err());
+        assert!(normalize_accelerator("Ctrl+Ctrl+S").is_err());
+        assert!(normalize_accelerator("Ctrl+F25").is_err());
+    }
+
+    #[test]
+    fn test_set_rejects_duplicates_and_unknown_items() {
+        let mut shortcuts = Shortcuts::default();
+        let err = shortcuts
+            .set("editor_blockquote", "cmdorctrl+b")
+            .unwrap_err();
+        assert_eq!(err, "cmdorctrl+b is already assigned to editor_bold");
+        assert!(shortcuts.set("no_such_item", "Ctrl+K").is_err());
+
+        // Reassigning an item's own shortcut and clearing one are fine
+        shortcuts.set("editor_bold", "CmdOrCtrl+B").unwrap();
+        shortcuts.set("editor_bold", "").unwrap();
+        assert_eq!(shortcuts.get("editor_bold"), None);
+        shortcuts.set("editor_blockquote", "CmdOrCtrl+B").unwrap();
+        assert_eq!(shortcuts.get("editor_blockquote"), Some("CmdOrCtrl+B"));
+    }
+
+    #[test]
+    fn test_save_and_load_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("shortcuts.json");
+        let mut shortcuts = Shortcuts::default();
+        shortcuts
+            .set("editor_code_block", "CmdOrCtrl+Alt+C")
+            .unwrap();
+        shortcuts.set("file_new", "").unwrap();
+        // Swap bold and italic
+        shortcuts.set("editor_bold", "").unwrap();
+        shortcuts.set("editor_italic", "CmdOrCtrl+B").unwrap();
+        shortcuts.set("editor_bold", "CmdOrCtrl+I").unwrap();
+        shortcuts.save(&path).unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        let loaded = Shortcuts::load(&path);
+
+        assert!(saved.contains("\"editor_code_block\": \"CmdOrCtrl+Alt+C\""));
+        assert!(!saved.contains("file_open"));
+        assert_eq!(loaded, shortcuts);
+        assert_eq!(
+            Shortcuts::load(Path::new("/nonexistent/shortcuts.json")),
+            Shortcuts::default()
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+
+/// Built-in themes as (id sent with `menu-set-theme`, menu label)
+pub const BUILTIN_THEMES: &[(&str, &str)] = &[
+    ("github-light", "GitHub Light"),
+    ("github-dark", "GitHub Dark"),
+    ("dracula", "Dracula"),
+    ("nord-light", "Nord Light"),
+    ("nord-dark", "Nord Dark"),
+    ("solarized-light", "Solarized Light"),
+    ("solarized-dark", "Solarized Dark"),
+];
+
+pub const DEFAULT_THEME: &str = "github-light";
+
+/// Menu ids of user themes carry the theme id after this prefix
+const CUSTOM_THEME_MENU_PREFIX: &str = "custom_theme:";
+
+/// A theme registered by the user. The frontend supplies its styles; the
+/// backend only lists it in View > Theme.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CustomTheme {
+    pub id: String,
+    pub label: String,
+}
+
+/// Menu id of a theme entry: "view_theme_github_light" for built-in themes,
+/// "custom_theme:<id>" for user themes
+pub fn menu_id(theme: &str) -> String {
+    if BUILTIN_THEMES.iter().any(|(id, _)| *id == theme) {
+        format!("view_theme_{}", theme.replace('-', "_"))
+    } else {
+        format!("{}{}", CUSTOM_THEME_MENU_PREFIX, theme)
+    }
+}
+
+/// The theme a View > Theme menu entry selects
+pub fn theme_for_menu_id(id: &str, custom: &[CustomTheme]) -> Option<String> {
+    if let Some(theme) = id.strip_prefix(CUSTOM_THEME_MENU_PREFIX) {
+        return custom.iter().find(|t| t.id == theme).map(|t| t.id.clone());
+    }
+    BUILTIN_THEMES
+        .iter()
+        .find(|(theme, _)| menu_id(theme) == id)
+        .map(|(theme, _)| theme.to_string())
+}
+
+pub fn is_known(theme: &str, custom: &[CustomTheme]) -> bool {
+    BUILTIN_THEMES.iter().any(|(id, _)| *id == theme) || custom.iter().any(|t| t.id == theme)
+}
+
+/// Check a theme the user wants to register: the id and label must be
+/// non-empty and the id must not be taken by another theme.
+pub fn validate_custom(
+    id: &str,
+    label: &str,
+    custom: &[CustomTheme],
+) -> Result<CustomTheme, String> {
+    let id = id.trim();
+    let label = label.trim();
+    if id.is_empty() {
+        return Err("Theme id cannot be empty".to_string());
+    }
+    if label.is_empty() {
+        return Err("Theme label cannot be empty".to_string());
+    }
+    if is_known(id, custom) {
+        return Err(format!("Theme already exists: {}", id));
+    }
+    Ok(CustomTheme {
+        id: id.to_string(),
+        label: label.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(id: &str) -> CustomTheme {
+        CustomTheme {
+            id: id.to_string(),
+            label: id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_menu_ids_round_trip() {
+        let themes = vec![custom("sepia"), custom("github_light")];
+        assert_eq!(menu_id("solarized-dark"), "view_theme_solarized_dark");
+        assert_eq!(menu_id("sepia"), "custom_theme:sepia");
+        // A user theme can't shadow the built-in entry with a similar id
+        assert_eq!(menu_id("github_light"), "custom_theme:github_light");
+
+        for theme in ["github-light", "dracula", "sepia", "github_light"] {
+            assert_eq!(
+                theme_for_menu_id(&menu_id(theme), &themes).as_deref(),
+                Some(theme)
+            );
+        }
+        assert_eq!(theme_for_menu_id("custom_theme:removed", &themes), None);
+        assert_eq!(theme_for_menu_id("view_source_code", &themes), None);
+    }
+
+    #[test]
+    fn test_validate_custom() {
+        let themes = vec![custom("sepia")];
+        assert_eq!(
+            validate_custom(" ocean ", "Ocean ", &themes),
+            Ok(CustomTheme {
+                id: "ocean".to_string(),
+                label: "Ocean".to_string()
+            })
+        );
+        assert!(validate_custom("  ", "Blank", &themes).is_err());
+        assert!(validate_custom("blank", "", &themes).is_err());
+        assert!(validate_custom("sepia", "Sepia again", &themes).is_err());
+        assert!(validate_custom("dracula", "Dracula", &themes).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+
+/// Quiet period before a burst of filesystem events is reported as one change
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Watches a single open document for modifications made by other programs.
+///
+/// The parent directory is watched rather than the file itself so that editors
+/// (and our own atomic save) replacing the file via rename keep being tracked.
+/// `on_change` only fires when the on-disk content differs from the last content
+/// the app knows about, so the app's own saves don't trigger it.
+pub struct FileWatcher {
+    _debouncer: Debouncer<RecommendedWatcher>,
+    last_seen: Arc<Mutex<Option<u64>>>,
+}
+
+impl FileWatcher {
+    pub fn new<F>(path: &Path, on_change: F) -> Result<Self, String>
+    where
+        F: Fn() + Send + 'static,
+    {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_os_string())
+            .ok_or_else(|| format!("Invalid file path: {}", path.display()))?;
+        let parent = path
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+
+        let last_seen = Arc::new(Mutex::new(content_hash(path)));
+        let target = path.to_path_buf();
+        let seen = Arc::clone(&last_seen);
+
+        let mut debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| {
+            let Ok(events) = result else { return };
+            if !events
+                .iter()
+                .any(|e| e.path.file_name() == Some(file_name.as_os_str()))
+            {
+                return;
+            }
+
+            let current = content_hash(&target);
+            let Ok(mut last) = seen.lock() else { return };
+            if *last != current {
+                *last = current;
+                on_change();
+            }
+        })
+        .map_err(|e| format!("Failed to create file watcher: {}", e))?;
+
+        debouncer
+            .watcher()
+            .watch(&parent, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch file: {}", e))?;
+
+        Ok(FileWatcher {
+            _debouncer: debouncer,
+            last_seen,
+        })
+    }
+
+    /// Record content the app itself just wrote so it isn't reported as external.
+    pub fn mark_synced(&self, content: &[u8]) {
+        if let Ok(mut last) = self.last_seen.lock() {
+            *last = Some(hash_bytes(content));
+        }
+    }
+}
+
+fn content_hash(path: &Path) -> Option<u64> {
+    fs::read(path).ok().map(|bytes| hash_bytes(&bytes))
+}
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_file_watcher_ignores_own_writes_and_reports_external_ones() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("note.md");
+        fs::write(&path, "original").unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        let watcher = FileWatcher::new(&path, move || {
+            let _ = tx.send(());
+        })
+        .unwrap();
+
+        // Our own write is marked as synced and must not be reported
+        watcher.mark_synced(b"ours");
+        fs::write(&path, "ours").unwrap();
+        let own_write = rx.recv_timeout(Duration::from_secs(2));
+
+        fs::write(&path, "theirs").unwrap();
+        let external = rx.recv_timeout(Duration::from_secs(5));
+
+        assert!(own_write.is_err());
+        assert!(external.is_ok());
+    }
+}
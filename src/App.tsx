@@ -2,12 +2,13 @@ import { useCallback, useEffect, useMemo, useRef, useState } from 'react';
 import { useTranslation } from 'react-i18next';
 import { listen } from '@tauri-apps/api/event';
 import { invoke } from '@tauri-apps/api/core';
-import { open, save } from '@tauri-apps/plugin-dialog';
+import { message, open, save } from '@tauri-apps/plugin-dialog';
 import { getCurrentWindow } from '@tauri-apps/api/window';
 import { WindowTitlebar } from 'tauri-controls';
 import { Editor } from '@/components/Editor/Editor';
 import { SourceEditor } from '@/components/Editor/SourceEditor';
 import { Sidebar } from '@/components/Sidebar/Sidebar';
+import { PdfExportDialog, DEFAULT_PDF_OPTIONS, type PdfOptions } from '@/components/Export/PdfExportDialog';
 import { useDocumentStore } from '@/stores/documentStore';
 import { useUIStore } from '@/stores/uiStore';
 import { useEditorStore } from '@/stores/editorStore';
@@ -15,6 +16,7 @@ import { useSettingsStore } from '@/stores/settingsStore';
 import { useAutoSave } from '@/hooks/useAutoSave';
 import { usePlatformInitialization } from '@/hooks/usePlatformInitialization';
 import { getPrimaryLanguageCode } from '@/i18n/languageUtils';
+import { errorMessage } from '@/lib/errors';
 import {
   FileText,
   PanelLeft,
@@ -34,6 +36,7 @@ function App() {
   const setSidebarVisible = useUIStore((state) => state.setSidebarVisible);
   const editorMode = useUIStore((state) => state.editorMode);
   const toggleEditorMode = useUIStore((state) => state.toggleEditorMode);
+  const readOnly = useUIStore((state) => state.readOnly);
   const osPlatform = useUIStore((state) => state.osPlatform);
   const setFindBarVisible = useUIStore((state) => state.setFindBarVisible);
   const setSidebarTab = useUIStore((state) => state.setSidebarTab);
@@ -47,7 +50,10 @@ function App() {
     format: string;
     state: 'loading' | 'success' | 'error';
     message?: string;
+    warnings?: string[];
   } | null>(null);
+  const [pdfDialogOpen, setPdfDialogOpen] = useState(false);
+  const [pdfOptions, setPdfOptions] = useState<PdfOptions>(DEFAULT_PDF_OPTIONS);
 
   // Initialize platform detection early (before first render ideally)
   usePlatformInitialization();
@@ -83,7 +89,7 @@ function App() {
   }, [initializeTheme]);
 
   // Sync language with i18n and Rust backend
-  // Ensures consistent language code format (always 'en' or 'zh', never 'en-US', 'zh-TW', etc.)
+  // Ensures consistent language code format (always 'en', 'zh' or 'ja', never 'en-US', 'zh-TW', etc.)
   // Persists user language preference to backend storage
   // Note: Native menu language is handled directly in Rust event handler
   useEffect(() => {
@@ -101,6 +107,77 @@ function App() {
     }
   }, [language, i18n]);
 
+  // Tell the backend about unsaved changes so closing the window asks first
+  const hasUnsavedChanges = documents.some((d) => d.isDirty);
+  useEffect(() => {
+    void invoke('set_document_dirty', { dirty: hasUnsavedChanges });
+  }, [hasUnsavedChanges]);
+
+  // Save every document with unsaved changes, asking where to put untitled ones.
+  // Resolves to false when a save failed or the user cancelled the save dialog.
+  const saveAllDocuments = useCallback(async () => {
+    const { activeDocumentId: docId, updateContent } = useDocumentStore.getState();
+    // Flush editor content to store, bypassing the 500 ms debounce
+    const editorInstance = useEditorStore.getState().editor;
+    if (docId && editorInstance) {
+      updateContent(docId, (editorInstance.storage['markdown'] as { getMarkdown: () => string }).getMarkdown());
+    }
+
+    const dirty = useDocumentStore.getState().documents.filter((d) => d.isDirty);
+    for (const doc of dirty) {
+      try {
+        if (!doc.path) {
+          const filePath = await save({
+            defaultPath: 'untitled.md',
+            filters: [{ name: 'Markdown', extensions: ['md', 'markdown'] }],
+          });
+          if (!filePath) return false;
+          useDocumentStore.setState((state) => ({
+            documents: state.documents.map((d) => (d.id === doc.id ? { ...d, path: filePath } : d)),
+          }));
+        }
+        await useDocumentStore.getState().saveDocument(doc.id);
+      } catch (error) {
+        console.error('Save failed:', error);
+        return false;
+      }
+    }
+    return true;
+  }, []);
+
+  // Closing a window or quitting with unsaved changes; the payload is true when quitting
+  useEffect(() => {
+    const unlisten = listen<boolean>('confirm-close', async (event) => {
+      const saveLabel = t('common.save');
+      const discardLabel = t('common.discard_changes');
+      const choice = await message(t('common.unsaved_changes'), {
+        title: t('common.markdown_editor'),
+        kind: 'warning',
+        buttons: { yes: saveLabel, no: discardLabel, cancel: t('common.cancel') },
+      });
+      // Custom buttons report their label; older dialog plugins report the button kind
+      const saving = choice === saveLabel || choice === 'Yes';
+      const discarding = choice === discardLabel || choice === 'No';
+      if (!saving && !discarding) return;
+      if (saving && !(await saveAllDocuments())) return;
+
+      await invoke('set_document_dirty', { dirty: false });
+      if (event.payload) {
+        await invoke('quit_app');
+      } else {
+        await getCurrentWindow().close();
+      }
+    });
+    return () => {
+      void unlisten.then((stop) => stop());
+    };
+  }, [t, saveAllDocuments]);
+
+  // Lock the WYSIWYG editor while View > Read Only is on
+  useEffect(() => {
+    editor?.setEditable(!readOnly);
+  }, [editor, readOnly]);
+
   // Sync editor mode to native menu
   useEffect(() => {
     void invoke('update_menu_item_state', {
@@ -109,6 +186,33 @@ function App() {
     });
   }, [editorMode]);
 
+  // Sync formatting at the cursor to the Format menu checkmarks
+  useEffect(() => {
+    if (!editor) return;
+
+    const syncFormatState = () => {
+      void invoke('update_format_state', {
+        states: {
+          editor_bold: editor.isActive('bold'),
+          editor_italic: editor.isActive('italic'),
+          editor_strike: editor.isActive('strike'),
+          editor_inline_code: editor.isActive('code'),
+          editor_bullet_list: editor.isActive('bulletList'),
+          editor_ordered_list: editor.isActive('orderedList'),
+          editor_blockquote: editor.isActive('blockquote'),
+        },
+      });
+    };
+
+    syncFormatState();
+    editor.on('selectionUpdate', syncFormatState);
+    editor.on('update', syncFormatState);
+    return () => {
+      editor.off('selectionUpdate', syncFormatState);
+      editor.off('update', syncFormatState);
+    };
+  }, [editor]);
+
   // Load any pending files requested by the OS (file association) and listen for new ones.
   useEffect(() => {
     let isActive = true;
@@ -116,7 +220,7 @@ function App() {
 
     const setupFileHandling = async () => {
       try {
-        // 1. First set up the listener for future events (e.g. via single-instance)
+        // 1. First set up the listener (startup files and later ones via single-instance)
         const stop = await listen<string>('open-file', (event) => {
           if (event.payload) {
             console.log('📬 Received open-file event:', event.payload);
@@ -124,28 +228,29 @@ function App() {
           }
         });
         
+        // Relaunching with a file that's already open switches to its tab
+        const stopFocus = await listen<string>('focus-file', (event) => {
+          const { documents, setActiveDocument } = useDocumentStore.getState();
+          const existing = documents.find((d) => d.path === event.payload);
+          if (existing) {
+            setActiveDocument(existing.id);
+          } else if (event.payload) {
+            void loadDocument(event.payload);
+          }
+        });
+
         if (!isActive) {
           stop();
+          stopFocus();
           return;
         }
-        unlisten = stop;
-
-        // 2. Then check for any files that arrived during startup
-        const pending = await invoke<string[]>('take_pending_open_files');
-        if (!isActive) return;
-
-        if (pending.length > 0) {
-          console.log('📥 Loading pending files:', pending);
-          await Promise.all(
-            pending.map(async (path) => {
-              try {
-                await loadDocument(path);
-              } catch (error) {
-                console.warn('Failed to load pending file:', path, error);
-              }
-            })
-          );
-        }
+        unlisten = () => {
+          stop();
+          stopFocus();
+        };
+
+        // 2. Then tell the backend we're listening so it emits files that arrived during startup
+        await invoke('frontend_ready');
       } catch (error) {
         console.warn('Failed to setup file handling:', error);
       }
@@ -244,15 +349,23 @@ function App() {
   const handleImport = useCallback(async (format: string) => {
     const extensionMap: Record<string, string[]> = {
       docx: ['docx'],
-      xlsx: ['xlsx', 'xls', 'ods'],
+      xlsx: ['xlsx', 'xls', 'ods', 'csv'],
       pdf: ['pdf'],
-      pptx: ['pptx', 'ppt'],
+      pptx: ['pptx'],
+      html: ['html', 'htm'],
+      rtf: ['rtf'],
+      odt: ['odt'],
+      any: ['docx', 'xlsx', 'xls', 'ods', 'csv', 'pptx', 'pdf', 'rtf', 'odt'],
     };
     const filterName: Record<string, string> = {
       docx: 'Word Document',
       xlsx: 'Spreadsheet',
       pdf: 'PDF Document',
       pptx: 'PowerPoint Presentation',
+      html: 'Web Page',
+      rtf: 'Rich Text Document',
+      odt: 'OpenDocument Text',
+      any: 'Supported Documents',
     };
 
     try {
@@ -264,9 +377,20 @@ function App() {
       const filePath = Array.isArray(selected) ? selected[0] : selected;
       if (!filePath || typeof filePath !== 'string') return;
 
-      setImportExportStatus({ type: 'import', format, state: 'loading' });
-
-      const markdown = await invoke<string>('import_document', { path: filePath, format });
+      // "any" lets the backend pick the importer from the file extension
+      const detected = format === 'any' ? (filePath.split('.').pop() ?? format).toLowerCase() : format;
+      setImportExportStatus({ type: 'import', format: detected, state: 'loading' });
+
+      let markdown: string;
+      let warnings: string[] = [];
+      if (format === 'any') {
+        ({ markdown, warnings } = await invoke<{ markdown: string; warnings: string[] }>(
+          'import_to_markdown',
+          { path: filePath },
+        ));
+      } else {
+        markdown = await invoke<string>('import_document', { path: filePath, format });
+      }
 
       const importedDoc = {
         id: crypto.randomUUID(),
@@ -280,21 +404,27 @@ function App() {
         activeDocumentId: importedDoc.id,
       }));
 
-      setImportExportStatus({ type: 'import', format, state: 'success' });
-      setTimeout(() => setImportExportStatus(null), 3000);
+      setImportExportStatus({ type: 'import', format: detected, state: 'success', warnings });
+      setTimeout(() => setImportExportStatus(null), warnings.length > 0 ? 8000 : 3000);
     } catch (err) {
       console.error('Import failed:', err);
       setImportExportStatus({
         type: 'import',
         format,
         state: 'error',
-        message: String(err),
+        message: errorMessage(err),
       });
       setTimeout(() => setImportExportStatus(null), 6000);
     }
   }, []);
 
-  const handleExport = useCallback(async (format: string) => {
+  const handleExport = useCallback(async (format: string, options?: PdfOptions) => {
+    // Ask for page layout before picking where to save a PDF
+    if (format === 'pdf' && !options) {
+      setPdfDialogOpen(true);
+      return;
+    }
+
     const doc = useDocumentStore.getState().documents.find(
       (d) => d.id === useDocumentStore.getState().activeDocumentId
     );
@@ -305,12 +435,20 @@ function App() {
       xlsx: 'xlsx',
       pdf: 'pdf',
       pptx: 'pptx',
+      html: 'html',
+      epub: 'epub',
+      odt: 'odt',
+      tex: 'tex',
     };
     const filterName: Record<string, string> = {
       docx: 'Word Document',
       xlsx: 'Spreadsheet',
       pdf: 'PDF Document',
       pptx: 'PowerPoint Presentation',
+      html: 'Web Page',
+      epub: 'E-book',
+      odt: 'OpenDocument Text',
+      tex: 'LaTeX Document',
     };
 
     const baseName = doc.path
@@ -327,7 +465,15 @@ function App() {
 
       setImportExportStatus({ type: 'export', format, state: 'loading' });
 
-      await invoke('export_document', { content: doc.content, path: filePath, format });
+      await invoke('export_document', {
+        content: doc.content,
+        path: filePath,
+        format,
+        pdfOptions: format === 'pdf' ? options : undefined,
+        // The book title falls back to the first heading on the backend
+        epubMeta: format === 'epub' ? { language: i18n.language } : undefined,
+        theme: format === 'html' ? useUIStore.getState().currentTheme : undefined,
+      });
 
       setImportExportStatus({ type: 'export', format, state: 'success' });
       setTimeout(() => setImportExportStatus(null), 3000);
@@ -337,11 +483,11 @@ function App() {
         type: 'export',
         format,
         state: 'error',
-        message: String(err),
+        message: errorMessage(err),
       });
       setTimeout(() => setImportExportStatus(null), 6000);
     }
-  }, []);
+  }, [i18n]);
 
   const handleOpenFile = async () => {
     try {
@@ -423,6 +569,15 @@ function App() {
   }, [handleSaveAs]);
 
   const runEditorCommand = (payload: { command: string; level?: number }) => {
+    // Undo/redo in the source view and text fields use the browser's own history
+    if (payload.command === 'undo' || payload.command === 'redo') {
+      const active = document.activeElement;
+      if (active instanceof HTMLTextAreaElement || active instanceof HTMLInputElement) {
+        document.execCommand(payload.command);
+        return;
+      }
+    }
+
     if (!editor) return;
 
     const chain = editor.chain().focus();
@@ -503,6 +658,9 @@ function App() {
           listen('menu-toggle-editor-mode', () => {
             toggleEditorMode();
           }),
+          listen<boolean>('menu-toggle-readonly', (event) => {
+            useUIStore.getState().setReadOnly(event.payload);
+          }),
           listen<string>('menu-set-theme', (event) => {
             const themeName = event.payload as any;
             const setCurrentTheme = useUIStore.getState().setCurrentTheme;
@@ -520,9 +678,26 @@ function App() {
               runEditorCommand(event.payload);
             }
           ),
+          listen('menu-clean-up-document', async () => {
+            const { documents, activeDocumentId, updateContent } = useDocumentStore.getState();
+            const doc = documents.find((d) => d.id === activeDocumentId);
+            if (!doc) return;
+            try {
+              const formatted = await invoke<string>('format_markdown', { markdown: doc.content });
+              if (formatted !== doc.content) {
+                updateContent(doc.id, formatted);
+              }
+            } catch (error) {
+              console.error('Failed to clean up document:', error);
+            }
+          }),
           listen('menu-find', () => {
             setFindBarVisible(true);
           }),
+          listen('menu-find-replace', () => {
+            useUIStore.getState().setReplaceVisible(true);
+            setFindBarVisible(true);
+          }),
           listen('menu-find-in-files', () => {
             setSidebarVisible(true);
             setSidebarTab('search');
@@ -551,11 +726,25 @@ function App() {
     };
   }, [editor, activeDocumentId, handleSaveAs, handleManualSave, createNewDocument, closeDocument, toggleSidebar, setFindBarVisible, setSidebarVisible, setSidebarTab, handleImport, handleExport]);
 
-  // Enable/disable export menu items based on whether a document is active
+  // Enable/disable Export As items based on whether a document is active.
+  // XLSX export needs tables, so it is re-checked shortly after edits stop.
+  const activeContent = activeDocument?.content;
   useEffect(() => {
-    const ids = ['file_export_docx', 'file_export_xlsx', 'file_export_pdf', 'file_export_pptx'];
-    ids.forEach((id) => void invoke('enable_menu_item', { id, enabled: !!activeDocument }));
-  }, [activeDocument]);
+    const ids = [
+      'file_export_docx', 'file_export_pdf', 'file_export_pptx', 'file_export_html',
+      'file_export_epub', 'file_export_odt', 'file_export_tex',
+    ];
+    ids.forEach((id) => void invoke('enable_menu_item', { id, enabled: activeContent !== undefined }));
+    if (activeContent === undefined) {
+      void invoke('enable_menu_item', { id: 'file_export_xlsx', enabled: false });
+      return;
+    }
+    const timer = setTimeout(async () => {
+      const hasTables = await invoke<boolean>('document_has_tables', { markdown: activeContent });
+      void invoke('enable_menu_item', { id: 'file_export_xlsx', enabled: hasTables });
+    }, 500);
+    return () => clearTimeout(timer);
+  }, [activeContent]);
 
   const titlebarClassName = useMemo(() => {
     if (osPlatform === 'macos') {
@@ -653,6 +842,17 @@ function App() {
           )}
         </div>
       </div>
+      {pdfDialogOpen && (
+        <PdfExportDialog
+          initialOptions={pdfOptions}
+          onCancel={() => setPdfDialogOpen(false)}
+          onConfirm={(options) => {
+            setPdfDialogOpen(false);
+            setPdfOptions(options);
+            void handleExport('pdf', options);
+          }}
+        />
+      )}
       {/* Import/Export status toast */}
       {importExportStatus && (
         <div className={`fixed bottom-4 right-4 z-50 rounded-lg px-4 py-3 text-sm shadow-lg max-w-sm ${
@@ -675,6 +875,11 @@ function App() {
               {importExportStatus.type === 'import'
                 ? t('import_export.import_success')
                 : t('import_export.export_success')}
+              {importExportStatus.warnings?.map((warning) => (
+                <span key={warning} className="block text-xs opacity-80">
+                  {t('import_export.warning')} {warning}
+                </span>
+              ))}
             </span>
           )}
           {importExportStatus.state === 'error' && (
@@ -36,7 +36,8 @@ export const Editor = memo(function Editor({ documentId }: EditorProps) {
   const [hasMeasuredLayout, setHasMeasuredLayout] = useState(false);
   const [searchTerm, setSearchTerm] = useState('');
   const [replaceTerm, setReplaceTerm] = useState('');
-  const [replaceVisible, setReplaceVisible] = useState(false);
+  const replaceVisible = useUIStore((state) => state.replaceVisible);
+  const setReplaceVisible = useUIStore((state) => state.setReplaceVisible);
   const document = documents.find(d => d.id === documentId);
 
   // Create lowlight instance with a smaller default language set
@@ -187,8 +188,8 @@ export const Editor = memo(function Editor({ documentId }: EditorProps) {
   }, [setFindBarVisible, editor]);
 
   const handleToggleReplace = useCallback(() => {
-    setReplaceVisible((v) => !v);
-  }, []);
+    setReplaceVisible(!replaceVisible);
+  }, [replaceVisible, setReplaceVisible]);
 
   if (!document) {
     return (
@@ -32,6 +32,7 @@ export const SourceEditor = ({ documentId }: SourceEditorProps) => {
   const updateContent = useDocumentStore((state) => state.updateContent);
   const fontSize = useUIStore((state) => state.fontSize);
   const fontFamily = useUIStore((state) => state.fontFamily);
+  const readOnly = useUIStore((state) => state.readOnly);
   const findBarVisible = useUIStore((state) => state.findBarVisible);
   const setFindBarVisible = useUIStore((state) => state.setFindBarVisible);
   const containerRef = useRef<HTMLDivElement>(null);
@@ -40,7 +41,8 @@ export const SourceEditor = ({ documentId }: SourceEditorProps) => {
 
   const [searchTerm, setSearchTerm] = useState('');
   const [replaceTerm, setReplaceTerm] = useState('');
-  const [replaceVisible, setReplaceVisible] = useState(false);
+  const replaceVisible = useUIStore((state) => state.replaceVisible);
+  const setReplaceVisible = useUIStore((state) => state.setReplaceVisible);
   const [currentIndex, setCurrentIndex] = useState(0);
 
   const doc = documents.find((d) => d.id === documentId);
@@ -154,7 +156,7 @@ export const SourceEditor = ({ documentId }: SourceEditorProps) => {
           onReplace={handleReplace}
           onReplaceAll={handleReplaceAll}
           onClose={handleCloseFindBar}
-          onToggleReplace={() => setReplaceVisible((v) => !v)}
+          onToggleReplace={() => setReplaceVisible(!replaceVisible)}
         />
       )}
       <div
@@ -170,6 +172,7 @@ export const SourceEditor = ({ documentId }: SourceEditorProps) => {
             maxWidth: `${layoutMetrics.contentWidth}px`,
           }}
           onChange={handleChange}
+          readOnly={readOnly}
           spellCheck={false}
           placeholder={t('editor.placeholder')}
         />
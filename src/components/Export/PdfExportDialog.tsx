@@ -0,0 +1,95 @@
+import { useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { Button } from '@/components/ui/button';
+
+export interface PdfOptions {
+  page_size: 'a4' | 'letter' | 'legal';
+  margin_mm: number;
+  font_size: number;
+}
+
+export const DEFAULT_PDF_OPTIONS: PdfOptions = {
+  page_size: 'a4',
+  margin_mm: 8,
+  font_size: 8,
+};
+
+interface PdfExportDialogProps {
+  initialOptions: PdfOptions;
+  onConfirm: (options: PdfOptions) => void;
+  onCancel: () => void;
+}
+
+export function PdfExportDialog({ initialOptions, onConfirm, onCancel }: PdfExportDialogProps) {
+  const { t } = useTranslation();
+  const [options, setOptions] = useState<PdfOptions>(initialOptions);
+
+  const inputClass =
+    'h-8 w-full rounded-md border border-input bg-background px-2 text-sm focus:outline-none focus:ring-1 focus:ring-ring';
+
+  return (
+    <div
+      className="fixed inset-0 z-50 flex items-center justify-center bg-black/30"
+      onKeyDown={(e) => {
+        if (e.key === 'Escape') onCancel();
+      }}
+    >
+      <form
+        className="w-80 rounded-lg border border-border bg-background p-4 shadow-lg"
+        onSubmit={(e) => {
+          e.preventDefault();
+          onConfirm(options);
+        }}
+      >
+        <h2 className="mb-3 text-sm font-semibold">{t('pdf_export.title')}</h2>
+        <div className="flex flex-col gap-3 text-sm">
+          <label className="flex flex-col gap-1">
+            {t('pdf_export.page_size')}
+            <select
+              className={inputClass}
+              value={options.page_size}
+              onChange={(e) => setOptions({ ...options, page_size: e.target.value as PdfOptions['page_size'] })}
+              autoFocus
+            >
+              <option value="a4">A4</option>
+              <option value="letter">Letter</option>
+              <option value="legal">Legal</option>
+            </select>
+          </label>
+          <label className="flex flex-col gap-1">
+            {t('pdf_export.margin_mm')}
+            <input
+              type="number"
+              className={inputClass}
+              min={0}
+              max={50}
+              step={1}
+              value={options.margin_mm}
+              onChange={(e) => setOptions({ ...options, margin_mm: Number(e.target.value) })}
+            />
+          </label>
+          <label className="flex flex-col gap-1">
+            {t('pdf_export.font_size')}
+            <input
+              type="number"
+              className={inputClass}
+              min={4}
+              max={72}
+              step={1}
+              value={options.font_size}
+              onChange={(e) => setOptions({ ...options, font_size: Number(e.target.value) })}
+            />
+          </label>
+        </div>
+        <div className="mt-4 flex justify-end gap-2">
+          <Button type="button" variant="ghost" size="sm" onClick={onCancel}>
+            {t('pdf_export.cancel')}
+          </Button>
+          <Button type="submit" size="sm">
+            {t('pdf_export.export')}
+          </Button>
+        </div>
+      </form>
+    </div>
+  );
+}
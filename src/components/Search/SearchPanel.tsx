@@ -5,6 +5,7 @@ import { Search, CaseSensitive, Regex, ChevronDown, ChevronRight, FileText, Load
 import { ScrollArea } from '@/components/ui/scroll-area';
 import { useDocumentStore } from '@/stores/documentStore';
 import { useUIStore } from '@/stores/uiStore';
+import { errorMessage } from '@/lib/errors';
 
 interface SearchResult {
   file_path: string;
@@ -89,7 +90,7 @@ export function SearchPanel({ currentDirectory, focusTrigger }: SearchPanelProps
         setGroups(newGroups);
         setSearched(true);
       } catch (e) {
-        setError(String(e));
+        setError(errorMessage(e));
         setResults([]);
         setGroups([]);
       } finally {
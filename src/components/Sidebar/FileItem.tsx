@@ -1,3 +1,6 @@
+import { useEffect, useState } from 'react';
+import { useTranslation } from 'react-i18next';
+import { invoke } from '@tauri-apps/api/core';
 import { File, Folder } from 'lucide-react';
 import { cn } from '@/lib/utils';
 
@@ -10,26 +13,91 @@ interface FileEntry {
 interface FileItemProps {
   file: FileEntry;
   onClick: () => void;
+  // Called after the file was moved to the trash
+  onTrashed?: () => void;
   isActive?: boolean;
 }
 
-export function FileItem({ file, onClick, isActive }: FileItemProps) {
+const menuItemClass = cn(
+  "relative flex cursor-pointer select-none items-center rounded-sm px-2 py-1.5",
+  "text-sm outline-none transition-colors hover:bg-accent hover:text-accent-foreground"
+);
+
+export function FileItem({ file, onClick, onTrashed, isActive }: FileItemProps) {
+  const { t } = useTranslation();
+  const [menuPosition, setMenuPosition] = useState<{ x: number; y: number } | null>(null);
+
+  useEffect(() => {
+    if (!menuPosition) return;
+    const close = () => setMenuPosition(null);
+    document.addEventListener('mousedown', close);
+    window.addEventListener('blur', close);
+    return () => {
+      document.removeEventListener('mousedown', close);
+      window.removeEventListener('blur', close);
+    };
+  }, [menuPosition]);
+
+  const handleReveal = async () => {
+    setMenuPosition(null);
+    try {
+      await invoke('reveal_in_file_manager', { path: file.path });
+    } catch (err) {
+      console.error('Failed to reveal file:', err);
+    }
+  };
+
+  // Deleting from the sidebar goes to the OS trash so a misclick can be undone
+  const handleTrash = async () => {
+    setMenuPosition(null);
+    try {
+      await invoke('trash_file', { path: file.path });
+      onTrashed?.();
+    } catch (err) {
+      console.error('Failed to move file to trash:', err);
+    }
+  };
+
   return (
-    <button
-      onClick={onClick}
-      className={cn(
-        "w-full flex items-center gap-2 px-2 py-1.5 rounded text-sm",
-        "hover:bg-accent transition-colors text-left",
-        isActive && "bg-accent font-medium"
-      )}
-      title={file.path}
-    >
-      {file.is_directory ? (
-        <Folder className="w-4 h-4 flex-shrink-0 text-blue-500" />
-      ) : (
-        <File className="w-4 h-4 flex-shrink-0 text-muted-foreground" />
+    <>
+      <button
+        onClick={onClick}
+        onContextMenu={(e) => {
+          e.preventDefault();
+          setMenuPosition({ x: e.clientX, y: e.clientY });
+        }}
+        className={cn(
+          "w-full flex items-center gap-2 px-2 py-1.5 rounded text-sm",
+          "hover:bg-accent transition-colors text-left",
+          isActive && "bg-accent font-medium"
+        )}
+        title={file.path}
+      >
+        {file.is_directory ? (
+          <Folder className="w-4 h-4 flex-shrink-0 text-blue-500" />
+        ) : (
+          <File className="w-4 h-4 flex-shrink-0 text-muted-foreground" />
+        )}
+        <span className="truncate">{file.name}</span>
+      </button>
+      {menuPosition && (
+        <div
+          className={cn(
+            "fixed z-50 min-w-[8rem] overflow-hidden rounded-md border",
+            "bg-popover p-1 text-popover-foreground shadow-md"
+          )}
+          style={{ left: menuPosition.x, top: menuPosition.y }}
+          // Keep the document listener from closing the menu before the click lands
+          onMouseDown={(e) => e.stopPropagation()}
+        >
+          <div className={menuItemClass} onClick={() => void handleReveal()}>
+            {t('sidebar.reveal_in_file_manager')}
+          </div>
+          <div className={menuItemClass} onClick={() => void handleTrash()}>
+            {t('sidebar.move_to_trash')}
+          </div>
+        </div>
       )}
-      <span className="truncate">{file.name}</span>
-    </button>
+    </>
   );
 }
@@ -1,6 +1,7 @@
-import { memo, useState } from 'react';
+import { memo, useEffect, useState } from 'react';
 import { useTranslation } from 'react-i18next';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { open } from '@tauri-apps/plugin-dialog';
 import { ScrollArea } from '@/components/ui/scroll-area';
 import { Button } from '@/components/ui/button';
@@ -12,10 +13,18 @@ import { FileItem } from './FileItem';
 import { SearchPanel } from '@/components/Search/SearchPanel';
 import { FolderOpen, FilePlus, Home, Files, Search } from 'lucide-react';
 
+interface MenuAction {
+  action: string;
+  arg: string | null;
+}
+
 interface FileEntry {
   name: string;
   path: string;
   is_directory: boolean;
+  size: number;
+  modified: number | null;
+  children?: FileEntry[] | null;
 }
 
 export const Sidebar = memo(function Sidebar() {
@@ -32,7 +41,8 @@ export const Sidebar = memo(function Sidebar() {
   const sidebarTab = useUIStore((state) => state.sidebarTab);
   const setSidebarTab = useUIStore((state) => state.setSidebarTab);
 
-  const loadDirectory = async (path: string) => {
+  // Resolves to whether the folder could be listed
+  const loadDirectory = async (path: string): Promise<boolean> => {
     setLoading(true);
     try {
       const entries = await invoke<FileEntry[]>('list_directory', { path });
@@ -44,13 +54,24 @@ export const Sidebar = memo(function Sidebar() {
       );
       setFiles(filtered);
       setCurrentDirectory(path);
+      return true;
     } catch (error) {
       console.error('Failed to load directory:', error);
+      return false;
     } finally {
       setLoading(false);
     }
   };
 
+  const openFolder = async (path: string) => {
+    if (!(await loadDirectory(path))) return;
+    try {
+      await invoke('add_recent_folder', { path });
+    } catch (error) {
+      console.error('Failed to add recent folder:', error);
+    }
+  };
+
   const handleOpenFolder = async () => {
     try {
       const selected = await open({
@@ -59,13 +80,27 @@ export const Sidebar = memo(function Sidebar() {
       });
       
       if (selected && typeof selected === 'string') {
-        loadDirectory(selected);
+        openFolder(selected);
       }
     } catch (error) {
       console.error('Failed to open folder:', error);
     }
   };
 
+  // File > Open Recent Folder
+  useEffect(() => {
+    const unlisten = listen<MenuAction>('menu-action', (event) => {
+      const { action, arg } = event.payload;
+      if (action === 'open-recent-folder' && arg) {
+        setSidebarTab('files');
+        openFolder(arg);
+      }
+    });
+    return () => {
+      void unlisten.then((stop) => stop());
+    };
+  }, [setSidebarTab]);
+
   const handleFileClick = async (file: FileEntry) => {
     if (file.is_directory) {
       loadDirectory(file.path);
@@ -220,6 +255,7 @@ export const Sidebar = memo(function Sidebar() {
                       key={file.path}
                       file={file}
                       onClick={() => handleFileClick(file)}
+                      onTrashed={() => void loadDirectory(currentDirectory)}
                       isActive={activeDocument?.path === file.path}
                     />
                   ))}
@@ -48,10 +48,10 @@ export function usePlatformInitialization() {
           console.warn('Failed to load user settings from backend:', settingsError);
           // Continue to fallback
           try {
-            const systemLocale = await invoke<string>('get_system_locale');
-            if (isActive && systemLocale) {
-              console.log(`🌍 No user preference, using system locale: ${systemLocale}`);
-              updateSettings({ language: systemLocale });
+            const systemLocale = await invoke<{ language: string; chain: string[] }>('get_system_locale');
+            if (isActive && systemLocale?.language) {
+              console.log(`🌍 No user preference, using system locale: ${systemLocale.language} (${systemLocale.chain.join(' → ')})`);
+              updateSettings({ language: systemLocale.language });
               console.log('✅ Language initialized from system locale');
             }
           } catch (localeError) {
@@ -3,6 +3,7 @@ import { initReactI18next } from 'react-i18next';
 
 import en from './locales/en.json';
 import zh from './locales/zh.json';
+import ja from './locales/ja.json';
 
 i18n
   .use(initReactI18next)
@@ -15,6 +16,9 @@ i18n
       zh: { translation: zh },
       'zh-TW': { translation: zh },
       'zh-HK': { translation: zh },
+      // Japanese (日本語)
+      ja: { translation: ja },
+      'ja-JP': { translation: ja },
     },
     fallbackLng: 'en',
     interpolation: {
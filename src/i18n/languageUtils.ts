@@ -1,14 +1,14 @@
 /**
  * Language utilities for consistent language code handling
  * Standardizes language codes across frontend and backend
- * Supports: English (en) and Traditional Chinese (繁體中文, zh)
+ * Supports: English (en), Traditional Chinese (繁體中文, zh) and Japanese (日本語, ja)
  */
 
-export type SupportedLanguage = 'en' | 'zh';
+export type SupportedLanguage = 'en' | 'zh' | 'ja';
 
 /**
  * Normalize language code to supported format
- * Handles various formats: 'en', 'en-US', 'zh', 'zh-TW', 'zh-HK', etc.
+ * Handles various formats: 'en', 'en-US', 'zh', 'zh-TW', 'zh-HK', 'ja-JP', etc.
  */
 export function normalizeLanguageCode(code: string | null | undefined): SupportedLanguage {
   if (!code) return 'en';
@@ -16,6 +16,7 @@ export function normalizeLanguageCode(code: string | null | undefined): Supporte
   const normalized = code.toLowerCase().split('-')[0];
   
   if (normalized === 'zh') return 'zh';
+  if (normalized === 'ja') return 'ja';
   if (normalized === 'en') return 'en';
   
   // Fallback to English for unsupported languages
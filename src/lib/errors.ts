@@ -0,0 +1,29 @@
+/**
+ * Error returned by Tauri commands: a stable `code` to branch on and a
+ * human-readable `message` for display.
+ */
+export type AppErrorCode =
+  | 'not_found'
+  | 'permission_denied'
+  | 'not_utf8'
+  | 'already_exists'
+  | 'io';
+
+export interface AppError {
+  code: AppErrorCode;
+  message: string;
+}
+
+export function isAppError(error: unknown): error is AppError {
+  return (
+    typeof error === 'object' &&
+    error !== null &&
+    typeof (error as AppError).code === 'string' &&
+    typeof (error as AppError).message === 'string'
+  );
+}
+
+/** Message to show for an error thrown by `invoke` or anything else. */
+export function errorMessage(error: unknown): string {
+  return isAppError(error) ? error.message : String(error);
+}
@@ -7,6 +7,8 @@ export interface Document {
   content: string;
   isDirty: boolean;
   lastSaved: Date | null;
+  // Encoding the file was read in ("UTF-8" unless it was detected otherwise)
+  encoding?: string;
 }
 
 interface DocumentState {
@@ -41,7 +43,17 @@ export const useDocumentStore = create<DocumentState>((set, get) => ({
     }));
   },
 
-  closeDocument: (id) =>
+  closeDocument: (id) => {
+    const closing = get().documents.find(d => d.id === id);
+    if (closing?.path) {
+      const path = closing.path;
+      // Write any autosave still waiting out its delay, then stop watching the
+      // file; unwatching also forgets the line ending the autosave writes with
+      void invoke('flush_autosaves', { path })
+        .catch((err) => console.warn('Failed to flush autosave:', err))
+        .finally(() => invoke('unwatch_file', { path }));
+    }
+
     set((state) => {
       const remainingDocs = state.documents.filter(d => d.id !== id);
       let newActiveId = state.activeDocumentId;
@@ -61,7 +73,8 @@ export const useDocumentStore = create<DocumentState>((set, get) => ({
         documents: remainingDocs,
         activeDocumentId: newActiveId,
       };
-    }),
+    });
+  },
 
   setActiveDocument: (id) => set({ activeDocumentId: id }),
 
@@ -82,6 +95,7 @@ export const useDocumentStore = create<DocumentState>((set, get) => ({
       await invoke('save_markdown_file', {
         path: doc.path,
         content: doc.content,
+        encoding: doc.encoding,
       });
       
       // Add to recent files
@@ -102,7 +116,7 @@ export const useDocumentStore = create<DocumentState>((set, get) => ({
 
   loadDocument: async (path) => {
     try {
-      const content = await invoke<string>('read_markdown_file', { path });
+      const { content, encoding } = await invoke<{ content: string; encoding: string }>('read_markdown_file', { path });
       
       const doc: Document = {
         id: crypto.randomUUID(),
@@ -110,10 +124,14 @@ export const useDocumentStore = create<DocumentState>((set, get) => ({
         content,
         isDirty: false,
         lastSaved: new Date(),
+        encoding,
       };
       
       // Add to recent files
       await invoke('add_recent_file', { path });
+
+      // Get notified when another program modifies the file
+      invoke('watch_file', { path }).catch((err) => console.warn('Failed to watch file:', err));
       
       get().openDocument(doc);
     } catch (error) {
@@ -1,5 +1,6 @@
 import { create } from 'zustand';
 import { persist, PersistOptions } from 'zustand/middleware';
+import { invoke } from '@tauri-apps/api/core';
 import { ThemeName, THEME_NAMES } from '@/theme/types';
 import { applyTheme } from '@/theme/utils';
 
@@ -13,6 +14,8 @@ interface UIState {
   editorMode: 'wysiwyg' | 'source';
   sidebarTab: 'files' | 'search';
   findBarVisible: boolean;
+  replaceVisible: boolean;
+  readOnly: boolean;
   // Actions
   setCurrentTheme: (theme: ThemeName) => void;
   toggleTheme: () => void;
@@ -28,6 +31,8 @@ interface UIState {
   setSidebarTab: (tab: 'files' | 'search') => void;
   setFindBarVisible: (visible: boolean) => void;
   toggleFindBar: () => void;
+  setReplaceVisible: (visible: boolean) => void;
+  setReadOnly: (readOnly: boolean) => void;
 }
 
 type PersistedUIState = Pick<UIState, 'currentTheme' | 'sidebarVisible' | 'fontSize' | 'fontFamily' | 'sidebarWidth' | 'editorMode' | 'sidebarTab'>;
@@ -43,6 +48,8 @@ export const useUIStore = create<UIState>()(
       editorMode: 'wysiwyg',
       sidebarTab: 'files',
       findBarVisible: false,
+      replaceVisible: false,
+      readOnly: false,
       osPlatform: (() => {
         if (typeof navigator !== 'undefined') {
           if (navigator.userAgent.includes('Macintosh')) return 'macos';
@@ -56,6 +63,8 @@ export const useUIStore = create<UIState>()(
         if (THEME_NAMES[theme]) {
           applyTheme(theme);
           set({ currentTheme: theme });
+          // Keep the backend's saved theme and View > Theme checkmark in sync
+          invoke('set_theme', { theme }).catch((err) => console.warn('Failed to save theme:', err));
         }
       },
 
@@ -119,6 +128,10 @@ export const useUIStore = create<UIState>()(
 
       toggleFindBar: () =>
         set((state) => ({ findBarVisible: !state.findBarVisible })),
+
+      setReplaceVisible: (visible) => set({ replaceVisible: visible }),
+
+      setReadOnly: (readOnly) => set({ readOnly }),
     }),
     {
       name: 'ui-preferences',
@@ -130,7 +143,7 @@ export const useUIStore = create<UIState>()(
         sidebarWidth: state.sidebarWidth,
         editorMode: state.editorMode,
         sidebarTab: state.sidebarTab,
-        // osPlatform and findBarVisible are excluded from persistence
+        // osPlatform, findBarVisible, replaceVisible and readOnly are excluded from persistence
       }),
       onRehydrate: (state: unknown) => {
         // Apply theme after hydration from localStorage